@@ -3,6 +3,8 @@ extern crate tempdir;
 use std::vec::Vec;
 use std::collections::HashMap;
 use std::fmt::Display;
+use std::fs::{self,File};
+use std::io::Write;
 use std::process::Command;
 use std::path::PathBuf;
 
@@ -15,15 +17,41 @@ use tempdir::TempDir;
 ///
 /// Requires imagemagick `convert` tool to be availabe on the path.
 ///
-/// Usage: generate_tiles src dest
+/// Usage: generate_tiles src dest [--symbols] [--palette high-contrast]
+///
+/// For players who can't easily tell the colours apart, `--symbols` marks
+/// each colour with its own shape: on every cell of normal and fused blocks,
+/// in place of the exclamation on breakers, and in the corner of counters.
+/// `--palette high-contrast` uses colours that stay distinct with the common
+/// kinds of colour blindness. With either, dest is made a theme pack, with a
+/// theme.ini listing the tiles, such as assets/themes/symbols.
 fn main() {
-    // http://www.colourlovers.com/palette/1418121/Primal_Dragon
+    let args: Vec<String> = std::env::args().collect();
+    let symbols = args.iter().any(|arg| arg == "--symbols");
+    let palette = args.windows(2)
+        .find(|pair| pair[0] == "--palette")
+        .map(|pair| pair[1].clone());
+
     let mut colors = HashMap::new();
-    colors.insert("red",    (186, 30,   35));
-    colors.insert("yellow", (224, 185,  26));
-    colors.insert("green",  (134, 179,  23));
-    colors.insert("blue",   (28,  143, 144));
-    colors.insert("grey",   (198, 198, 198));
+    match palette.as_ref().map(|name| &name[..]) {
+        None | Some("default") => {
+            // http://www.colourlovers.com/palette/1418121/Primal_Dragon
+            colors.insert("red",    (186, 30,   35));
+            colors.insert("yellow", (224, 185,  26));
+            colors.insert("green",  (134, 179,  23));
+            colors.insert("blue",   (28,  143, 144));
+            colors.insert("grey",   (198, 198, 198));
+        },
+        Some("high-contrast") => {
+            // Okabe and Ito's colour blind safe palette, at full strength.
+            colors.insert("red",    (213, 94,   0));
+            colors.insert("yellow", (240, 228, 66));
+            colors.insert("green",  (0,   158, 115));
+            colors.insert("blue",   (0,   114, 178));
+            colors.insert("grey",   (230, 230, 230));
+        },
+        Some(name) => panic!("Unknown palette {}, expected default or high-contrast", name),
+    }
 
     let width = 32;
     let height = 32;
@@ -41,6 +69,7 @@ fn main() {
     let out = std::env::args().nth(2)
         .expect("Must pass output directory as second argument");
     let out = PathBuf::from(out);
+    fs::create_dir_all(&out).expect("Could not create output directory");
 
     let circle_bumpmap = tmp.join("circle_bumpmap.png");
     let square_bumpmap = tmp.join("square_bumpmap.png");
@@ -73,6 +102,19 @@ fn main() {
         tile.extend(overlay(&square_bumpmap));
         tile.extend(border(c3(darker), &p(width * 3 - 1, height * 3 - 1)));
 
+        // A symbol in the middle of every cell, so that each slice, and the
+        // normal block put together from the corners, has one.
+        if symbols {
+            tile.extend(fill(c3(darker)));
+            for x in 0..3 {
+                for y in 0..3 {
+                    let cx = (x * width) as f32 + (width - 1) as f32 / 2.0;
+                    let cy = (y * height) as f32 + (height - 1) as f32 / 2.0;
+                    tile.extend(symbol(name, cx, cy, width as f32 / 4.0));
+                }
+            }
+        }
+
         convert(tile, &large);
 
         for (suffix, p) in &positions {
@@ -106,8 +148,14 @@ fn main() {
 
         let mut cmd = Vec::new();
         cmd.extend(size(d(width, height)));
-        cmd.push(bg(c3(darker)));
-        cmd.extend(cutout(&src.join("exclamation.png")));
+        if symbols && *name != "grey" {
+            cmd.push(bg("none".to_string()));
+            cmd.extend(fill(c3(darker)));
+            cmd.extend(symbol(name, (width - 1) as f32 / 2.0, (height - 1) as f32 / 2.0, width as f32 / 4.0));
+        } else {
+            cmd.push(bg(c3(darker)));
+            cmd.extend(cutout(&src.join("exclamation.png")));
+        }
         convert(cmd, &exclamation);
 
         let center = p((width - 1) as f32 / 2.0, (height - 1) as f32 / 2.0);
@@ -148,13 +196,67 @@ fn main() {
             cmd.extend(cutout(&number));
             convert(cmd, &color_number);
 
-            // Place number on background
+            // Place number on background, with a small symbol in the
+            // corner.
             let mut cmd = Vec::new();
             cmd.push(background.to_str().unwrap().to_string());
             cmd.extend(over(&color_number));
+            if symbols {
+                let r = width as f32 / 8.0;
+                cmd.extend(fill(c3(*rgb)));
+                cmd.extend(symbol(name, r + 3.0, r + 3.0, r));
+            }
             convert(cmd, &out.join(format!("{}_{}.png", name, n)));
         }
     }
+
+    let high_contrast = palette.as_ref().map_or(false, |name| name == "high-contrast");
+    if symbols || high_contrast {
+        let name = match (symbols, high_contrast) {
+            (true, true) => "High contrast symbols",
+            (true, false) => "Symbols",
+            _ => "High contrast",
+        };
+        write_manifest(name, &out);
+    }
+}
+
+// Shape that tells a colour apart without seeing it, centered on (x, y) and
+// reaching about r from the center.
+fn symbol(color: &str, x: f32, y: f32, r: f32) -> Vec<String> {
+    match color {
+        "red" => circle(&p(x, y), &p(x, y - r)),
+        "yellow" => polygon(&[(x, y - r), (x + r, y + r * 0.8), (x - r, y + r * 0.8)]),
+        "green" => polygon(&[(x, y - r), (x + r, y), (x, y + r), (x - r, y)]),
+        "blue" => {
+            let t = r / 3.0;
+            let mut cmd = rect(&p(x - r, y - t), &p(x + r, y + t));
+            cmd.extend(rect(&p(x - t, y - r), &p(x + t, y + r)));
+            cmd
+        },
+        _ => Vec::new(),
+    }
+}
+
+// Make a directory of tiles a theme pack, standing in for the default
+// tiles of the same names.
+fn write_manifest(name: &str, out: &PathBuf) {
+    let mut images: Vec<String> = fs::read_dir(out)
+        .expect("Could not list output directory")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "png"))
+        .map(|path| path.file_name().unwrap().to_str().unwrap().to_string())
+        .collect();
+    images.sort();
+
+    let mut manifest = format!("name = {}\n\n[images]\n", name);
+    for image in images {
+        manifest.push_str(&format!("{} = {}\n", &image[..image.len() - 4], image));
+    }
+
+    File::create(out.join("theme.ini"))
+        .and_then(|mut f| f.write_all(manifest.as_bytes()))
+        .expect("Could not write theme.ini");
 }
 
 fn size(arg: String) -> Vec<String> {
@@ -180,6 +282,11 @@ fn rect(tl: &String, tr: &String) -> Vec<String> {
     vec!("-draw".to_string(), format!("rectangle {} {}", tl, tr))
 }
 
+fn polygon(points: &[(f32, f32)]) -> Vec<String> {
+    let points: Vec<String> = points.iter().map(|&(x, y)| p(x, y)).collect();
+    vec!("-draw".to_string(), format!("polygon {}", points.join(" ")))
+}
+
 fn overlay(path: &PathBuf) -> Vec<String> {
     vec!(
         "-compose".to_string(),
@@ -0,0 +1,65 @@
+extern crate puzzlefighter;
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use puzzlefighter::lobby::*;
+
+const DEFAULT_BEST_OF: u32 = 3;
+
+// Value following a command line flag, such as `--port 7700`.
+fn arg(flag: &str) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+
+    args.windows(2)
+        .find(|pair| pair[0] == flag)
+        .map(|pair| pair[1].clone())
+}
+
+/// Pairs up players for online matches and keeps their results.
+///
+/// Usage: lobby [--port N] [--best-of N] [--results FILE]
+fn main() {
+    let port = match arg("--port").map(|value| value.parse::<u16>()) {
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            println!("--port must be a port number");
+            process::exit(1);
+        },
+        None => DEFAULT_LOBBY_PORT,
+    };
+    let best_of = match arg("--best-of").map(|value| value.parse::<u32>()) {
+        Some(Ok(n)) if n % 2 == 1 => n,
+        Some(_) => {
+            println!("--best-of must be an odd number");
+            process::exit(1);
+        },
+        None => DEFAULT_BEST_OF,
+    };
+    let results = PathBuf::from(arg("--results").unwrap_or("lobby-results.txt".to_string()));
+
+    let mut server = match Server::listen(port, best_of, Some(results.clone())) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("{}", e);
+            process::exit(1);
+        },
+    };
+    println!("Lobby listening on port {}, results in {}", server.port(), results.display());
+
+    loop {
+        match server.poll() {
+            Ok(finished) => {
+                for result in finished {
+                    println!("Match {}", result.to_line());
+                }
+            },
+            Err(e) => println!("{}", e),
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
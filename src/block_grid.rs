@@ -1,6 +1,7 @@
 use values::*;
 use std::collections::{LinkedList,HashMap};
 
+#[derive(Clone)]
 pub struct BlockGrid {
     cells: Vec<Vec<Option<PositionedBlock>>>,
 }
@@ -151,16 +152,24 @@ impl BlockGrid {
         list
     }
 
-    pub fn age(&mut self) {
+    /// Bring every counter gem a turn closer to being a normal gem. Returns
+    /// those that changed, as they are now.
+    pub fn age(&mut self) -> Vec<PositionedBlock> {
+        let mut aged = Vec::new();
+
         for row in self.cells.iter_mut() {
             for cell in row.iter_mut() {
                 match *cell {
-                    Some(x) => {
-                        *cell = Some(x.do_age())
+                    Some(x) if x.age() > 0 => {
+                        let block = x.do_age();
+                        *cell = Some(block);
+                        aged.push(block);
                     },
-                    None => {}
+                    _ => {}
                 }
             }
         }
+
+        aged
     }
 }
@@ -1,8 +1,14 @@
+extern crate rand;
+
 use values::*;
 use block_grid::*;
 use board_renderer::RenderState;
 
-use std::collections::LinkedList;
+use self::rand::{SeedableRng,XorShiftRng};
+use std::cmp::max;
+use std::collections::{LinkedList,VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash,Hasher};
 
 #[derive(Copy,Clone,Debug)]
 enum Phase {
@@ -10,10 +16,12 @@ enum Phase {
     PieceFalling,
     AgeAndAttack,
     Settling(u32, bool),
+    GameOver,
 }
 
 type StrikePattern = BlockGrid;
 
+#[derive(Clone)]
 struct Attack {
     strike_pattern: StrikePattern,
     sprinkles: u32,
@@ -59,6 +67,14 @@ impl Attack {
 
 const MAX_FLOOR_KICKS: u8 = 1;
 
+// Column that new pieces appear in.
+const SPAWN_COLUMN: i8 = 3;
+
+/// Pieces known ahead of time, counting the next piece, unless a player
+/// asks for another number.
+pub const DEFAULT_PREVIEW: usize = 3;
+
+#[derive(Clone)]
 pub struct Board {
     // Public
     dimensions: Dimension,
@@ -74,22 +90,54 @@ pub struct Board {
     // in a single attack when board is settled.
     strength: u32,
 
+    // Dispatched attack strength not yet collected by take_attack.
+    outgoing: u32,
+
     // Toggles each attack, alternate which sides sprinkles fall from.
     attack_from_left: bool,
 
     // Time since last block step.
     step_accumulator: f64,
 
-    // Seconds between block steps.
-    speed: f64,
+    // Seconds between block steps when not in turbo.
+    step_interval: f64,
+
+    turbo: bool,
+
+    // Seconds between block steps in turbo, at most.
+    turbo_interval: f64,
+
+    // When off, pieces only fall in turbo.
+    gravity: bool,
 
     // Currently and next falling pieces
     current_piece: Option<Piece>,
     next_piece: Option<Piece>,
 
+    // Pieces after the next one, drawn early so they can be previewed.
+    upcoming: VecDeque<Piece>,
+    // How many pieces to know ahead, counting the next piece.
+    preview: usize,
+
+    // Fixed order of pieces to play, rather than random ones. Once it is
+    // exhausted there is no next piece and the board waits.
+    sequence: Option<LinkedList<Piece>>,
+
+    // Generator for random pieces once seeded, so that boards with the same
+    // seed get the same pieces.
+    rng: Option<XorShiftRng>,
+
     // Current update phase
     phase: Phase,
 
+    stats: Stats,
+
+    // Snapshots taken as each piece locks, most recent last, and those
+    // undone since the last lock.
+    history: LinkedList<Snapshot>,
+    future: LinkedList<Snapshot>,
+    history_limit: usize,
+
     pub events: LinkedList<BlockEvent>,
 }
 
@@ -97,6 +145,43 @@ const SLOW_SPEED: f64 = 0.8;
 const DROP_WAIT: f64 = 0.05;
 const TURBO_SPEED: f64 = 0.05;
 
+// Points for each block destroyed, before the combo multiplier.
+const BLOCK_SCORE: u32 = 10;
+
+/// Running totals for a board, for scoring and end of game summaries.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    pub score: u32,
+    pub pieces: u32,
+    pub blocks_broken: u32,
+    pub attack_sent: u32,
+    pub best_chain: u32,
+    // Most attack strength sent from a single piece, over its whole combo.
+    pub best_combo: u32,
+    // Chain depth and strength of the most recent combo that broke blocks.
+    pub last_chain: u32,
+    pub last_combo: u32,
+    // Area of the largest fused block broken.
+    pub largest_gem: u32,
+}
+
+// Everything needed to put a board back to an earlier state. Animation
+// timing isn't kept.
+#[derive(Clone)]
+struct Snapshot {
+    grid: BlockGrid,
+    current_piece: Option<Piece>,
+    next_piece: Option<Piece>,
+    upcoming: VecDeque<Piece>,
+    sequence: Option<LinkedList<Piece>>,
+    rng: Option<XorShiftRng>,
+    attacks: LinkedList<Attack>,
+    strength: u32,
+    attack_from_left: bool,
+    phase: Phase,
+    stats: Stats,
+}
+
 impl Board {
     pub fn new(dimensions: Dimension) -> Self {
 
@@ -104,13 +189,25 @@ impl Board {
             dimensions: dimensions,
 
             step_accumulator: 0.0,
-            speed: SLOW_SPEED,
+            step_interval: SLOW_SPEED,
+            turbo: false,
+            turbo_interval: TURBO_SPEED,
+            gravity: true,
             current_piece: None,
             next_piece: None,
+            upcoming: VecDeque::new(),
+            preview: DEFAULT_PREVIEW,
+            sequence: None,
+            rng: None,
             attacks: LinkedList::new(),
             strength: 0,
+            outgoing: 0,
             attack_from_left: false,
             phase: Phase::NewPiece,
+            stats: Stats::default(),
+            history: LinkedList::new(),
+            future: LinkedList::new(),
+            history_limit: 0,
 
             grid: BlockGrid::new(dimensions),
             events: LinkedList::new(),
@@ -126,12 +223,42 @@ impl Board {
 
     pub fn next_piece(&self) -> Option<Piece> { self.next_piece }
     pub fn current_piece(&self) -> Option<Piece> { self.current_piece }
+    pub fn stats(&self) -> Stats { self.stats }
+    pub fn dimensions(&self) -> Dimension { self.dimensions }
+
+    /// True once there is no room left to drop a new piece. A board that is
+    /// game over no longer updates.
+    pub fn is_game_over(&self) -> bool {
+        match self.phase {
+            Phase::GameOver => true,
+            _ => false,
+        }
+    }
 
     #[allow(dead_code)]
     pub fn _dead_code(&self) {
         self.debug()
     }
 
+    /// Whether add_blocks can take these lines: they fit on the board and
+    /// have only spaces and block characters.
+    pub fn check_blocks(&self, lines: &[String]) -> Result<(), String> {
+        if lines.len() > self.dimensions.h() as usize {
+            return Err(format!("{} rows is more than the board's {}", lines.len(), self.dimensions.h()));
+        }
+
+        for line in lines {
+            if line.chars().count() > self.dimensions.w() as usize {
+                return Err(format!("`{}` is wider than the board's {} columns", line, self.dimensions.w()));
+            }
+            if let Some(c) = line.chars().find(|&c| c != ' ' && Block::from_char(c).is_none()) {
+                return Err(format!("Unknown block `{}` in `{}`", c, line));
+            }
+        }
+
+        Ok(())
+    }
+
     // Helper method for testing. Provides a string syntax for specifying a
     // board. Capital first letter of color makes a block, lower case makes a
     // breaker.
@@ -142,19 +269,8 @@ impl Board {
             let ref line = lines[y];
             for c in line.chars() {
                 let y = (height - y - 1) as i8;
-                let block = match c {
-                    'R' => Some(Block::new(Color::Red, false)),
-                    'G' => Some(Block::new(Color::Green, false)),
-                    'B' => Some(Block::new(Color::Blue, false)),
-                    'Y' => Some(Block::new(Color::Yellow, false)),
-                    'r' => Some(Block::new(Color::Red, true)),
-                    'g' => Some(Block::new(Color::Green, true)),
-                    'b' => Some(Block::new(Color::Blue, true)),
-                    'y' => Some(Block::new(Color::Yellow, true)),
-                    _   => None
-                };
 
-                if let Some(block) = block {
+                if let Some(block) = Block::from_char(c) {
                     let position = GridPosition::new(x, y);
                     self.grid.set(PositionedBlock::new(block, position));
                 }
@@ -192,7 +308,140 @@ impl Board {
     }
 
     pub fn generate_next_piece(&mut self) {
-        self.set_next_piece(Piece::rand(0, 0));
+        self.next_piece = match self.upcoming.pop_front() {
+            Some(piece) => Some(piece),
+            None => self.draw_piece(),
+        };
+        self.fill_preview();
+    }
+
+    // The piece after all those already drawn.
+    fn draw_piece(&mut self) -> Option<Piece> {
+        match self.sequence {
+            Some(ref mut sequence) => sequence.pop_front(),
+            None => match self.rng {
+                Some(ref mut rng) => Some(Piece::rand_with(0, 0, rng)),
+                None => Some(Piece::rand(0, 0)),
+            },
+        }
+    }
+
+    fn fill_preview(&mut self) {
+        if self.next_piece.is_none() {
+            return;
+        }
+
+        while self.upcoming.len() + 1 < self.preview {
+            match self.draw_piece() {
+                Some(piece) => self.upcoming.push_back(piece),
+                None => break,
+            }
+        }
+    }
+
+    /// Know this many pieces ahead, counting the next piece, so they can be
+    /// previewed. Pieces come in the same order whatever the preview.
+    pub fn set_preview(&mut self, count: usize) {
+        self.preview = max(count, 1);
+        self.fill_preview();
+    }
+
+    /// The next piece and those after it, as many as are previewed.
+    pub fn upcoming(&self) -> Vec<Piece> {
+        self.next_piece.into_iter()
+            .chain(self.upcoming.iter().cloned())
+            .take(self.preview)
+            .collect()
+    }
+
+    /// Draw random pieces from a generator with this seed, starting with the
+    /// next piece. Boards seeded alike get the same pieces.
+    pub fn set_seed(&mut self, seed: u64) {
+        // The generator needs a seed that isn't all zeroes.
+        let seed = [seed as u32, (seed >> 32) as u32, 0x9E37_79B9, 0x7F4A_7C15];
+
+        self.rng = Some(XorShiftRng::from_seed(seed));
+        self.upcoming.clear();
+        self.generate_next_piece();
+    }
+
+    /// A hash of everything that decides how the game plays out, to check
+    /// that two copies of a board are still the same. Block ids are only for
+    /// rendering and are left out.
+    pub fn checksum(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for block in self.grid.blocks() {
+            block.position().hash(&mut hasher);
+            block.color().hash(&mut hasher);
+            block.breaker().hash(&mut hasher);
+            block.age().hash(&mut hasher);
+            block.borders().bits().hash(&mut hasher);
+        }
+
+        // Pieces further ahead are left out: they are the same whatever the
+        // preview, but are drawn earlier for longer ones.
+        for piece in vec!(self.current_piece, self.next_piece) {
+            if let Some(piece) = piece {
+                piece.position.hash(&mut hasher);
+                piece.direction.hash(&mut hasher);
+                for block in piece.blocks.iter() {
+                    block.color.hash(&mut hasher);
+                    block.breaker().hash(&mut hasher);
+                }
+            }
+        }
+
+        format!("{:?} {:?}", self.phase, self.step_accumulator).hash(&mut hasher);
+        for attack in &self.attacks {
+            attack.sprinkles.hash(&mut hasher);
+        }
+        self.strength.hash(&mut hasher);
+        self.outgoing.hash(&mut hasher);
+        self.attack_from_left.hash(&mut hasher);
+        self.turbo.hash(&mut hasher);
+        self.stats.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Play the given pieces in order instead of random ones, starting with
+    /// the next piece.
+    pub fn set_piece_sequence(&mut self, pieces: Vec<Piece>) {
+        self.sequence = Some(pieces.into_iter().collect());
+        self.upcoming.clear();
+        self.generate_next_piece();
+    }
+
+    /// Pieces left to play from a fixed sequence, including the next piece.
+    /// None when pieces are random.
+    pub fn pieces_remaining(&self) -> Option<usize> {
+        self.sequence.as_ref().map(|sequence| {
+            sequence.len() + self.upcoming.len() + if self.next_piece.is_some() { 1 } else { 0 }
+        })
+    }
+
+    /// True when the board is settled and ready for a piece but the fixed
+    /// sequence has run out.
+    pub fn is_out_of_pieces(&self) -> bool {
+        match self.phase {
+            Phase::NewPiece => self.next_piece.is_none(),
+            _ => false,
+        }
+    }
+
+    /// True when the board is settled and about to drop a new piece.
+    pub fn is_waiting_for_piece(&self) -> bool {
+        match self.phase {
+            Phase::NewPiece => true,
+            _ => false,
+        }
+    }
+
+    fn age_blocks(&mut self) {
+        for block in self.grid.age() {
+            self.emit(BlockEvent::Age(block));
+        }
     }
 
     fn emit(&mut self, event: BlockEvent) {
@@ -205,11 +454,62 @@ impl Board {
         list
     }
 
-    pub fn update(&mut self, dt: f64, enemy: &mut Board, render_state: &RenderState) {
+    /// Attack built up by the combo being resolved, not yet sent.
+    pub fn pending_attack(&self) -> u32 {
+        self.strength
+    }
+
+    /// Attack sent by an opponent that has yet to drop onto the board.
+    pub fn incoming_attack(&self) -> u32 {
+        self.attacks.iter().map(|attack| attack.sprinkles).sum()
+    }
+
+    /// How many times blocks have broken since the last piece landed, while
+    /// the board is still settling. Zero otherwise.
+    pub fn chain(&self) -> u32 {
+        match self.phase {
+            Phase::Settling(depth, _) => depth,
+            _ => 0,
+        }
+    }
+
+    /// Collect attack strength sent since the last call, to be passed on to
+    /// an opponent with attack.
+    pub fn take_attack(&mut self) -> u32 {
+        let strength = self.outgoing;
+        self.outgoing = 0;
+        strength
+    }
+
+    /// Seconds between steps of a falling piece, when not in turbo.
+    pub fn set_step_interval(&mut self, interval: f64) {
+        self.step_interval = interval;
+    }
+
+    /// Turn off to leave the falling piece where it is until turbo is held.
+    pub fn set_gravity(&mut self, enabled: bool) {
+        self.gravity = enabled;
+    }
+
+    /// How many times faster than the starting speed pieces fall in turbo.
+    pub fn set_soft_drop(&mut self, factor: f64) {
+        self.turbo_interval = SLOW_SPEED / factor;
+    }
+
+    // Seconds between block steps.
+    fn speed(&self) -> f64 {
+        if self.turbo {
+            self.turbo_interval.min(self.step_interval)
+        } else {
+            self.step_interval
+        }
+    }
+
+    pub fn update(&mut self, dt: f64, render_state: &RenderState) {
         match self.phase {
             Phase::AgeAndAttack => {
                 // Age everything
-                self.grid.age();
+                self.age_blocks();
 
                 // Apply attack
                 if let Some(attack) = self.attacks.pop_front() {
@@ -229,9 +529,17 @@ impl Board {
 
             // TODO: Is a noop phase really a phase? Probably not.
             Phase::NewPiece => {
-                let piece = self.next_piece.unwrap().dup_to(
-                    GridPosition::new(3, self.dimensions.h() as i8),
-                    Direction::Up);
+                if self.is_topped_out() {
+                    self.phase = Phase::GameOver;
+                    return;
+                }
+
+                let piece = match self.next_piece {
+                    Some(piece) => piece.dup_to(
+                        GridPosition::new(SPAWN_COLUMN, self.dimensions.h() as i8),
+                        Direction::Up),
+                    None => return,
+                };
 
                 self.set_current_piece(piece);
                 self.generate_next_piece();
@@ -239,9 +547,11 @@ impl Board {
                 self.phase = Phase::PieceFalling;
             },
             Phase::PieceFalling => {
-                self.step_accumulator += dt;
+                if self.gravity || self.turbo {
+                    self.step_accumulator += dt;
+                }
 
-                if self.step_accumulator > self.speed {
+                if self.step_accumulator > self.speed() {
                     let mut step = false;
 
                     if self.move_piece(|current| current.offset(Direction::Down) ) {
@@ -249,13 +559,7 @@ impl Board {
                     } else {
                         if self.step_accumulator > DROP_WAIT {
                             if let Some(piece) = self.current_piece {
-                                for pb in piece.blocks().iter() {
-                                    let bottom = self.grid.bottom(*pb);
-                                    let resting = pb.drop(pb.y() - bottom.y());
-                                    self.grid.set(resting);
-                                    self.emit(BlockEvent::Drop(*pb, resting));
-                                }
-                                self.current_piece = None;
+                                self.lock_piece(piece);
                                 self.phase = Phase::Settling(0, true);
                                 step = true;
                             }
@@ -263,7 +567,7 @@ impl Board {
                     }
 
                     if step {
-                        self.step_accumulator -= SLOW_SPEED;
+                        self.step_accumulator -= self.step_interval;
                         if self.step_accumulator < 0.0 {
                             self.step_accumulator = 0.0;
                         }
@@ -280,8 +584,7 @@ impl Board {
                         if break_depth > 0.0 {
                             self.phase = Phase::Settling(combo_depth + 1, age_and_attack);
                         } else {
-                            enemy.attack(self.strength);
-                            self.strength = 0;
+                            self.dispatch_attack(combo_depth);
                             self.phase = if age_and_attack {
                                 Phase::AgeAndAttack
                             } else {
@@ -290,8 +593,166 @@ impl Board {
                         }
                     }
                 }
+            },
+            Phase::GameOver => {}
+        }
+    }
+
+    // Drop each block of a piece as far as it will go and fix it in place.
+    fn lock_piece(&mut self, piece: Piece) {
+        if self.history_limit > 0 {
+            // Undoing puts the piece back where it first appeared.
+            let spawned = Piece::new(piece.blocks[0], piece.blocks[1]).dup_to(
+                GridPosition::new(SPAWN_COLUMN, self.dimensions.h() as i8),
+                Direction::Up);
+
+            let mut snapshot = self.snapshot();
+            snapshot.current_piece = Some(spawned);
+            snapshot.phase = Phase::PieceFalling;
+
+            self.history.push_back(snapshot);
+            if self.history.len() > self.history_limit {
+                self.history.pop_front();
             }
+            self.future.clear();
+        }
+
+        for pb in piece.blocks().iter() {
+            let bottom = self.grid.bottom(*pb);
+            let resting = pb.drop(pb.y() - bottom.y());
+            self.grid.set(resting);
+            self.emit(BlockEvent::Drop(*pb, resting));
         }
+        self.current_piece = None;
+        self.stats.pieces += 1;
+    }
+
+    /// Keep up to this many placements around to undo. History is off (zero)
+    /// by default, since every placement copies the grid.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn undo_count(&self) -> usize { self.history.len() }
+    pub fn redo_count(&self) -> usize { self.future.len() }
+
+    /// Put the board back as it was when the last piece locked, with that
+    /// piece back at the top. Emits BlockEvent::Reset. Returns false if
+    /// there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.future.push_back(current);
+                self.restore(snapshot);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Reverse the last undo. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.future.pop_back() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.history.push_back(current);
+                self.restore(snapshot);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            grid: self.grid.clone(),
+            current_piece: self.current_piece,
+            next_piece: self.next_piece,
+            upcoming: self.upcoming.clone(),
+            sequence: self.sequence.clone(),
+            rng: self.rng.clone(),
+            attacks: self.attacks.clone(),
+            strength: self.strength,
+            attack_from_left: self.attack_from_left,
+            phase: self.phase,
+            stats: self.stats,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.grid = snapshot.grid;
+        self.current_piece = snapshot.current_piece;
+        self.next_piece = snapshot.next_piece;
+        self.upcoming = snapshot.upcoming;
+        self.sequence = snapshot.sequence;
+        self.rng = snapshot.rng;
+        self.attacks = snapshot.attacks;
+        self.strength = snapshot.strength;
+        self.attack_from_left = snapshot.attack_from_left;
+        self.phase = snapshot.phase;
+        self.stats = snapshot.stats;
+        self.step_accumulator = 0.0;
+
+        // Pending events describe a board that no longer exists.
+        self.events.clear();
+        self.emit(BlockEvent::Reset);
+    }
+
+    // Send the strength accumulated over a combo once the board settles.
+    fn dispatch_attack(&mut self, chain: u32) {
+        if chain > 0 {
+            self.stats.last_chain = chain;
+            self.stats.last_combo = self.strength;
+        }
+        if self.strength > 0 {
+            self.emit(BlockEvent::Attack(self.strength));
+        }
+        self.outgoing += self.strength;
+        self.stats.attack_sent += self.strength;
+        self.stats.best_combo = max(self.stats.best_combo, self.strength);
+        self.strength = 0;
+    }
+
+    /// Headless equivalent of a piece landing: hard drop the piece where it
+    /// is, then drop, fuse and break blocks until nothing changes, without
+    /// waiting on any animation. For solvers and tests.
+    pub fn play_piece(&mut self, piece: Piece) {
+        self.lock_piece(piece);
+        self.settle();
+        self.age_blocks();
+    }
+
+    /// Resolve the board immediately, running every step of a combo back to
+    /// back. Any resulting attack is dispatched.
+    pub fn settle(&mut self) {
+        let mut combo_depth = 0;
+
+        loop {
+            while self.drop_blocks() {}
+            self.fuse_blocks();
+
+            if self.break_blocks(combo_depth) > 0 {
+                combo_depth += 1;
+            } else {
+                break;
+            }
+        }
+
+        self.dispatch_attack(combo_depth);
+    }
+
+    // The board is lost when the column new pieces drop into is full, or
+    // when blocks have come to rest above the visible area (which can
+    // happen with large attacks).
+    fn is_topped_out(&self) -> bool {
+        let h = self.dimensions.h() as i8;
+
+        self.grid.at(GridPosition::new(SPAWN_COLUMN, h - 1)).is_some() ||
+            self.grid.blocks().iter().any(|block| block.y() >= h)
     }
 
     // Scan the board looking for blocks that should be dropped down to a lower
@@ -557,6 +1018,21 @@ impl Board {
         false
     }
 
+    // Blocks in the power gem a block is part of, or one for a block on
+    // its own.
+    fn gem_size(&self, block: &PositionedBlock) -> u32 {
+        if !block.is_fused() {
+            return 1;
+        }
+
+        let left   = self.grid.find_opposite_corner(block, Direction::Left);
+        let right  = self.grid.find_opposite_corner(block, Direction::Right);
+        let top    = self.grid.find_opposite_corner(block, Direction::Up);
+        let bottom = self.grid.find_opposite_corner(block, Direction::Down);
+
+        ((right.x() - left.x() + 1) as u32) * ((top.y() - bottom.y() + 1) as u32)
+    }
+
     fn break_blocks(&mut self, combo_depth: u32) -> u8 {
         let break_list = self.grid.find_breakers();
 
@@ -581,6 +1057,7 @@ impl Board {
                         let fuse_attack = (w * h) as u32 * fuse_multiplier;
 
                         attack += fuse_attack;
+                        self.stats.largest_gem = max(self.stats.largest_gem, (w * h) as u32);
                     }
                 } else if !block.breaker() {
                     attack += 1
@@ -590,12 +1067,18 @@ impl Board {
             let x = (attack / 2) * (combo_depth + 1);
             self.strength += x;
 
-            // Destroy everything
+            let broken = break_list.len() as u32;
+            self.stats.blocks_broken += broken;
+            self.stats.score += broken * BLOCK_SCORE * (combo_depth + 1);
+            self.stats.best_chain = max(self.stats.best_chain, combo_depth + 1);
+
+            // Destroy everything, sizing gems while they are still there.
             let mut highest_depth = 0;
+            let gems: Vec<u32> = break_list.keys().map(|block| self.gem_size(block)).collect();
 
-            for (block, depth) in &break_list {
+            for ((block, depth), gem) in break_list.iter().zip(gems) {
                 self.grid.clear(block.position());
-                self.emit(BlockEvent::Explode(*block, *depth as u32));
+                self.emit(BlockEvent::Explode(*block, *depth as u32, gem));
 
                 if *depth > highest_depth {
                     highest_depth = *depth;
@@ -607,10 +1090,6 @@ impl Board {
     }
 
     pub fn turbo(&mut self, enable: bool) {
-        if enable {
-            self.speed = TURBO_SPEED;
-        } else {
-            self.speed = SLOW_SPEED;
-        }
+        self.turbo = enable;
     }
 }
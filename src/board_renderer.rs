@@ -3,18 +3,25 @@ extern crate piston_window;
 extern crate gfx;
 extern crate gfx_device_gl;
 extern crate gfx_texture;
-extern crate rand;
 extern crate sprite;
 extern crate ai_behavior;
 
 use values::*;
 use textures::*;
 use board::*;
+use effects::*;
+use font::Font;
+use gravity::*;
+use hud::*;
+use layout::*;
+use projectile::*;
+use match_controller::Side;
 use wrapper_types::*;
 
 use self::uuid::Uuid;
 use self::piston_window::*;
 use self::sprite::*;
+use graphics::{Context,Graphics};
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -26,14 +33,70 @@ use self::ai_behavior::{
     Wait,
 };
 
+// Space between the bottom of the board's frame and the score.
+const HUD_MARGIN: f64 = 8.0;
+
+// Space between pieces in the preview panel.
+const PIECE_GAP: f64 = 8.0;
+
+// Seconds a counter gem's number takes to flip over to the next, and to
+// pulse after.
+const AGE_FLIP: f64 = 0.15;
+const AGE_PULSE: f64 = 0.1;
+
+// Seconds a counter gem takes to grow back in as a normal gem.
+const MATURE_TIME: f64 = 0.35;
+
+// Height of the warning gauge in the frame above the grid, and the rows of
+// incoming blocks that fill it.
+const GAUGE_HEIGHT: f64 = 6.0;
+const GAUGE_ROWS: u32 = 4;
+
+// Attacks in flight, and the gauge they fill.
+const ATTACK_COLOR: [f32; 4] = [1.0, 0.2, 0.1, 1.0];
+
+// Blobs in the streak behind an attack in flight, and seconds between them.
+const TRAIL_LENGTH: usize = 6;
+const TRAIL_SPACING: f64 = 0.02;
+
+// Boards break alike for the same combos.
+const EFFECTS_SEED: u64 = 1;
+
 pub struct BoardRenderer<I: ImageSize, R: gfx::Resources> {
     textures: Rc<Textures<R>>,
     sprites: HashMap<Block, Uuid>,
+    // Top left of the grid, in view units, and window pixels to a view unit.
     position: PixelPosition,
+    scale: f64,
     scene: Scene<I>,
     dimensions: Dimension,
     cell_dimensions: Dimension,
+    // Width of the frame around the grid in the board texture.
+    frame: f64,
+    // Seconds until every falling block has landed, and until every broken
+    // block has started to break.
+    drop_wait: f64,
     break_wait: f64,
+    // How far blocks squash on landing at full speed.
+    squash: f64,
+    paused: bool,
+    hud: Hud,
+    effects: Effects,
+    // Where blocks last broke, from the top left of the grid, attacks sent
+    // since the last update, with where they left from in view units, and
+    // attacks on their way to this board.
+    break_site: PixelPosition,
+    sent: Vec<(PixelPosition, u32)>,
+    projectiles: Vec<Projectile>,
+    // Attack waiting to drop onto the board, counting any still in flight.
+    incoming: u32,
+    hud_font: Font,
+    chain_font: Font,
+    // Which side of the grid upcoming pieces are shown on, how many are
+    // shown, and their sprites.
+    preview_side: Side,
+    previewing: usize,
+    preview_sprites: HashSet<Uuid>,
 }
 
 #[derive(Copy,Clone)]
@@ -43,7 +106,7 @@ struct BlockSprite {
 
 #[derive(Copy,Clone,Debug)]
 pub struct RenderState {
-    dropping: u32,
+    drop_wait: f64,
     break_wait: f64,
 }
 
@@ -55,56 +118,179 @@ macro_rules! delayed_animation {
 
 impl RenderState {
     pub fn is_settled(&self) -> bool {
-        self.dropping == 0 && self.break_wait <= 0.0
+        self.drop_wait <= 0.0 && self.break_wait <= 0.0
     }
 
     pub fn new() -> Self {
         RenderState {
-            dropping: 0,
+            drop_wait: 0.0,
             break_wait: 0.0,
         }
     }
 }
 
 impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources> {
+    /// A board with upcoming pieces shown to its left. The position is the
+    /// top left of its grid, as given by a Layout.
     pub fn new(textures: Rc<Textures<gfx_device_gl::Resources>>,
                position: PixelPosition,
                dimensions: Dimension) -> Self {
+        BoardRenderer::with_preview_on(textures, position, dimensions, Side::Left)
+    }
+
+    /// A board with upcoming pieces shown on the given side, outside the
+    /// board, as for the right player.
+    pub fn with_preview_on(textures: Rc<Textures<gfx_device_gl::Resources>>,
+                           position: PixelPosition,
+                           dimensions: Dimension,
+                           preview_side: Side) -> Self {
 
         // Use an arbitrary block to get the cell dimensions. Assumes they are
         // all the same.
         let texture = textures.get(Block::new(Color::Blue, false).to_texture_name());
         let cell_dimensions = Dimension::from_tuple(texture.get_size());
+        let board = Dimension::from_tuple(textures.get("board".to_string()).get_size());
+        let frame = Layout::new(dimensions, cell_dimensions, board).frame();
 
         BoardRenderer {
             textures: textures,
             sprites: HashMap::new(),
             scene: Scene::new(),
             position: position,
+            scale: 1.0,
             dimensions: dimensions,
             cell_dimensions: cell_dimensions,
+            frame: frame,
+            drop_wait: 0.0,
             break_wait: 0.0,
+            squash: SQUASH,
+            paused: false,
+            hud: Hud::new(),
+            effects: Effects::new(EFFECTS_SEED),
+            break_site: PixelPosition::new(0.0, 0.0),
+            sent: Vec::new(),
+            projectiles: Vec::new(),
+            incoming: 0,
+            hud_font: Font::new(2.0),
+            chain_font: Font::new(3.0),
+            preview_side: preview_side,
+            previewing: 0,
+            preview_sprites: HashSet::new(),
+        }
+    }
+
+    /// Move the board, as when the window is resized. Sprites are kept
+    /// relative to the grid, so nothing needs to catch up.
+    pub fn set_layout(&mut self, position: PixelPosition, scale: f64) {
+        self.position = position;
+        self.scale = scale;
+    }
+
+    /// While paused, animations are frozen and the board is not synced. The
+    /// board is still drawn as it was.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// How far blocks squash on landing at full speed, from 0 for not at
+    /// all, as the `squash` player setting.
+    pub fn set_squash(&mut self, squash: f64) {
+        self.squash = squash;
+    }
+
+    /// Stop every animation, so that the next render puts each block where
+    /// the board has it. For when the board has changed without events, as
+    /// after an online rollback.
+    pub fn resync(&mut self) {
+        for (_, sprite_id) in self.sprites.iter() {
+            self.scene.stop_all(*sprite_id);
+        }
+        self.drop_wait = 0.0;
+        self.break_wait = 0.0;
+    }
+
+    /// Attacks this board sent in the last update, with where each left
+    /// from, to be passed to the opponent's renderer with `receive`.
+    pub fn take_sent(&mut self) -> Vec<(PixelPosition, u32)> {
+        self.sent.drain(..).collect()
+    }
+
+    /// An attack is on its way from the given position to this board's
+    /// warning gauge, which shows it once it lands.
+    pub fn receive(&mut self, from: PixelPosition, strength: u32) {
+        let rect = self.gauge_rect();
+        let to = PixelPosition::new(rect[0] + rect[2] / 2.0, rect[1] + rect[3] / 2.0);
+        self.projectiles.push(Projectile::new(from, to, strength));
+    }
+
+    /// Pass the attacks each of two boards sent on to the other, and draw
+    /// those in flight over both boards. For once both have rendered.
+    pub fn exchange_attacks(event: &GameWindow, left: &mut Self, right: &mut Self) {
+        for (from, strength) in left.take_sent() {
+            right.receive(from, strength);
+        }
+        for (from, strength) in right.take_sent() {
+            left.receive(from, strength);
         }
+
+        event.draw_2d(|c, g| {
+            let c = c.scale(left.scale, left.scale);
+            left.render_projectiles(&c, g);
+            right.render_projectiles(&c, g);
+        });
     }
 
     fn cell_w(&self) -> f64 { self.cell_dimensions.w() as f64 }
     fn cell_h(&self) -> f64 { self.cell_dimensions.h() as f64 }
+    fn grid_w(&self) -> f64 { self.dimensions.w() as f64 }
     fn grid_h(&self) -> f64 { self.dimensions.h() as f64 }
-    fn x_gutter(&self) -> f64 { self.cell_w() * 2.0 }
-    fn grid_margin(&self) -> PixelPosition {
-        PixelPosition::new(self.x_gutter(), 29.0)
-    }
 
-    // Scale grid coordinates to pixel coordinates for a block.
+    // Scale grid coordinates to the sprite position of a block: the middle
+    // of its cell, from the top left of the grid.
     fn scale(&self, block: &PositionedBlock) -> PixelPosition {
-        let h = self.cell_h() * self.grid_h() as f64;
-
         PixelPosition::new(
-            block.x() as f64 * self.cell_w(),
-            h - (block.y() as f64 * self.cell_h())
+            (block.x() as f64 + 0.5) * self.cell_w(),
+            (self.grid_h() - block.y() as f64 - 0.5) * self.cell_h()
         )
     }
 
+    // The inside of the preview panel in view units, as [x, y, w, h], sized
+    // to hold some pieces.
+    fn panel_rect(&self, pieces: usize) -> [f64; 4] {
+        let (cell_w, cell_h) = (self.cell_w(), self.cell_h());
+        let w = panel_width(cell_w) - PANEL_BORDER * 2.0;
+        let h = pieces as f64 * (cell_h * 2.0 + PIECE_GAP) - PIECE_GAP + PANEL_PADDING * 2.0;
+        let gap = self.frame + PANEL_GAP + PANEL_BORDER;
+
+        let x = match self.preview_side {
+            Side::Left => self.position.x() - gap - w,
+            Side::Right => self.position.x() + cell_w * self.grid_w() + gap,
+        };
+
+        [x, self.position.y(), w, h]
+    }
+
+    // The warning gauge in view units, as [x, y, w, h]: across the frame
+    // above the grid.
+    fn gauge_rect(&self) -> [f64; 4] {
+        let y = self.position.y() - (self.frame + GAUGE_HEIGHT) / 2.0;
+        [self.position.x(), y, self.cell_w() * self.grid_w(), GAUGE_HEIGHT]
+    }
+
+    // Sprite position of a block of the i'th upcoming piece, stacked down
+    // the preview panel from the next piece. Upcoming pieces sit at the
+    // origin.
+    fn preview_position(&self, i: usize, block: &PositionedBlock) -> PixelPosition {
+        let (cell_w, cell_h) = (self.cell_w(), self.cell_h());
+        let panel = self.panel_rect(i + 1);
+
+        let center_x = panel[0] + panel[2] / 2.0 + block.x() as f64 * cell_w;
+        let center_y = panel[1] + PANEL_PADDING + i as f64 * (cell_h * 2.0 + PIECE_GAP) +
+            (1.5 - block.y() as f64) * cell_h;
+
+        PixelPosition::new(center_x - self.position.x(), center_y - self.position.y())
+    }
+
     // Returns a sprite id for a given block. Creates a sprite as necessary.
     fn sprite_for(&mut self, block: &PositionedBlock) -> Uuid {
         let exists = { self.sprites.contains_key(&block.block()) };
@@ -118,11 +304,9 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
         *self.sprites.get(&block.block()).unwrap()
     }
 
-    // Update a block sprite's position and texture to match the grid, using
-    // the default grid margin.
+    // Update a block sprite's position, scale and texture to match the grid.
     fn update_block(&mut self, sprite_id: Uuid, block: &PositionedBlock) {
-        let margin = self.grid_margin();
-        let pos = self.scale(block).add(margin);
+        let pos = self.scale(block);
         self.update_block_to_pos(sprite_id, block, pos);
     }
 
@@ -134,17 +318,40 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
         let sprite = { self.scene.child_mut(sprite_id).unwrap() };
 
         sprite.set_position(pos.x(), pos.y());
+        // Undo any squash cut short.
+        sprite.set_scale(1.0, 1.0);
         sprite.set_texture(self.textures.get(block.to_texture_name()));
     }
 
+    /// Render, keeping the board's render state up to date for its next
+    /// update.
+    pub fn render_into(&mut self, event: &GameWindow, board: &mut Board, state: &mut RenderState) {
+        if let Some(new_state) = self.render(event, board) {
+            *state = new_state;
+        }
+    }
+
     pub fn render(&mut self, event: &GameWindow, board: &mut Board) -> Option<RenderState> {
         let mut result = None;
 
-        self.scene.event(event);
+        if !self.paused {
+            self.scene.event(event);
+        }
 
         event.update(|args| {
+            if self.paused {
+                return;
+            }
+
+            self.drop_wait -= args.dt;
             self.break_wait -= args.dt;
-            let mut render_state = RenderState::new();
+            self.sent.clear();
+
+            for projectile in self.projectiles.iter_mut() {
+                projectile.update(args.dt);
+            }
+            self.projectiles.retain(|projectile| !projectile.has_landed());
+            self.incoming = board.incoming_attack();
             let mut seen = HashSet::new();
 
             if let Some(piece) = board.current_piece() {
@@ -156,16 +363,17 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
                 }
             }
 
-            if let Some(piece) = board.next_piece() {
+            let upcoming = board.upcoming();
+            self.previewing = upcoming.len();
+            self.preview_sprites.clear();
+
+            for (i, piece) in upcoming.iter().enumerate() {
                 for block in piece.blocks().into_iter() {
                     let sprite_id = self.sprite_for(&block);
                     seen.insert(block.block());
+                    self.preview_sprites.insert(sprite_id);
 
-                    // Top left
-                    let pos = PixelPosition::new(
-                        block.x() as f64,
-                        (2 - block.y()) as f64 * self.cell_h() + self.grid_margin().y());
-
+                    let pos = self.preview_position(i, block);
                     self.update_block_to_pos(sprite_id, block, pos);
                 }
             }
@@ -176,18 +384,74 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
                         let sprite_id = self.sprite_for(&to);
                         self.update_block(sprite_id, &from);
 
-                        // Animated drop to new position
-                        // TODO: Scale duration by height, apply gravity
-                        let new_pos = self.scale(&to).add(self.grid_margin());
+                        // Fall from rest under gravity, so longer drops
+                        // take longer, then squash on landing.
+                        let new_pos = self.scale(&to);
+                        let cells = (from.y() - to.y()) as f64;
+                        let t = fall_time(cells);
+                        let s = squash(cells, self.squash);
 
                         self.scene.stop_all(sprite_id);
-                        let action = Action(Ease(EaseFunction::QuadraticIn, Box::new(
-                            MoveTo(0.2, new_pos.x(), new_pos.y())
+                        let fall = Action(Ease(EaseFunction::QuadraticIn, Box::new(
+                            MoveTo(t, new_pos.x(), new_pos.y())
                         )));
+                        let action = if s > 0.0 {
+                            Sequence(vec!(
+                                fall,
+                                Action(ScaleTo(SQUASH_TIME, 1.0 + s, 1.0 - s)),
+                                Action(ScaleTo(SQUASH_TIME, 1.0, 1.0)),
+                            ))
+                        } else {
+                            fall
+                        };
                         self.scene.run(sprite_id, &action);
+
+                        // Play waits for blocks to land, but not for them
+                        // to spring back.
+                        self.drop_wait = self.drop_wait.max(t);
                     },
-                    BlockEvent::Explode(block, depth) => {
-                        use self::rand::*;
+                    BlockEvent::Age(block) => {
+                        // Blocks still falling pick up their new look when
+                        // they land.
+                        let sprite_id = self.sprite_for(&block);
+
+                        if self.scene.running_for_child(sprite_id).unwrap() == 0 {
+                            self.update_block(sprite_id, &block);
+
+                            let action = if block.age() > 0 {
+                                self.scene.child_mut(sprite_id).unwrap().set_scale(0.0, 1.0);
+                                Sequence(vec!(
+                                    Action(ScaleTo(AGE_FLIP, 1.0, 1.0)),
+                                    Action(ScaleTo(AGE_PULSE, 1.2, 1.2)),
+                                    Action(ScaleTo(AGE_PULSE, 1.0, 1.0)),
+                                ))
+                            } else {
+                                // Overshoots a little as it matures.
+                                self.scene.child_mut(sprite_id).unwrap().set_scale(0.0, 0.0);
+                                Action(Ease(EaseFunction::BackOut, Box::new(
+                                    ScaleTo(MATURE_TIME, 1.0, 1.0)
+                                )))
+                            };
+                            self.scene.run(sprite_id, &action);
+                        }
+                    },
+                    BlockEvent::Attack(strength) => {
+                        let from = self.position.add(self.break_site);
+                        self.sent.push((from, strength));
+                    },
+                    BlockEvent::Reset => {
+                        for (_, sprite_id) in self.sprites.drain() {
+                            self.scene.remove_child(sprite_id);
+                        }
+                        self.drop_wait = 0.0;
+                        self.break_wait = 0.0;
+                        self.hud.reset();
+                        self.effects.reset();
+                    },
+                    BlockEvent::Explode(block, depth, gem) => {
+                        self.hud.explode(block);
+                        self.break_site = self.scale(&block);
+                        let b = self.effects.explode(block, depth, gem);
 
                         {
                             let sprite_id = self.sprite_for(&block);
@@ -202,16 +466,11 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
 
                             self.update_block(sprite_id, &block);
 
-                            let mut rng = thread_rng();
-
-                            let t = rng.gen_range(0.4, 0.7);
-                            let s = rng.gen_range(1.3, 1.7);
-                            let r = rng.gen_range(-90.0, 90.0);
-                            let delay = depth as f64 * 0.05;
+                            let (delay, t, s) = (b.delay, b.time, b.scale);
 
                             self.scene.run(sprite_id, &delayed_animation!(delay, FadeOut(t)));
                             self.scene.run(sprite_id, &delayed_animation!(delay, ScaleBy(t, s, s)));
-                            self.scene.run(sprite_id, &delayed_animation!(delay, RotateBy(t, r)));
+                            self.scene.run(sprite_id, &delayed_animation!(delay, RotateBy(t, b.rotation)));
 
                             self.break_wait = self.break_wait.max(delay);
                         }
@@ -229,8 +488,6 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
 
                 if self.scene.running_for_child(sprite_id).unwrap() == 0 {
                     self.update_block(sprite_id, &block);
-                } else {
-                    render_state.dropping += 1;
                 }
             }
 
@@ -250,48 +507,70 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
                     self.scene.remove_child(sprite_id);
                 }
             }
-            render_state.break_wait = self.break_wait;
-            result = Some(render_state)
+            result = Some(RenderState {
+                drop_wait: self.drop_wait,
+                break_wait: self.break_wait,
+            });
+
+            let damage = self.hud.update(args.dt, board.stats(), board.chain(), board.pending_attack());
+            self.effects.hit(damage);
+            self.effects.update(args.dt, board.chain());
         });
 
         event.draw_2d(|c, g| {
-            let cam = &c.trans(self.position.x() as f64 - 29.0,
-                               self.position.y() as f64 - 29.0);
-                               
-            let (clip_x, clip_y, clip_w, clip_h) = (
-                self.position.x() as u16 - 29,
-                (self.position.y() + self.grid_margin().y()) as u16 - 29,
-                (self.cell_dimensions.w() as f64 * self.dimensions.w() as f64 + self.grid_margin().x() as f64) as u16,
-                (self.cell_dimensions.h() as f64 * self.dimensions.h() as f64) as u16
-            );
-            let (clip_x, clip_y, clip_w, clip_h) =
-                (clip_x, c.viewport.unwrap().draw_size[1] as u16 - clip_y - clip_h, clip_w, clip_h);
-
-            // Board background box
+            let c = c.scale(self.scale, self.scale);
+            let (cell_w, cell_h) = (self.cell_w(), self.cell_h());
+
+            // The board and everything on it, shaken.
+            let (shake_x, shake_y) = self.effects.offset();
+            let position = self.position.add(PixelPosition::new(shake_x * cell_w, -shake_y * cell_h));
+
+            // The grid in draw pixels, counted up from the bottom of the
+            // window, which may have more of them than window pixels.
+            let (clip_x, clip_y, clip_w, clip_h) = {
+                let viewport = c.viewport.unwrap();
+                let density = viewport.draw_size[0] as f64 / viewport.window_size[0] as f64;
+                let to_pixels = |x: f64| (x * self.scale * density).round();
+
+                let (x, y) = (to_pixels(position.x()), to_pixels(position.y()));
+                let (w, h) = (to_pixels(cell_w * self.grid_w()), to_pixels(cell_h * self.grid_h()));
+                let bottom = viewport.draw_size[1] as f64 - y - h;
+
+                (x.max(0.0) as u16, bottom.max(0.0) as u16, w as u16, h as u16)
+            };
+
+            // Board background box, framing the grid.
             {
                 use graphics::*;
-                let cam = &cam
-                    .trans(-29.0, -29.0) // Top-left corner of inner board
-                    .trans(
-                        self.grid_margin().x(),
-                        self.grid_margin().y(),
-                    );
-
-                let board = self.textures.get("board.png".to_string());
+
+                let board = self.textures.get("board".to_string());
+                let cam = c.trans(position.x() - self.frame, position.y() - self.frame);
                 image(&*board, cam.transform, g);
             }
 
-            let cam = cam.trans(
-                self.cell_dimensions.w() as f64 / 2.0,
-                self.cell_dimensions.h() as f64 / -2.0,
-            );
+            // Preview panel, framed.
+            if self.previewing > 0 {
+                use graphics::*;
+
+                let panel = self.panel_rect(self.previewing);
+                let (x, y, w, h) = (panel[0], panel[1], panel[2], panel[3]);
+                let edge = PANEL_BORDER;
+                let color = [0.8, 0.8, 0.8, 1.0];
+
+                rectangle([0.0, 0.0, 0.0, 0.6], panel, c.transform, g);
+                rectangle(color, [x - edge, y - edge, w + edge * 2.0, edge], c.transform, g);
+                rectangle(color, [x - edge, y + h, w + edge * 2.0, edge], c.transform, g);
+                rectangle(color, [x - edge, y, edge, h], c.transform, g);
+                rectangle(color, [x + w, y, edge, h], c.transform, g);
+            }
+
+            // Sprites are positioned from the top left of the grid.
+            let cam = c.trans(position.x(), position.y());
 
             for child in self.scene.children() {
-               use graphics::*;
+                use graphics::*;
 
-                let (w, h) = (self.cell_dimensions.w(), self.cell_dimensions.h());
-                let w = w as f64;
-                let h = h as f64;
+                let (w, h) = (cell_w, cell_h);
                 let anchor = [child.get_anchor().0 * w, child.get_anchor().1 * h];
 
                 let transformed = cam.trans(child.get_position().0, child.get_position().1)
@@ -301,10 +580,10 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
                 let ref tex = *child.get_texture();
                 let color = child.get_color();
 
-                // Exploding blocks should not be clipped
+                // Exploding and previewed blocks should not be clipped
                 let draw_state = default_draw_state();
                 let clipped = &draw_state.scissor(clip_x, clip_y, clip_w, clip_h);
-                let state = if child.get_opacity() < 1.0 {
+                let state = if child.get_opacity() < 1.0 || self.preview_sprites.contains(&child.id()) {
                     draw_state
                 } else {
                     clipped
@@ -315,8 +594,123 @@ impl BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>
                     .rect([-anchor[0], -anchor[1], w, h])
                     .draw(&**tex, &state, transformed.transform, g);
             }
+
+            // Pieces of shattered gems, flying free of the grid.
+            for fragment in self.effects.fragments().iter().filter(|f| f.is_visible()) {
+                use graphics::*;
+
+                let tex = self.textures.get(fragment.block.to_texture_name());
+                let (tex_w, tex_h) = tex.get_size();
+                let source = fragment.source;
+                let (w, h) = (source[2] * cell_w, source[3] * cell_h);
+                let x = (fragment.x + 0.5) * cell_w;
+                let y = (self.grid_h() - fragment.y - 0.5) * cell_h;
+                let fade = 1.0 - fragment.age / FRAGMENT_TIME;
+
+                Image::new()
+                    .color([1.0, 1.0, 1.0, fade as f32])
+                    .rect([-w / 2.0, -h / 2.0, w, h])
+                    .src_rect([
+                        (source[0] * tex_w as f64) as i32,
+                        (source[1] * tex_h as f64) as i32,
+                        (source[2] * tex_w as f64) as i32,
+                        (source[3] * tex_h as f64) as i32,
+                    ])
+                    .draw(&*tex, default_draw_state(), cam.trans(x, y).rot_deg(fragment.rotation).transform, g);
+            }
+
+            // Chains light up the grid.
+            let flash = self.effects.flash();
+            if flash > 0.0 {
+                use graphics::*;
+
+                let grid = [position.x(), position.y(), cell_w * self.grid_w(), cell_h * self.grid_h()];
+                rectangle([1.0, 1.0, 1.0, flash as f32], grid, c.transform, g);
+            }
+
+            self.render_hud(&c, g);
+            self.render_gauge(&c, g);
         });
 
         result
     }
+
+    // The warning gauge, filling as attacks land on it.
+    fn render_gauge<G: Graphics>(&self, c: &Context, g: &mut G) {
+        use graphics::*;
+
+        let flying: u32 = self.projectiles.iter().map(|projectile| projectile.strength()).sum();
+        let shown = self.incoming.saturating_sub(flying);
+
+        if shown > 0 {
+            let rect = self.gauge_rect();
+            let full = (self.dimensions.w() * GAUGE_ROWS) as f64;
+            let w = rect[2] * (shown as f64 / full).min(1.0);
+
+            rectangle([0.0, 0.0, 0.0, 0.6], rect, c.transform, g);
+            rectangle(ATTACK_COLOR, [rect[0], rect[1], w, rect[3]], c.transform, g);
+        }
+    }
+
+    // Attacks on their way to this board, trailing a streak.
+    fn render_projectiles<G: Graphics>(&self, c: &Context, g: &mut G) {
+        use graphics::*;
+
+        let red = ATTACK_COLOR;
+
+        for projectile in &self.projectiles {
+            for i in 0..TRAIL_LENGTH {
+                let t = projectile.age() - i as f64 * TRAIL_SPACING;
+                if t < 0.0 {
+                    break;
+                }
+
+                let fade = 1.0 - i as f64 / TRAIL_LENGTH as f64;
+                let r = projectile.radius() * fade;
+                let pos = projectile.position_at(t);
+
+                ellipse([red[0], red[1], red[2], fade as f32], [pos.x() - r, pos.y() - r, r * 2.0, r * 2.0], c.transform, g);
+            }
+        }
+    }
+
+    // The score and attack under the board, damage where blocks broke, and
+    // the chain across the board.
+    fn render_hud<G: Graphics>(&self, c: &Context, g: &mut G) {
+        let (cell_w, cell_h) = (self.cell_w(), self.cell_h());
+        let left = self.position.x();
+        let width = cell_w * self.grid_w();
+        let top = self.position.y();
+        let bottom = top + cell_h * self.grid_h();
+        let below = bottom + self.frame + HUD_MARGIN;
+        let font = &self.hud_font;
+
+        let score = format!("Score {}", self.hud.score());
+        font.draw(&score, [1.0, 1.0, 1.0, 1.0], c.trans(left, below).transform, g);
+
+        if let Some(attack) = self.hud.sending() {
+            let y = below + font.height() * 1.5;
+            font.draw(&format!("Attack {}", attack), [1.0, 0.8, 0.1, 1.0], c.trans(left, y).transform, g);
+        }
+
+        // Numbers rise a cell as they fade.
+        for popup in self.hud.popups() {
+            let t = popup.age / POPUP_TIME;
+            let text = format!("+{}", popup.damage);
+            let x = left + (popup.x + 0.5) * cell_w - font.width(&text) / 2.0;
+            let y = top + (self.grid_h() - popup.y - 0.5 - t) * cell_h - font.height() / 2.0;
+
+            font.draw(&text, [1.0, 1.0, 1.0, 1.0 - t as f32], c.trans(x, y).transform, g);
+        }
+
+        if let Some((chain, age)) = self.hud.chain() {
+            let font = &self.chain_font;
+            let text = format!("{} chain!", chain);
+            let x = left + (width - font.width(&text)) / 2.0;
+            let y = top + cell_h * self.grid_h() / 3.0 - age * cell_h;
+            let fade = 1.0 - (age / CHAIN_TIME) as f32;
+
+            font.draw(&text, [1.0, 0.8, 0.1, fade], c.trans(x, y).transform, g);
+        }
+    }
 }
@@ -0,0 +1,245 @@
+use std::collections::VecDeque;
+
+use input::Command;
+use lockstep::*;
+use match_controller::MatchEvent;
+use net::Connection;
+use settings::PlayerSettings;
+use values::*;
+
+/// Ticks spectators are kept behind the agreed game, so that play can be
+/// shown smoothly while the players' input trickles in.
+pub const SPECTATOR_DELAY: u64 = 90;
+
+/// Sends a match to spectators as it is played. Spectators get the match
+/// setup and everything from the start of the current round, so they can
+/// join at any time, then each agreed tick as it comes.
+pub struct Broadcast {
+    setup: Option<Message>,
+    // The start of the current round and every tick played since.
+    history: Vec<Message>,
+    spectators: Vec<Connection>,
+}
+
+impl Broadcast {
+    pub fn new() -> Self {
+        Broadcast {
+            setup: None,
+            history: Vec::new(),
+            spectators: Vec::new(),
+        }
+    }
+
+    /// Start sending a match. Spectators who were already waiting get it
+    /// now.
+    pub fn start(&mut self, simulation: &Simulation) {
+        self.setup = Some(simulation.setup());
+        self.history = vec!(simulation.round_start());
+
+        let greeting = self.greeting();
+        let spectators: Vec<Connection> = self.spectators.drain(..).collect();
+        for mut spectator in spectators {
+            if send_all(&mut spectator, &greeting).is_ok() {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+
+    /// Everything a spectator needs to pick up the match where it is.
+    pub fn greeting(&self) -> Vec<Message> {
+        match self.setup {
+            Some(ref setup) => {
+                let mut messages = vec!(setup.clone());
+                messages.extend(self.history.iter().cloned());
+                messages
+            },
+            None => Vec::new(),
+        }
+    }
+
+    /// Someone who asked to watch.
+    pub fn add(&mut self, mut spectator: Connection) {
+        let greeting = self.greeting();
+
+        if send_all(&mut spectator, &greeting).is_ok() {
+            self.spectators.push(spectator);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Pass on a Play or Round message. Spectators who can't be reached are
+    /// dropped.
+    pub fn record(&mut self, message: Message) {
+        if let Message::Round { .. } = message {
+            self.history.clear();
+        }
+        self.history.push(message.clone());
+
+        let spectators: Vec<Connection> = self.spectators.drain(..).collect();
+        for mut spectator in spectators {
+            if spectator.send(&message).is_ok() {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+
+    /// Send whatever is still queued, and drop spectators who have left.
+    pub fn flush(&mut self) {
+        let spectators: Vec<Connection> = self.spectators.drain(..).collect();
+        for mut spectator in spectators {
+            if spectator.flush().is_ok() && spectator.receive().is_ok() {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+}
+
+impl Drop for Broadcast {
+    fn drop(&mut self) {
+        for spectator in self.spectators.iter_mut() {
+            let _ = spectator.send(&Message::Bye);
+        }
+    }
+}
+
+fn send_all(connection: &mut Connection, messages: &[Message]) -> Result<(), String> {
+    for message in messages {
+        try!(connection.send(message));
+    }
+    Ok(())
+}
+
+/// Replays a broadcast match a little behind the players. Ticks that
+/// arrive are held back by SPECTATOR_DELAY, and if more than twice that
+/// many pile up, as when joining part way through a round, play skips
+/// ahead.
+pub struct Spectator {
+    dimensions: Dimension,
+    setup: Option<(u64, u32, PlayerSettings, PlayerSettings)>,
+    simulation: Option<Simulation>,
+    // Both players' commands for ticks received but not shown yet.
+    buffer: VecDeque<(Vec<Command>, Vec<Command>)>,
+    // Waiting for the buffer to fill before showing more.
+    buffering: bool,
+    skipped: bool,
+    accumulator: f64,
+}
+
+impl Spectator {
+    pub fn new(dimensions: Dimension) -> Self {
+        Spectator {
+            dimensions: dimensions,
+            setup: None,
+            simulation: None,
+            buffer: VecDeque::new(),
+            buffering: true,
+            skipped: false,
+            accumulator: 0.0,
+        }
+    }
+
+    /// The match being shown, once it has arrived.
+    pub fn simulation(&self) -> Option<&Simulation> { self.simulation.as_ref() }
+    pub fn simulation_mut(&mut self) -> Option<&mut Simulation> { self.simulation.as_mut() }
+
+    /// Whether play is waiting for the players.
+    pub fn is_buffering(&self) -> bool { self.buffering }
+
+    /// Take in a message from the host.
+    pub fn receive(&mut self, message: Message) -> Result<(), String> {
+        match message {
+            Message::Setup { version, seed, best_of, left_soft_drop, right_soft_drop } => {
+                if version != PROTOCOL_VERSION {
+                    return Err("The host has a different version of the game".to_string());
+                }
+                if best_of % 2 == 0 || left_soft_drop < 1.0 || right_soft_drop < 1.0 {
+                    return Err("The host sent a bad setup".to_string());
+                }
+
+                let settings = |soft_drop| PlayerSettings {
+                    soft_drop: soft_drop,
+                    .. PlayerSettings::default()
+                };
+                self.setup = Some((seed, best_of, settings(left_soft_drop), settings(right_soft_drop)));
+            },
+            Message::Round { round, left_wins, right_wins, tick } => {
+                // Later rounds are reached by playing on.
+                if self.simulation.is_none() {
+                    let (seed, best_of, left, right) = try!(self.setup.ok_or("Round before setup".to_string()));
+                    let mut simulation = Simulation::new(self.dimensions, seed, best_of, left, right);
+
+                    simulation.resume(round, left_wins, right_wins, tick);
+                    self.simulation = Some(simulation);
+                }
+            },
+            Message::Play(tick, left, right) => {
+                let expected = match self.simulation {
+                    Some(ref simulation) => simulation.tick() + self.buffer.len() as u64,
+                    None => return Err("Play before round".to_string()),
+                };
+                if tick != expected {
+                    return Err(format!("Expected tick {}, got {}", expected, tick));
+                }
+
+                self.buffer.push_back((left, right));
+            },
+            Message::Bye => return Err("The host left".to_string()),
+            message => return Err(format!("Unexpected `{}`", message.to_line())),
+        }
+
+        Ok(())
+    }
+
+    /// Play on by some seconds. Returns the last match event.
+    pub fn update(&mut self, dt: f64) -> Option<MatchEvent> {
+        let mut event = None;
+
+        if let Some(ref mut simulation) = self.simulation {
+            if self.buffer.len() as u64 > SPECTATOR_DELAY * 2 {
+                while self.buffer.len() as u64 > SPECTATOR_DELAY {
+                    let (left, right) = self.buffer.pop_front().unwrap();
+                    event = simulation.step(&left, &right).or(event);
+                }
+
+                simulation.clear_events();
+                self.skipped = true;
+                self.buffering = false;
+            }
+
+            if self.buffering {
+                if self.buffer.len() as u64 >= SPECTATOR_DELAY {
+                    self.buffering = false;
+                } else {
+                    return event;
+                }
+            }
+
+            self.accumulator += dt;
+            while self.accumulator >= TICK {
+                self.accumulator -= TICK;
+
+                match self.buffer.pop_front() {
+                    Some((left, right)) => event = simulation.step(&left, &right).or(event),
+                    None => {
+                        self.buffering = true;
+                        self.accumulator = 0.0;
+                        break;
+                    },
+                }
+            }
+        }
+
+        event
+    }
+
+    /// Whether play skipped ahead since the last call, leaving the boards
+    /// changed without events.
+    pub fn take_skipped(&mut self) -> bool {
+        let skipped = self.skipped;
+        self.skipped = false;
+        skipped
+    }
+}
@@ -0,0 +1,528 @@
+use std::fs::File;
+use std::io::{Read,Write};
+use std::path::{Path,PathBuf};
+
+use match_controller::Side;
+use records::data_dir;
+use settings::PlayerSettings;
+
+/// Something a player can do with their board.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    AntiClockwise,
+    Clockwise,
+    Left,
+    Right,
+    Turbo,
+    Pause,
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        vec!(
+            Action::AntiClockwise,
+            Action::Clockwise,
+            Action::Left,
+            Action::Right,
+            Action::Turbo,
+            Action::Pause,
+        )
+    }
+
+    /// Name used in the config file.
+    pub fn key(&self) -> &'static str {
+        match *self {
+            Action::AntiClockwise => "anticlockwise",
+            Action::Clockwise     => "clockwise",
+            Action::Left          => "left",
+            Action::Right         => "right",
+            Action::Turbo         => "turbo",
+            Action::Pause         => "pause",
+        }
+    }
+
+    /// Name shown to players.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Action::AntiClockwise => "Rotate left",
+            Action::Clockwise     => "Rotate right",
+            Action::Left          => "Move left",
+            Action::Right         => "Move right",
+            Action::Turbo         => "Drop",
+            Action::Pause         => "Pause",
+        }
+    }
+}
+
+/// A physical input: a keyboard key (by Piston key code), a gamepad button,
+/// or a gamepad axis pushed in one direction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Key(u32),
+    // Gamepad id, button
+    Button(i32, u8),
+    // Gamepad id, axis, whether pushed towards positive
+    Axis(i32, u8, bool),
+}
+
+// Piston key codes for keys that aren't a single letter or digit.
+const KEY_NAMES: &'static [(u32, &'static str)] = &[
+    (0x08, "Backspace"),
+    (0x09, "Tab"),
+    (0x0D, "Return"),
+    (0x1B, "Escape"),
+    (0x20, "Space"),
+    (0x27, "Quote"),
+    (0x2C, "Comma"),
+    (0x2D, "Minus"),
+    (0x2E, "Period"),
+    (0x2F, "Slash"),
+    (0x3B, "Semicolon"),
+    (0x3D, "Equals"),
+    (0x5B, "LeftBracket"),
+    (0x5C, "Backslash"),
+    (0x5D, "RightBracket"),
+    (0x60, "Backquote"),
+    (0x7F, "Delete"),
+    (0x4000_0049, "Insert"),
+    (0x4000_004A, "Home"),
+    (0x4000_004B, "PageUp"),
+    (0x4000_004D, "End"),
+    (0x4000_004E, "PageDown"),
+    (0x4000_004F, "Right"),
+    (0x4000_0050, "Left"),
+    (0x4000_0051, "Down"),
+    (0x4000_0052, "Up"),
+    (0x4000_00E0, "LCtrl"),
+    (0x4000_00E1, "LShift"),
+    (0x4000_00E2, "LAlt"),
+    (0x4000_00E4, "RCtrl"),
+    (0x4000_00E5, "RShift"),
+    (0x4000_00E6, "RAlt"),
+];
+
+fn key_name(code: u32) -> String {
+    match ::std::char::from_u32(code) {
+        Some(c) if c.is_ascii_lowercase() || c.is_ascii_digit() => {
+            return c.to_ascii_uppercase().to_string();
+        },
+        _ => {}
+    }
+
+    for &(x, name) in KEY_NAMES {
+        if x == code {
+            return name.to_string();
+        }
+    }
+
+    format!("#{}", code)
+}
+
+fn key_code(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_lowercase() as u32);
+        }
+    }
+
+    if name.starts_with("#") {
+        return name[1..].parse().ok();
+    }
+
+    KEY_NAMES.iter()
+        .find(|&&(_, x)| x.eq_ignore_ascii_case(name))
+        .map(|&(code, _)| code)
+}
+
+impl Binding {
+    /// Parse the config file form: `key W`, `button 0 1` (gamepad 0, button
+    /// 1) or `axis 0 1+` (gamepad 0, axis 1, pushed positive).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let error = || format!("Bad binding `{}`", text);
+
+        match (words.len(), words.get(0)) {
+            (2, Some(&"key")) => {
+                key_code(words[1])
+                    .map(Binding::Key)
+                    .ok_or(format!("Unknown key `{}`", words[1]))
+            },
+            (3, Some(&"button")) => {
+                match (words[1].parse(), words[2].parse()) {
+                    (Ok(id), Ok(button)) => Ok(Binding::Button(id, button)),
+                    _ => Err(error()),
+                }
+            },
+            (3, Some(&"axis")) => {
+                let axis = words[2];
+                let positive = if axis.ends_with("+") {
+                    true
+                } else if axis.ends_with("-") {
+                    false
+                } else {
+                    return Err(error());
+                };
+
+                match (words[1].parse(), axis[..axis.len() - 1].parse()) {
+                    (Ok(id), Ok(axis)) => Ok(Binding::Axis(id, axis, positive)),
+                    _ => Err(error()),
+                }
+            },
+            _ => Err(error()),
+        }
+    }
+
+    /// Inverse of parse.
+    pub fn to_config(&self) -> String {
+        match *self {
+            Binding::Key(code) => format!("key {}", key_name(code)),
+            Binding::Button(id, button) => format!("button {} {}", id, button),
+            Binding::Axis(id, axis, positive) =>
+                format!("axis {} {}{}", id, axis, if positive { "+" } else { "-" }),
+        }
+    }
+
+    /// Short name shown to players.
+    pub fn label(&self) -> String {
+        match *self {
+            Binding::Key(code) => key_name(code),
+            Binding::Button(id, button) => format!("Pad{} B{}", id, button),
+            Binding::Axis(id, axis, positive) =>
+                format!("Pad{} A{}{}", id, axis, if positive { "+" } else { "-" }),
+        }
+    }
+
+    fn is_key(&self) -> bool {
+        match *self {
+            Binding::Key(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Which inputs trigger which action for one player, and how they are
+/// handled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerControls {
+    bindings: Vec<(Binding, Action)>,
+    settings: PlayerSettings,
+}
+
+impl PlayerControls {
+    fn new() -> Self {
+        PlayerControls {
+            bindings: Vec::new(),
+            settings: PlayerSettings::default(),
+        }
+    }
+
+    pub fn bindings(&self) -> &[(Binding, Action)] {
+        &self.bindings
+    }
+
+    pub fn settings(&self) -> PlayerSettings {
+        self.settings
+    }
+
+    pub fn action_for(&self, binding: Binding) -> Option<Action> {
+        self.bindings.iter()
+            .find(|&&(b, _)| b == binding)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn bindings_for(&self, action: Action) -> Vec<Binding> {
+        self.bindings.iter()
+            .filter(|&&(_, a)| a == action)
+            .map(|&(binding, _)| binding)
+            .collect()
+    }
+
+    fn add(&mut self, binding: Binding, action: Action) {
+        self.bindings.push((binding, action));
+    }
+
+    fn unbind(&mut self, binding: Binding) {
+        self.bindings.retain(|&(b, _)| b != binding);
+    }
+}
+
+/// Bindings for both players, kept in controls.ini in the data directory:
+///
+/// ```text
+/// [left]
+/// clockwise = key S, button 0 1
+/// left = key A, axis 0 0-
+/// das = 0.2
+/// ```
+///
+/// Along with the actions, each player can set the timings in
+/// PlayerSettings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Controls {
+    left: PlayerControls,
+    right: PlayerControls,
+}
+
+impl Default for Controls {
+    /// WASD with C to drop for the left player, arrows with space for the
+    /// right, and a gamepad each.
+    fn default() -> Self {
+        let mut controls = Controls {
+            left: PlayerControls::new(),
+            right: PlayerControls::new(),
+        };
+
+        let keys = vec!(
+            (Side::Left, vec!("W", "S", "A", "D", "C", "Escape"), 0),
+            (Side::Right, vec!("Up", "Down", "Left", "Right", "Space", "Return"), 1),
+        );
+
+        for (side, names, pad) in keys {
+            let player = controls.player_mut(side);
+            let pad_bindings = vec!(
+                Binding::Button(pad, 3),
+                Binding::Button(pad, 1),
+                Binding::Axis(pad, 0, false),
+                Binding::Axis(pad, 0, true),
+                Binding::Axis(pad, 1, true),
+                Binding::Button(pad, 9),
+            );
+
+            for ((action, name), pad_binding) in Action::all().into_iter().zip(names).zip(pad_bindings) {
+                player.add(Binding::Key(key_code(name).unwrap()), action);
+                player.add(pad_binding, action);
+            }
+        }
+
+        controls
+    }
+}
+
+fn side_key(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Right => "right",
+    }
+}
+
+impl Controls {
+    pub fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("controls.ini"))
+    }
+
+    /// Controls from the config file. The defaults are written out if there
+    /// is no file yet, and used without overwriting it if it is invalid.
+    pub fn load() -> Self {
+        let path = match Controls::path() {
+            Some(path) => path,
+            None => return Controls::default(),
+        };
+
+        if !path.exists() {
+            let controls = Controls::default();
+            if let Err(e) = controls.save_to(&path) {
+                println!("Could not write default controls: {}", e);
+            }
+            return controls;
+        }
+
+        match Controls::load_from(&path) {
+            Ok(controls) => controls,
+            Err(e) => {
+                println!("{}, using default controls", e);
+                Controls::default()
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        let mut contents = String::new();
+
+        try!(File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)));
+
+        Controls::parse(&contents)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut controls = Controls {
+            left: PlayerControls::new(),
+            right: PlayerControls::new(),
+        };
+        let mut side = None;
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+
+            if line.starts_with("[") && line.ends_with("]") {
+                side = match &line[1..line.len() - 1] {
+                    "left" => Some(Side::Left),
+                    "right" => Some(Side::Right),
+                    x => return Err(format!("Unknown player `{}`", x)),
+                };
+                continue;
+            }
+
+            let side = try!(side.ok_or(format!("`{}` is not under [left] or [right]", line)));
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("Expected `action = bindings`, got `{}`", line)),
+            };
+
+            if PlayerSettings::keys().contains(&key) {
+                try!(controls.player_mut(side).settings.set(key, value));
+                continue;
+            }
+
+            let action = try!(Action::all().into_iter()
+                .find(|action| action.key() == key)
+                .ok_or(format!("Unknown action `{}`", key)));
+
+            for binding in value.split(',') {
+                let binding = try!(Binding::parse(binding));
+                controls.player_mut(side).add(binding, action);
+            }
+        }
+
+        try!(controls.validate());
+        Ok(controls)
+    }
+
+    /// Every action needs a binding, and no binding can do two things.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut seen: Vec<(Binding, Side, Action)> = Vec::new();
+
+        for side in vec!(Side::Left, Side::Right) {
+            let player = self.player(side);
+
+            for action in Action::all() {
+                if player.bindings_for(action).is_empty() {
+                    return Err(format!("Nothing is bound to {} {}", side_key(side), action.key()));
+                }
+            }
+
+            for &(binding, action) in player.bindings() {
+                if let Some(&(_, other_side, other_action)) = seen.iter().find(|x| x.0 == binding) {
+                    return Err(format!("`{}` is bound to both {} {} and {} {}",
+                        binding.to_config(),
+                        side_key(other_side), other_action.key(),
+                        side_key(side), action.key()));
+                }
+                seen.push((binding, side, action));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Controls made up of each player's.
+    pub fn new(left: PlayerControls, right: PlayerControls) -> Self {
+        Controls {
+            left: left,
+            right: right,
+        }
+    }
+
+    pub fn player(&self, side: Side) -> &PlayerControls {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    fn player_mut(&mut self, side: Side) -> &mut PlayerControls {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    /// Swap in different controls for one player, taking any of their
+    /// bindings away from the other.
+    pub fn set_player(&mut self, side: Side, controls: PlayerControls) {
+        {
+            let other = match side {
+                Side::Left => &mut self.right,
+                Side::Right => &mut self.left,
+            };
+            for &(binding, _) in controls.bindings() {
+                other.unbind(binding);
+            }
+        }
+        *self.player_mut(side) = controls;
+    }
+
+    /// Make a binding trigger an action for a player, taking it away from
+    /// whatever it did before. Replaces the action's other bindings of the
+    /// same kind (keyboard or gamepad), so each action keeps one of each.
+    pub fn rebind(&mut self, side: Side, action: Action, binding: Binding) {
+        self.left.unbind(binding);
+        self.right.unbind(binding);
+
+        let player = self.player_mut(side);
+        for old in player.bindings_for(action) {
+            if old.is_key() == binding.is_key() {
+                player.unbind(old);
+            }
+        }
+        player.add(binding, action);
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = String::from(
+            "# Controls for each player. Bind an action to any number of inputs,\n\
+             # separated by commas: `key W`, `button 0 1` (gamepad 0, button 1)\n\
+             # or `axis 0 1+` (gamepad 0, axis 1 pushed positive).\n\
+             #\n\
+             # das: seconds a button is held before it repeats\n\
+             # arr: seconds between repeats\n\
+             # soft_drop: how many times faster pieces fall when dropping\n\
+             # dead_zone: how far, from 0 to 1, to push a stick\n\
+             # preview: how many upcoming pieces to show, from 1 to 5\n\
+             # squash: how far blocks squash on landing, from 0 (not at all) to 0.5\n");
+
+        for side in vec!(Side::Left, Side::Right) {
+            config.push_str(&format!("\n[{}]\n", side_key(side)));
+
+            for action in Action::all() {
+                let bindings: Vec<String> = self.player(side).bindings_for(action)
+                    .iter().map(|b| b.to_config()).collect();
+
+                config.push_str(&format!("{} = {}\n", action.key(), bindings.join(", ")));
+            }
+
+            let settings = self.player(side).settings();
+            for key in PlayerSettings::keys() {
+                config.push_str(&format!("{} = {}\n", key, settings.get(key).unwrap()));
+            }
+        }
+
+        config
+    }
+
+    /// Write to the config file, if there is a data directory.
+    pub fn save(&self) -> Result<(), String> {
+        match Controls::path() {
+            Some(path) => self.save_to(&path),
+            None => Ok(()),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            try!(::std::fs::create_dir_all(dir).map_err(|e| e.to_string()));
+        }
+
+        File::create(path)
+            .and_then(|mut f| f.write_all(self.to_config().as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,242 @@
+use piston_window::*;
+
+use controls::*;
+use human_player::HumanPlayer;
+use match_controller::Side;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use profiles::PlayerProfiles;
+use wrapper_types::*;
+
+// Top left of the table of bindings.
+const TABLE_X: f64 = 24.0;
+const TABLE_Y: f64 = 48.0;
+
+// Left edge of each player's column.
+const LEFT_COLUMN: f64 = 200.0;
+const RIGHT_COLUMN: f64 = 440.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Row {
+    Bind(Side, Action),
+    Reset,
+    Done,
+}
+
+/// Shows each player's bindings and lets them be changed: select an action,
+/// then press the key, button or stick direction to use for it. Changes are
+/// saved straight away: to the player's profile if they have picked one,
+/// otherwise to controls.ini.
+pub struct ControlsScreen {
+    controls: Controls,
+    rows: Vec<Row>,
+    selected: usize,
+    waiting: bool,
+    error: Option<String>,
+    font: Font,
+    layout: Layout,
+}
+
+impl ControlsScreen {
+    /// Start from the controls the players are using now.
+    pub fn new(controls: Controls, layout: Layout) -> Self {
+        let mut rows = Vec::new();
+        for side in vec!(Side::Left, Side::Right) {
+            for action in Action::all() {
+                rows.push(Row::Bind(side, action));
+            }
+        }
+        rows.push(Row::Reset);
+        rows.push(Row::Done);
+
+        ControlsScreen {
+            controls: controls,
+            rows: rows,
+            selected: 0,
+            waiting: false,
+            error: None,
+            font: Font::new(2.0),
+            layout: layout,
+        }
+    }
+
+    fn apply(&mut self, left_player: &mut HumanPlayer, right_player: &mut HumanPlayer) {
+        left_player.set_controls(self.controls.player(Side::Left));
+        right_player.set_controls(self.controls.player(Side::Right));
+
+        self.error = self.save(left_player.profile(), right_player.profile()).err();
+    }
+
+    fn save(&self, left: Option<&str>, right: Option<&str>) -> Result<(), String> {
+        try!(self.controls.validate());
+
+        let mut shared = Controls::load();
+        let mut profiles = PlayerProfiles::load();
+
+        for &(side, profile) in [(Side::Left, left), (Side::Right, right)].iter() {
+            let player = self.controls.player(side).clone();
+            match profile {
+                Some(name) => try!(profiles.set_controls(name, side, player)),
+                None => shared.set_player(side, player),
+            }
+        }
+
+        if left.is_none() || right.is_none() {
+            try!(shared.validate());
+            try!(shared.save());
+        }
+        Ok(())
+    }
+
+    // Line of the table a row is drawn on. Both players' bindings for an
+    // action share a line.
+    fn line_for(&self, row: Row) -> usize {
+        let actions = Action::all();
+
+        match row {
+            Row::Bind(_, action) => actions.iter().position(|&a| a == action).unwrap() + 1,
+            Row::Reset => actions.len() + 2,
+            Row::Done => actions.len() + 3,
+        }
+    }
+
+    fn render(&self, e: &GameWindow) {
+        let font = &self.font;
+        let line = font.height() * 2.0;
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let highlight = [1.0, 0.8, 0.1, 1.0];
+
+        let mut labels = vec!(
+            ("Controls".to_string(), TABLE_X, TABLE_Y, highlight),
+            ("Left player".to_string(), LEFT_COLUMN, TABLE_Y, white),
+            ("Right player".to_string(), RIGHT_COLUMN, TABLE_Y, white),
+        );
+
+        for (i, &row) in self.rows.iter().enumerate() {
+            let color = if i == self.selected { highlight } else { white };
+            let y = TABLE_Y + line * self.line_for(row) as f64;
+
+            match row {
+                Row::Bind(side, action) => {
+                    let x = match side {
+                        Side::Left => LEFT_COLUMN,
+                        Side::Right => RIGHT_COLUMN,
+                    };
+
+                    let text = if self.waiting && i == self.selected {
+                        "Press a button...".to_string()
+                    } else {
+                        let names: Vec<String> = self.controls.player(side).bindings_for(action)
+                            .iter().map(|b| b.label()).collect();
+                        names.join(", ")
+                    };
+
+                    if side == Side::Left {
+                        labels.push((action.label().to_string(), TABLE_X, y, white));
+                    }
+                    labels.push((text, x, y, color));
+                },
+                Row::Reset => labels.push(("Reset to defaults".to_string(), TABLE_X, y, color)),
+                Row::Done => labels.push(("Done".to_string(), TABLE_X, y, color)),
+            }
+        }
+
+        if let Some(ref error) = self.error {
+            let y = TABLE_Y + line * (self.line_for(Row::Done) + 2) as f64;
+            labels.push((error.clone(), TABLE_X, y, [1.0, 0.3, 0.3, 1.0]));
+        }
+
+        let layout = &self.layout;
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let origin = layout.origin();
+            let c = layout.apply(&c).trans(origin.x(), origin.y());
+
+            for &(ref text, x, y, color) in &labels {
+                font.draw(text, color, c.trans(x, y).transform, g);
+            }
+        });
+    }
+}
+
+impl Mode for ControlsScreen {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            if self.waiting {
+                if let Row::Bind(side, action) = self.rows[self.selected] {
+                    // Gamepads only bind for the player they are seated with.
+                    let binding = match side {
+                        Side::Left => left_player.pressed_binding(e),
+                        Side::Right => right_player.pressed_binding(e),
+                    };
+
+                    if let Some(binding) = binding {
+                        self.waiting = false;
+
+                        // Refused if it would leave an action with nothing
+                        // bound, rather than saving controls that can't be
+                        // loaded again.
+                        let mut controls = self.controls.clone();
+                        controls.rebind(side, action, binding);
+                        match controls.validate() {
+                            Ok(()) => {
+                                self.controls = controls;
+                                self.apply(left_player, right_player);
+                            },
+                            Err(e) => self.error = Some(e),
+                        }
+                    }
+                }
+            } else {
+                let mut command = None;
+                for c in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                    command = c.or(command);
+                }
+
+                let n = self.rows.len();
+                match command {
+                    Some(MenuCommand::Up) => self.selected = (self.selected + n - 1) % n,
+                    Some(MenuCommand::Down) => self.selected = (self.selected + 1) % n,
+                    Some(MenuCommand::Select) => match self.rows[self.selected] {
+                        Row::Bind(_, _) => {
+                            self.waiting = true;
+                        },
+                        Row::Reset => {
+                            self.controls = Controls::default();
+                            self.apply(left_player, right_player);
+                        },
+                        Row::Done => {
+                            result = Some(ModeEvent::Finished);
+                        },
+                    },
+                    Some(MenuCommand::Pause) => {
+                        result = Some(ModeEvent::Finished);
+                    },
+                    None => {}
+                }
+            }
+        }
+
+        self.render(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.selected = 0;
+        self.waiting = false;
+    }
+}
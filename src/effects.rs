@@ -0,0 +1,226 @@
+extern crate rand;
+
+use self::rand::{Rng,SeedableRng,XorShiftRng};
+
+use values::*;
+
+/// Blocks a power gem needs for its blocks to shatter rather than fade.
+pub const SHATTER_GEM: u32 = 4;
+
+/// Pieces a shattered block breaks into along each side.
+pub const FRAGMENTS_PER_SIDE: u32 = 2;
+
+/// Seconds fragments last, fading as they go.
+pub const FRAGMENT_TIME: f64 = 0.8;
+
+/// How fast fragments speed up as they fall, in cells per second squared.
+pub const FRAGMENT_GRAVITY: f64 = 30.0;
+
+/// Cells a board shakes by for each point of damage a combo step does, and
+/// the most it shakes by.
+pub const SHAKE_PER_DAMAGE: f64 = 0.02;
+pub const MAX_SHAKE: f64 = 0.4;
+
+/// Seconds for a shake to die down.
+pub const SHAKE_TIME: f64 = 0.3;
+
+/// Seconds a chain flash lasts.
+pub const FLASH_TIME: f64 = 0.25;
+
+/// How a broken block's own sprite goes: after a delay, it fades over some
+/// seconds while scaling and turning by some degrees.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Break {
+    pub delay: f64,
+    pub time: f64,
+    pub scale: f64,
+    pub rotation: f64,
+}
+
+/// A piece of a shattered block. Positions are in grid cells, like a
+/// block's, of the middle of the piece, and velocities in cells per second.
+/// `source` is the part of the block's texture it shows, as [x, y, w, h]
+/// fractions of the texture. The age starts negative while the block waits
+/// its turn to break.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fragment {
+    pub block: Block,
+    pub source: [f64; 4],
+    pub x: f64,
+    pub y: f64,
+    pub vx: f64,
+    pub vy: f64,
+    pub rotation: f64,
+    pub spin: f64,
+    pub age: f64,
+}
+
+impl Fragment {
+    /// Whether the block has broken and the piece is showing.
+    pub fn is_visible(&self) -> bool {
+        self.age >= 0.0 && self.age < FRAGMENT_TIME
+    }
+}
+
+/// Effects of blocks breaking on a board: broken blocks fading and
+/// tumbling, power gems shattering into fragments, the board shaking with
+/// the damage done, and a flash for each link of a chain. Everything random
+/// comes from a seeded generator, so effects seeded alike and fed the same
+/// events play out the same.
+#[derive(Clone)]
+pub struct Effects {
+    rng: XorShiftRng,
+    fragments: Vec<Fragment>,
+    // Strength of the current shake in cells, how long it has gone on, and
+    // where it has the board.
+    shake: f64,
+    shake_age: f64,
+    offset: (f64, f64),
+    // The chain last flashed for, and how long since, with how bright.
+    chain: u32,
+    flash_age: f64,
+    flash: f64,
+}
+
+impl Effects {
+    pub fn new(seed: u64) -> Self {
+        // The generator needs a seed that isn't all zeroes.
+        let seed = [seed as u32, (seed >> 32) as u32, 0x2545_F491, 0x6C07_8965];
+
+        Effects {
+            rng: XorShiftRng::from_seed(seed),
+            fragments: Vec::new(),
+            shake: 0.0,
+            shake_age: SHAKE_TIME,
+            offset: (0.0, 0.0),
+            chain: 0,
+            flash_age: FLASH_TIME,
+            flash: 0.0,
+        }
+    }
+
+    pub fn fragments(&self) -> &[Fragment] { &self.fragments }
+
+    /// How far the board is shaken from where it belongs, in cells.
+    pub fn offset(&self) -> (f64, f64) { self.offset }
+
+    /// Opacity of the flash over the board, from 0 for none.
+    pub fn flash(&self) -> f64 {
+        if self.flash_age < FLASH_TIME {
+            self.flash * (1.0 - self.flash_age / FLASH_TIME)
+        } else {
+            0.0
+        }
+    }
+
+    /// Stop everything, as for a new board. The generator carries on.
+    pub fn reset(&mut self) {
+        self.fragments.clear();
+        self.shake = 0.0;
+        self.shake_age = SHAKE_TIME;
+        self.offset = (0.0, 0.0);
+        self.chain = 0;
+        self.flash_age = FLASH_TIME;
+    }
+
+    /// A block broke, as told by BlockEvent::Explode. Blocks deeper into a
+    /// combo step break later. Those of large enough power gems shatter,
+    /// flying apart harder the larger the gem, and their sprites vanish
+    /// quickly to make way.
+    pub fn explode(&mut self, block: PositionedBlock, depth: u32, gem: u32) -> Break {
+        let delay = depth as f64 * 0.05;
+
+        if gem < SHATTER_GEM {
+            return Break {
+                delay: delay,
+                time: self.rng.gen_range(0.4, 0.7),
+                scale: self.rng.gen_range(1.3, 1.7),
+                rotation: self.rng.gen_range(-90.0, 90.0),
+            };
+        }
+
+        let n = FRAGMENTS_PER_SIDE;
+        let size = 1.0 / n as f64;
+        let force = (gem as f64).sqrt() / 2.0;
+
+        for i in 0..n {
+            for j in 0..n {
+                // From the left and top of the block, as textures go.
+                let u = i as f64 * size;
+                let v = j as f64 * size;
+                let dx = u + size / 2.0 - 0.5;
+                let dy = 0.5 - v - size / 2.0;
+
+                self.fragments.push(Fragment {
+                    block: block.block(),
+                    source: [u, v, size, size],
+                    x: block.x() as f64 + dx,
+                    y: block.y() as f64 + dy,
+                    vx: (dx * 8.0 + self.rng.gen_range(-1.5, 1.5)) * force,
+                    vy: (dy * 8.0 + self.rng.gen_range(2.0, 6.0)) * force,
+                    rotation: 0.0,
+                    spin: self.rng.gen_range(-360.0, 360.0),
+                    age: -delay,
+                });
+            }
+        }
+
+        Break {
+            delay: delay,
+            time: 0.05,
+            scale: 1.0,
+            rotation: 0.0,
+        }
+    }
+
+    /// A combo step did some damage. Shakes the board harder for more,
+    /// unless it is already shaking harder.
+    pub fn hit(&mut self, damage: u32) {
+        let strength = (damage as f64 * SHAKE_PER_DAMAGE).min(MAX_SHAKE);
+
+        if strength > self.remaining_shake() {
+            self.shake = strength;
+            self.shake_age = 0.0;
+        }
+    }
+
+    fn remaining_shake(&self) -> f64 {
+        if self.shake_age < SHAKE_TIME {
+            self.shake * (1.0 - self.shake_age / SHAKE_TIME)
+        } else {
+            0.0
+        }
+    }
+
+    /// Move on by some seconds, given the chain the board is on. Each link
+    /// from two up flashes, brighter for longer chains.
+    pub fn update(&mut self, dt: f64, chain: u32) {
+        for fragment in self.fragments.iter_mut() {
+            let was_waiting = fragment.age < 0.0;
+            fragment.age += dt;
+
+            if !was_waiting {
+                fragment.x += fragment.vx * dt;
+                fragment.y += fragment.vy * dt - FRAGMENT_GRAVITY * dt * dt / 2.0;
+                fragment.vy -= FRAGMENT_GRAVITY * dt;
+                fragment.rotation += fragment.spin * dt;
+            }
+        }
+        self.fragments.retain(|fragment| fragment.age < FRAGMENT_TIME);
+
+        self.shake_age += dt;
+        let shake = self.remaining_shake();
+        self.offset = if shake > 0.0 {
+            (self.rng.gen_range(-shake, shake), self.rng.gen_range(-shake, shake))
+        } else {
+            (0.0, 0.0)
+        };
+
+        self.flash_age += dt;
+        if chain > self.chain && chain >= 2 {
+            self.flash = (0.2 + 0.1 * chain as f64).min(0.6);
+            self.flash_age = 0.0;
+        }
+        self.chain = chain;
+    }
+}
@@ -0,0 +1,135 @@
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board::Board;
+use board_renderer::*;
+use human_player::HumanPlayer;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use progression::Progression;
+use versus::{TEXT_MARGIN,render_breakdown};
+use wrapper_types::*;
+
+/// Single player survival against garbage from a simulated opponent, getting
+/// faster over time. Either player's controls drive the board.
+pub struct Endless {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    board: Board,
+    renderer: GameBoardRenderer,
+    render_state: RenderState,
+    progression: Progression,
+    font: Font,
+}
+
+impl Endless {
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout) -> Self {
+
+        Endless {
+            board: Board::new(dimensions),
+            renderer: BoardRenderer::new(textures.clone(), layout.center(), dimensions),
+            render_state: RenderState::new(),
+            progression: Progression::new(),
+            font: Font::new(3.0),
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+        }
+    }
+
+    fn render_hud(&self, e: &GameWindow) {
+        let font = &self.font;
+        let stats = self.board.stats();
+        let level = self.progression.level();
+        let game_over = self.board.is_game_over();
+        let layout = self.layout;
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+
+            let text = format!("Level {}", level);
+            let top = c.trans(layout.center().x(), layout.top() + TEXT_MARGIN);
+            font.draw(&text, [1.0, 1.0, 1.0, 1.0], top.transform, g);
+
+            if game_over {
+                let lines = vec!(
+                    "Game over!".to_string(),
+                    format!("Final score {}", stats.score),
+                    format!("Reached level {}", level),
+                );
+                render_breakdown(font, &lines, &c, g);
+            }
+        });
+    }
+}
+
+impl Mode for Endless {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            if self.board.is_game_over() {
+                // Wait for a button before leaving the final score.
+                for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                    if command.is_some() {
+                        result = Some(ModeEvent::Finished);
+                    }
+                }
+            } else {
+                let left = left_player.update(e, &mut self.board);
+                let right = right_player.update(e, &mut self.board);
+
+                if left.or(right) == Some(MenuCommand::Pause) {
+                    result = Some(ModeEvent::Pause);
+                }
+
+                let board = &mut self.board;
+                let render_state = &self.render_state;
+                let progression = &mut self.progression;
+
+                e.update(|args| {
+                    board.update(args.dt, render_state);
+
+                    progression.counter(board.take_attack());
+                    board.attack(progression.update(args.dt));
+                    board.set_step_interval(progression.step_interval());
+                });
+            }
+        }
+
+        self.renderer.set_paused(paused);
+        self.renderer.set_layout(self.layout.center(), self.layout.scale());
+        self.renderer.set_squash(left_player.controls().settings().squash);
+
+        self.renderer.render_into(e, &mut self.board, &mut self.render_state);
+
+        self.render_hud(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.board = Board::new(self.dimensions);
+        self.renderer = BoardRenderer::new(self.textures.clone(), self.layout.center(), self.dimensions);
+        self.render_state = RenderState::new();
+        self.progression = Progression::new();
+    }
+}
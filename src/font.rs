@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use graphics::{Graphics,rectangle};
+use graphics::math::Matrix2d;
+
+pub const GLYPH_W: usize = 5;
+pub const GLYPH_H: usize = 7;
+
+// The glyphs, built in so that text can be drawn before anything is loaded.
+const GLYPHS: &'static str = include_str!("../assets/font.txt");
+
+// Rows of lit pixels, top down.
+type Glyph = Vec<Vec<bool>>;
+
+thread_local! {
+    static DEFAULT_GLYPHS: Rc<HashMap<char, Glyph>> =
+        Rc::new(parse_glyphs(GLYPHS).expect("Bad assets/font.txt"));
+}
+
+// Space between adjacent glyphs, in font pixels.
+const SPACING: usize = 1;
+
+/// A tiny fixed-width bitmap font. Glyphs are drawn as a rectangle per lit
+/// pixel, so text can be shown without a font renderer or pre-generated
+/// images. The glyphs are in assets/font.txt: upper case letters, digits
+/// and a little punctuation. Lower case is drawn as upper case.
+pub struct Font {
+    // Size of a single font pixel, in screen pixels.
+    pixel: f64,
+    glyphs: Rc<HashMap<char, Glyph>>,
+}
+
+impl Font {
+    pub fn new(pixel: f64) -> Self {
+        Font {
+            pixel: pixel,
+            glyphs: DEFAULT_GLYPHS.with(|glyphs| glyphs.clone()),
+        }
+    }
+
+    /// Whether there is a glyph for a character, rather than a gap.
+    pub fn supports(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c.to_ascii_uppercase())
+    }
+
+    pub fn height(&self) -> f64 {
+        GLYPH_H as f64 * self.pixel
+    }
+
+    pub fn width(&self, text: &str) -> f64 {
+        let n = text.chars().count();
+
+        if n == 0 {
+            0.0
+        } else {
+            (n * (GLYPH_W + SPACING) - SPACING) as f64 * self.pixel
+        }
+    }
+
+    /// Draw text with its top left corner at the origin of transform.
+    pub fn draw<G: Graphics>(&self, text: &str, color: [f32; 4], transform: Matrix2d, g: &mut G) {
+        let mut x = 0.0;
+
+        for c in text.chars() {
+            let rows = self.glyphs.get(&c.to_ascii_uppercase()).map_or(&[][..], |glyph| &glyph[..]);
+
+            for (y, row) in rows.iter().enumerate() {
+                for (i, &lit) in row.iter().enumerate() {
+                    if lit {
+                        rectangle(
+                            color,
+                            [
+                                x + i as f64 * self.pixel,
+                                y as f64 * self.pixel,
+                                self.pixel,
+                                self.pixel
+                            ],
+                            transform,
+                            g
+                        );
+                    }
+                }
+            }
+
+            x += (GLYPH_W + SPACING) as f64 * self.pixel;
+        }
+    }
+}
+
+/// Read glyphs written as in assets/font.txt: the character, then a line of
+/// `#` (lit) and `.` (unlit) per row. Lines starting with `;` are comments.
+pub fn parse_glyphs(text: &str) -> Result<HashMap<char, Vec<Vec<bool>>>, String> {
+    let mut glyphs = HashMap::new();
+    let mut lines = text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with(";"));
+
+    while let Some(name) = lines.next() {
+        let mut chars = name.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err(format!("Expected a character, got `{}`", name)),
+        };
+
+        let mut rows = Vec::new();
+        for _ in 0..GLYPH_H {
+            let row = try!(lines.next().ok_or(format!("`{}` is too short", c)));
+            if row.len() != GLYPH_W || row.chars().any(|pixel| pixel != '#' && pixel != '.') {
+                return Err(format!("Bad row `{}` in `{}`", row, c));
+            }
+            rows.push(row.chars().map(|pixel| pixel == '#').collect());
+        }
+
+        glyphs.insert(c, rows);
+    }
+
+    Ok(glyphs)
+}
@@ -0,0 +1,78 @@
+use sdl2::joystick::Joystick;
+
+use seats::Gamepad;
+use wrapper_types::GameWindow;
+
+// Seconds between looking for gamepads being plugged in or pulled out.
+const SCAN_INTERVAL: f64 = 1.0;
+
+pub enum DeviceEvent {
+    Connected(Gamepad),
+    Disconnected(i32),
+}
+
+/// Keeps every plugged in gamepad open, so that the window sees its input.
+/// SDL only reports buttons on open joysticks, and the window doesn't pass
+/// on devices coming and going, so this checks for them now and then.
+pub struct Gamepads {
+    open: Vec<Joystick>,
+    wait: f64,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        Gamepads {
+            open: Vec::new(),
+            wait: 0.0,
+        }
+    }
+
+    /// Gamepads connected or disconnected since last time. The first update
+    /// reports everything already plugged in.
+    pub fn poll(&mut self, e: &GameWindow) -> Vec<DeviceEvent> {
+        let mut events = Vec::new();
+        let mut due = false;
+
+        e.update(|args| {
+            self.wait -= args.dt;
+            if self.wait <= 0.0 {
+                self.wait = SCAN_INTERVAL;
+                due = true;
+            }
+        });
+
+        if !due {
+            return events;
+        }
+
+        for joystick in &self.open {
+            if !joystick.attached() {
+                events.push(DeviceEvent::Disconnected(joystick.instance_id()));
+            }
+        }
+        self.open.retain(|joystick| joystick.attached());
+
+        let window = e.window.borrow();
+        let subsystem = match window.sdl_context.joystick() {
+            Ok(subsystem) => subsystem,
+            Err(_) => return events,
+        };
+
+        // Devices are listed by index, which shifts as they come and go, so
+        // open them all and keep the new ones.
+        for index in 0..subsystem.num_joysticks().unwrap_or(0) {
+            let joystick = match subsystem.open(index) {
+                Ok(joystick) => joystick,
+                Err(_) => continue,
+            };
+
+            let id = joystick.instance_id();
+            if !self.open.iter().any(|x| x.instance_id() == id) {
+                events.push(DeviceEvent::Connected(Gamepad::new(id, &joystick.name())));
+                self.open.push(joystick);
+            }
+        }
+
+        events
+    }
+}
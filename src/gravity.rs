@@ -0,0 +1,35 @@
+/// How fast falling blocks speed up, in cells per second squared. A block
+/// falls one cell in about 0.16 seconds and a whole board in about 0.57.
+pub const GRAVITY: f64 = 80.0;
+
+/// How far a block squashes on landing at full speed: wider and shorter by
+/// this fraction of its size.
+pub const SQUASH: f64 = 0.15;
+
+/// Seconds a block takes to squash on landing, and again to spring back.
+pub const SQUASH_TIME: f64 = 0.06;
+
+/// Blocks land at full speed from this many cells up, and squash less from
+/// lower down.
+pub const FULL_SQUASH_HEIGHT: f64 = 4.0;
+
+/// Seconds a block takes to fall some cells from rest. Falling in this
+/// time with quadratic easing keeps the acceleration constant.
+pub fn fall_time(cells: f64) -> f64 {
+    if cells <= 0.0 {
+        0.0
+    } else {
+        (2.0 * cells / GRAVITY).sqrt()
+    }
+}
+
+/// How much a block squashes after falling some cells, for a given full
+/// squash. Grows with landing speed, which grows with the root of the
+/// height.
+pub fn squash(cells: f64, amount: f64) -> f64 {
+    if cells <= 0.0 {
+        0.0
+    } else {
+        amount * (cells / FULL_SQUASH_HEIGHT).sqrt().min(1.0)
+    }
+}
@@ -0,0 +1,135 @@
+use board::Stats;
+use values::*;
+
+/// Seconds a damage number floats up for.
+pub const POPUP_TIME: f64 = 1.0;
+
+/// Seconds a chain stays up after it is reached.
+pub const CHAIN_TIME: f64 = 1.5;
+
+/// Seconds the attack sent by a combo stays up after it has gone.
+pub const SENDING_TIME: f64 = 1.0;
+
+/// Damage done by one step of a combo, floating up from where the blocks
+/// broke. The position is in grid cells, like a block's.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Popup {
+    pub damage: u32,
+    pub x: f64,
+    pub y: f64,
+    pub age: f64,
+}
+
+/// What is shown over a board besides the blocks: the score, the chain
+/// being played, damage numbers where blocks break, and the attack a combo
+/// is sending. Fed by the board's explosions, and caught up with the board
+/// once they have all been seen.
+#[derive(Clone, Debug)]
+pub struct Hud {
+    score: u32,
+    // Deepest chain of the current combo, and how long it has been up.
+    chain: u32,
+    chain_age: f64,
+    // Attack of the current or last combo, and how long since it was sent.
+    sending: u32,
+    sending_age: f64,
+    popups: Vec<Popup>,
+    // Blocks broken since the last update.
+    exploded: Vec<PositionedBlock>,
+    // The board's pending attack at the last update, to tell how much each
+    // step of a combo adds.
+    pending: u32,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Hud {
+            score: 0,
+            chain: 0,
+            chain_age: CHAIN_TIME,
+            sending: 0,
+            sending_age: SENDING_TIME,
+            popups: Vec::new(),
+            exploded: Vec::new(),
+            pending: 0,
+        }
+    }
+
+    pub fn score(&self) -> u32 { self.score }
+    pub fn popups(&self) -> &[Popup] { &self.popups }
+
+    /// The chain to show, from two up, and for how long it has been shown.
+    pub fn chain(&self) -> Option<(u32, f64)> {
+        if self.chain >= 2 && self.chain_age < CHAIN_TIME {
+            Some((self.chain, self.chain_age))
+        } else {
+            None
+        }
+    }
+
+    /// Attack the current combo is building, or the last one sent, while it
+    /// is still shown.
+    pub fn sending(&self) -> Option<u32> {
+        if self.sending > 0 && self.sending_age < SENDING_TIME {
+            Some(self.sending)
+        } else {
+            None
+        }
+    }
+
+    /// A block broke, as told by BlockEvent::Explode.
+    pub fn explode(&mut self, block: PositionedBlock) {
+        self.exploded.push(block);
+    }
+
+    /// Start over for a new board.
+    pub fn reset(&mut self) {
+        *self = Hud::new();
+    }
+
+    /// Move on by some seconds, catching up with the board. Blocks broken
+    /// since the last update make up one step of a combo, and whatever it
+    /// added to the pending attack floats up from their middle. Returns that
+    /// damage, or 0 if there was none.
+    pub fn update(&mut self, dt: f64, stats: Stats, chain: u32, pending: u32) -> u32 {
+        let mut result = 0;
+
+        for popup in self.popups.iter_mut() {
+            popup.age += dt;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_TIME);
+        self.chain_age += dt;
+        self.sending_age += dt;
+
+        if !self.exploded.is_empty() {
+            // A lower pending attack means the last combo was sent and this
+            // is a new one.
+            let damage = if pending >= self.pending { pending - self.pending } else { pending };
+
+            if damage > 0 {
+                result = damage;
+                let n = self.exploded.len() as f64;
+                self.popups.push(Popup {
+                    damage: damage,
+                    x: self.exploded.iter().map(|block| block.x() as f64).sum::<f64>() / n,
+                    y: self.exploded.iter().map(|block| block.y() as f64).sum::<f64>() / n,
+                    age: 0.0,
+                });
+            }
+            self.exploded.clear();
+        }
+
+        if chain != self.chain && chain > 0 {
+            self.chain = chain;
+            self.chain_age = 0.0;
+        }
+        if pending > 0 {
+            self.sending = pending;
+            self.sending_age = 0.0;
+        }
+
+        self.score = stats.score;
+        self.pending = pending;
+        result
+    }
+}
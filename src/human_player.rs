@@ -1,283 +1,160 @@
 use board::Board;
+use controls::{Action,Binding,PlayerControls};
+use input::*;
+use menu::MenuCommand;
 use piston_window::*;
-use values::*;
+use seats::SeatPad;
 use wrapper_types::*;
 
-use std::collections::{HashMap,LinkedList};
+fn to_binding(button: Button) -> Option<Binding> {
+    match button {
+        Button::Keyboard(key) => Some(Binding::Key(key as u32)),
+        Button::Joystick(button) => Some(Binding::Button(button.id, button.button)),
+        _ => None,
+    }
+}
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-enum InputAction {
-    AntiClockwise,
-    Clockwise,
-    Left,
-    Right,
-    Turbo,
-    DebugLoadBoard,
-    DebugAttack,
-    DebugBreaker(Color),
+/// A button or key pressed in this event, straight from the device.
+pub fn press_event(e: &GameWindow) -> Option<InputEvent> {
+    e.press_args().and_then(to_binding).map(InputEvent::Press)
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-enum JoystickDirection {
-    Left,
-    Right,
-    Up,
-    Down,
+/// A player at this computer. Translates window events into input events
+/// for PlayerInput, which does the rest. Reads the keyboard and the gamepad
+/// seated for it, if any.
+pub struct HumanPlayer {
+    input: PlayerInput,
+    controls: PlayerControls,
+    // Name of the profile playing in this seat, if any.
+    profile: Option<String>,
+    pad: Option<SeatPad>,
+    // Seconds of updates seen, to timestamp events with.
+    clock: f64,
 }
 
-impl JoystickDirection {
-    fn to_axis(&self) -> u8 {
-        match *self {
-            JoystickDirection::Left => 0,
-            JoystickDirection::Right => 0,
-            JoystickDirection::Up => 1,
-            JoystickDirection::Down => 1,
+impl HumanPlayer {
+    pub fn new(controls: &PlayerControls) -> Self {
+        HumanPlayer {
+            input: PlayerInput::new(controls),
+            controls: controls.clone(),
+            profile: None,
+            pad: None,
+            clock: 0.0,
         }
     }
 
-    fn from_axis(axis: u8, position: f64) -> Option<Self> {
-        if position > 0.0 {
-            match axis {
-                0 => Some(JoystickDirection::Right),
-                1 => Some(JoystickDirection::Down),
-                _ => {
-                    println!("Unknown joystick axis: {}", axis);
-                    None
-                }
-            }
-        } else {
-            match axis {
-                0 => Some(JoystickDirection::Left),
-                1 => Some(JoystickDirection::Up),
-                _ => {
-                    println!("Unknown joystick axis: {}", axis);
-                    None
-                }
-            }
-        }
+    pub fn controls(&self) -> &PlayerControls {
+        &self.controls
     }
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-enum InputButton {
-    Piston(Button),
-    Joystick(i32, JoystickDirection),
-}
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-enum InputEvent {
-    Press(InputButton),
-    Release(InputButton),
-}
+    pub fn set_controls(&mut self, controls: &PlayerControls) {
+        self.input = PlayerInput::new(controls);
+        self.controls = controls.clone();
+    }
 
-enum PressState {
-    Initial(f64),
-    Repeat(f64),
-    Release,
-}
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_ref().map(|name| &name[..])
+    }
 
-pub struct HumanPlayer {
-    input_map: HashMap<InputButton, InputAction>,
-    held: HashMap<InputButton, PressState>,
-}
+    /// Sit a profile in this seat, or no one in particular.
+    pub fn set_profile(&mut self, profile: Option<String>) {
+        self.profile = profile;
+    }
 
-impl HumanPlayer {
-    pub fn new(left: bool) -> Self {
-        let mut inputs = HashMap::new();
+    /// Use a different gamepad, or none. Buttons held on the old one are
+    /// forgotten.
+    pub fn set_pad(&mut self, pad: Option<SeatPad>) {
+        self.pad = pad;
+        self.input.clear();
+    }
 
-        if left {
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::W)), InputAction::AntiClockwise);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::S)), InputAction::Clockwise);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::A)), InputAction::Left);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D)), InputAction::Right);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::C)), InputAction::Turbo);
-            inputs.insert(InputButton::Piston(Button::Joystick(JoystickButton::new(0, 1))), InputAction::Clockwise);
-            inputs.insert(InputButton::Piston(Button::Joystick(JoystickButton::new(0, 3))), InputAction::AntiClockwise);
-            inputs.insert(InputButton::Joystick(0, JoystickDirection::Left), InputAction::Left);
-            inputs.insert(InputButton::Joystick(0, JoystickDirection::Right), InputAction::Right);
-            inputs.insert(InputButton::Joystick(0, JoystickDirection::Down), InputAction::Turbo);
-        } else {
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Up)), InputAction::AntiClockwise);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Down)), InputAction::Clockwise);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Left)), InputAction::Left);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Right)), InputAction::Right);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Space)), InputAction::Turbo);
-            inputs.insert(InputButton::Piston(Button::Joystick(JoystickButton::new(1, 1))), InputAction::Clockwise);
-            inputs.insert(InputButton::Piston(Button::Joystick(JoystickButton::new(1, 3))), InputAction::AntiClockwise);
-            inputs.insert(InputButton::Joystick(1, JoystickDirection::Left), InputAction::Left);
-            inputs.insert(InputButton::Joystick(1, JoystickDirection::Right), InputAction::Right);
-            inputs.insert(InputButton::Joystick(1, JoystickDirection::Down), InputAction::Turbo);
+    fn translate(&self, event: InputEvent) -> Option<InputEvent> {
+        match self.pad {
+            Some(ref pad) => pad.translate(event),
+            None => match event {
+                InputEvent::Press(Binding::Key(_)) | InputEvent::Release(Binding::Key(_)) => Some(event),
+                _ => None,
+            },
         }
+    }
+
+    /// The input newly pressed in this event, if any, whether or not it is
+    /// bound to anything. For rebinding controls.
+    pub fn pressed_binding(&self, e: &GameWindow) -> Option<Binding> {
+        let mut event = press_event(e);
 
-        if cfg!(debug_assertions) {
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::Q)), InputAction::DebugLoadBoard);
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D1)), InputAction::DebugBreaker(Color::Red));
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D2)), InputAction::DebugBreaker(Color::Green));
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D3)), InputAction::DebugBreaker(Color::Blue));
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D4)), InputAction::DebugBreaker(Color::Yellow));
-            inputs.insert(InputButton::Piston(Button::Keyboard(Key::D5)), InputAction::DebugAttack);
+        if let Some(JoystickAxisArgs { axis, position, id }) = e.joystick_axis_args() {
+            if position.abs() > self.controls.settings().dead_zone {
+                event = Some(InputEvent::Press(Binding::Axis(id, axis, position > 0.0)));
+            }
         }
 
-        HumanPlayer {
-            input_map: inputs,
-            held: HashMap::new(),
+        match event.and_then(|event| self.translate(event)) {
+            Some(InputEvent::Press(binding)) => Some(binding),
+            _ => None,
         }
     }
 
-    pub fn update(&mut self, e: &GameWindow, board: &mut Board) {
-        let mut events: LinkedList<InputEvent> = LinkedList::new();
-        let key_repeat = 0.05;
-        let initial_key_repeat = 0.2;
+    /// Commands from a single window event, including repeats of held
+    /// buttons when time passes, for modes that apply them themselves.
+    pub fn commands(&mut self, e: &GameWindow) -> Vec<Command> {
+        let mut events = Vec::new();
 
         e.update(|args| {
-            let mut to_delete = LinkedList::new();
-            for (button, state) in self.held.iter_mut() {
-                match *state {
-                    PressState::Initial(t) => {
-                        let t = t + args.dt;
-
-                        *state = if t > initial_key_repeat {
-                            events.push_back(InputEvent::Press(*button));
-                            PressState::Repeat(0.0)
-                        } else {
-                            PressState::Initial(t)
-                        }
-                    },
-                    PressState::Repeat(t) => {
-                        let t = t + args.dt;
-
-                        *state = if t > key_repeat {
-                            events.push_back(InputEvent::Press(*button));
-                            PressState::Repeat(0.0)
-                        } else {
-                            PressState::Repeat(t)
-                        }
-                    },
-                    PressState::Release => {
-                        to_delete.push_back(*button);
-                        events.push_back(InputEvent::Release(*button));
-                    }
-                }
-            }
-
-            for button in to_delete {
-                self.held.remove(&button);
-            }
+            self.clock += args.dt;
         });
 
         if let Some(JoystickAxisArgs { axis, position, id }) = e.joystick_axis_args() {
-            let dead_zone = 0.8;
-
-            if position.abs() > dead_zone {
-                let direction = JoystickDirection::from_axis(axis, position);
-
-                match direction {
-                    Some(direction) => {
-                        let button = InputButton::Joystick(id, direction);
-                        self.held.insert(button, PressState::Initial(0.0));
-                        events.push_back(InputEvent::Press(button));
-                    },
-                    None => {}
-                }
-            } else {
-                // Generate up events for all held directions for the axis.
-                for (button, state) in self.held.iter_mut() {
-                    match button {
-                        &InputButton::Joystick(held_id, direction) if
-                            id == held_id && axis == direction.to_axis() => {
-
-                            *state = PressState::Release;
-                        },
-                        _ => {}
-                    }
-                }
-            }
+            events.push(InputEvent::Axis(id, axis, position));
         }
-
-        if let Some(button) = e.release_args() {
-            let button = InputButton::Piston(button);
-            self.held.insert(button, PressState::Release);
-            events.push_back(InputEvent::Release(button));
+        if let Some(button) = e.release_args().and_then(to_binding) {
+            events.push(InputEvent::Release(button));
         }
-        if let Some(button) = e.press_args() {
-            let button = InputButton::Piston(button);
-            self.held.insert(button, PressState::Initial(0.0));
-            events.push_back(InputEvent::Press(button));
+        if let Some(button) = e.press_args().and_then(to_binding) {
+            events.push(InputEvent::Press(button));
         }
 
+        let events: Vec<InputEvent> = events.into_iter()
+            .filter_map(|event| self.translate(event))
+            .collect();
+
+        let mut commands = self.input.advance(self.clock);
         for event in events {
-            match event {
-                InputEvent::Press(button) => {
-                    if let Some(action) = self.input_map.get(&button) {
-                        match action {
-                            &InputAction::AntiClockwise => {
-                                board.rotate(Rotation::AntiClockwise);
-                            },
-                            &InputAction::Clockwise => {
-                                board.rotate(Rotation::Clockwise);
-                            },
-                            &InputAction::Left => {
-                                board.move_piece(|current| { current.offset(Direction::Left) });
-                            },
-                            &InputAction::Right => {
-                                board.move_piece(|current| { current.offset(Direction::Right) });
-                            },
-                            &InputAction::Turbo => {
-                                board.turbo(true);
-                            },
-                            &InputAction::DebugBreaker(color) => {
-                                if cfg!(debug_assertions) {
-                                    board.set_next_piece(Piece::new(
-                                        Block::new(color, true),
-                                        Block::new(color, true),
-                                    ))
-                                }
+            commands.extend(self.input.handle(TimedEvent::new(self.clock, event)));
+        }
+        commands
+    }
 
-                            },
-                            &InputAction::DebugAttack => {
-                                if cfg!(debug_assertions) {
-                                    board.attack(6);
-                                }
-                            },
-                            &InputAction::DebugLoadBoard => {
-                                if cfg!(debug_assertions) {
-                                    use std::io::{BufReader,BufRead};
-                                    use std::fs::File;
+    /// Navigate a menu rather than controlling a board. Rotating moves the
+    /// selection, turbo selects.
+    pub fn update_menu(&mut self, e: &GameWindow) -> Option<MenuCommand> {
+        let mut result = None;
+
+        for command in self.commands(e) {
+            result = match command {
+                Command::Press(Action::AntiClockwise) => Some(MenuCommand::Up),
+                Command::Press(Action::Clockwise)     => Some(MenuCommand::Down),
+                Command::Press(Action::Turbo)         => Some(MenuCommand::Select),
+                Command::Press(Action::Pause)         => Some(MenuCommand::Pause),
+                _ => result,
+            }
+        }
 
-                                    let f = File::open("board.txt");
+        result
+    }
 
-                                    match f {
-                                        Err(e) => {
-                                            println!("Could not open file: {}", e);
-                                        },
-                                        Ok(f) => {
-                                            let reader = BufReader::new(&f);
-                                            let eol: &[_] = &['\n', '\r'];
-                                            let lines: Vec<_> = reader.lines().map(|x| {
-                                                x.ok().unwrap()
-                                                    .trim_right_matches(eol)
-                                                    .to_string()
-                                            }).collect();
-                                            board.add_blocks(lines);
-                                            board.fuse_blocks();
-                                        }
-                                    }
-                                }
-                            },
-                        }
-                    }
-                },
-                InputEvent::Release(button) => {
-                    if let Some(action) = self.input_map.get(&button) {
-                        match action {
-                            &InputAction::Turbo => {
-                                board.turbo(false)
-                            },
-                            _ => {},
-                        }
-                    }
-                },
+    /// Apply input to the board, and show it as many upcoming pieces as
+    /// the player likes. Returns MenuCommand::Pause if the player
+    /// asked to pause, in which case the remaining input is dropped.
+    pub fn update(&mut self, e: &GameWindow, board: &mut Board) -> Option<MenuCommand> {
+        board.set_preview(self.controls.settings().preview);
+
+        for command in self.commands(e) {
+            if self.input.apply(command, board) {
+                return Some(MenuCommand::Pause);
             }
         }
+
+        None
     }
 }
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use board::Board;
+use controls::{Action,Binding,PlayerControls};
+use settings::PlayerSettings;
+use values::*;
+
+enum PressState {
+    Initial(f64),
+    Repeat(f64),
+}
+
+/// Generates repeat presses for held buttons: the first after the DAS delay,
+/// then one every ARR interval until released. Knows nothing of where the
+/// presses come from, so timings can be tested without a window.
+pub struct Repeater<T> {
+    das: f64,
+    arr: f64,
+    held: HashMap<T, PressState>,
+}
+
+impl<T: Copy + Eq + Hash> Repeater<T> {
+    pub fn new(settings: &PlayerSettings) -> Self {
+        Repeater {
+            das: settings.das,
+            arr: settings.arr,
+            held: HashMap::new(),
+        }
+    }
+
+    pub fn press(&mut self, button: T) {
+        self.held.insert(button, PressState::Initial(0.0));
+    }
+
+    pub fn release(&mut self, button: T) {
+        self.held.remove(&button);
+    }
+
+    pub fn is_held(&self, button: T) -> bool {
+        self.held.contains_key(&button)
+    }
+
+    /// Forget all held buttons, for when their releases won't be seen.
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    /// Advance time, returning the buttons that repeat. A button repeats at
+    /// most once per update.
+    pub fn update(&mut self, dt: f64) -> Vec<T> {
+        let mut repeats = Vec::new();
+
+        for (button, state) in self.held.iter_mut() {
+            *state = match *state {
+                PressState::Initial(t) => {
+                    let t = t + dt;
+
+                    if t > self.das {
+                        repeats.push(*button);
+                        PressState::Repeat(0.0)
+                    } else {
+                        PressState::Initial(t)
+                    }
+                },
+                PressState::Repeat(t) => {
+                    let t = t + dt;
+
+                    if t > self.arr {
+                        repeats.push(*button);
+                        PressState::Repeat(0.0)
+                    } else {
+                        PressState::Repeat(t)
+                    }
+                },
+            }
+        }
+
+        repeats
+    }
+}
+
+/// A backend independent input event.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Press(Binding),
+    Release(Binding),
+    // Gamepad id, axis and position from -1 to 1.
+    Axis(i32, u8, f64),
+}
+
+/// An input event and when it happened, in seconds from any fixed point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TimedEvent {
+    pub time: f64,
+    pub event: InputEvent,
+}
+
+impl TimedEvent {
+    pub fn new(time: f64, event: InputEvent) -> Self {
+        TimedEvent {
+            time: time,
+            event: event,
+        }
+    }
+}
+
+/// What a player asked for. Held buttons send repeated presses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    Press(Action),
+    Release(Action),
+}
+
+/// Turns one player's input events into commands, using their bindings
+/// and settings, and applies commands to a board.
+pub struct PlayerInput {
+    bindings: HashMap<Binding, Action>,
+    settings: PlayerSettings,
+    repeater: Repeater<Binding>,
+    time: f64,
+}
+
+impl PlayerInput {
+    pub fn new(controls: &PlayerControls) -> Self {
+        PlayerInput {
+            bindings: controls.bindings().iter().cloned().collect(),
+            settings: controls.settings(),
+            repeater: Repeater::new(&controls.settings()),
+            time: 0.0,
+        }
+    }
+
+    /// Move the clock forward, returning repeats of held buttons.
+    pub fn advance(&mut self, time: f64) -> Vec<Command> {
+        let dt = time - self.time;
+        if dt <= 0.0 {
+            return Vec::new();
+        }
+        self.time = time;
+
+        self.repeater.update(dt).into_iter()
+            .filter_map(|button| self.bindings.get(&button).map(|&a| Command::Press(a)))
+            .collect()
+    }
+
+    /// Handle an event, after any repeats due before it. Events must be in
+    /// time order; earlier ones are treated as happening now.
+    pub fn handle(&mut self, event: TimedEvent) -> Vec<Command> {
+        let mut commands = self.advance(event.time);
+
+        match event.event {
+            InputEvent::Press(button) => self.press(button, &mut commands),
+            InputEvent::Release(button) => self.release(button, &mut commands),
+            InputEvent::Axis(id, axis, position) => {
+                let pushed = position.abs() > self.settings.dead_zone;
+
+                // Returning to the middle releases both directions, pushing
+                // one way releases the other.
+                for &positive in &[true, false] {
+                    if !pushed || (position > 0.0) != positive {
+                        self.release(Binding::Axis(id, axis, positive), &mut commands);
+                    }
+                }
+
+                if pushed {
+                    self.press(Binding::Axis(id, axis, position > 0.0), &mut commands);
+                }
+            },
+        }
+
+        commands
+    }
+
+    fn press(&mut self, button: Binding, commands: &mut Vec<Command>) {
+        if let Some(&action) = self.bindings.get(&button) {
+            if !self.repeater.is_held(button) {
+                self.repeater.press(button);
+                commands.push(Command::Press(action));
+            }
+        }
+    }
+
+    fn release(&mut self, button: Binding, commands: &mut Vec<Command>) {
+        if self.repeater.is_held(button) {
+            self.repeater.release(button);
+            if let Some(&action) = self.bindings.get(&button) {
+                commands.push(Command::Release(action));
+            }
+        }
+    }
+
+    /// Forget held buttons, for when their releases won't be seen.
+    pub fn clear(&mut self) {
+        self.repeater.clear();
+    }
+
+    /// Apply a command to a board. Returns true if the player asked to
+    /// pause, which is left to the caller.
+    pub fn apply(&mut self, command: Command, board: &mut Board) -> bool {
+        let pause = apply(command, board, &self.settings);
+        if pause {
+            self.clear();
+        }
+        pause
+    }
+}
+
+/// Apply a command to a board for a player with the given settings. Returns
+/// true if the player asked to pause, which is left to the caller.
+pub fn apply(command: Command, board: &mut Board, settings: &PlayerSettings) -> bool {
+    match command {
+        Command::Press(Action::AntiClockwise) => {
+            board.rotate(Rotation::AntiClockwise);
+        },
+        Command::Press(Action::Clockwise) => {
+            board.rotate(Rotation::Clockwise);
+        },
+        Command::Press(Action::Left) => {
+            board.move_piece(|current| { current.offset(Direction::Left) });
+        },
+        Command::Press(Action::Right) => {
+            board.move_piece(|current| { current.offset(Direction::Right) });
+        },
+        Command::Press(Action::Turbo) => {
+            board.set_soft_drop(settings.soft_drop);
+            board.turbo(true);
+        },
+        Command::Release(Action::Turbo) => {
+            board.turbo(false);
+        },
+        Command::Press(Action::Pause) => {
+            // Buttons released while paused are never seen by the board,
+            // so don't leave it stuck in turbo.
+            board.turbo(false);
+            return true;
+        },
+        Command::Release(_) => {},
+    }
+
+    false
+}
@@ -0,0 +1,148 @@
+use graphics::{Context,Transformed};
+
+use values::*;
+
+/// Space between a board's frame and its preview panel.
+pub const PANEL_GAP: f64 = 8.0;
+
+/// Space inside the preview panel around the pieces, and the width of the
+/// border drawn around it.
+pub const PANEL_PADDING: f64 = 4.0;
+pub const PANEL_BORDER: f64 = 2.0;
+
+/// Width of a preview panel, border and all, for cells of a given width.
+pub fn panel_width(cell_w: f64) -> f64 {
+    cell_w + (PANEL_PADDING + PANEL_BORDER) * 2.0
+}
+
+/// Where everything goes in a window of any size. Boards are laid out on a
+/// canvas with room for two of them, their preview panels, the text above
+/// them and the HUD below. The canvas is scaled to fit the window, keeping
+/// its shape, and centered in it.
+///
+/// Positions are in view units: window pixels divided by the scale. Draw
+/// through `apply` to use them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Layout {
+    dimensions: Dimension,
+    cell: Dimension,
+    frame: f64,
+    window: [f64; 2],
+}
+
+impl Layout {
+    /// Boards of some dimensions, with cells of the given size, framed by a
+    /// board texture of the given size. The window starts out the size of
+    /// the canvas.
+    pub fn new(dimensions: Dimension, cell: Dimension, board: Dimension) -> Self {
+        let grid_w = (cell.w() * dimensions.w()) as f64;
+
+        let mut layout = Layout {
+            dimensions: dimensions,
+            cell: cell,
+            frame: ((board.w() as f64 - grid_w) / 2.0).max(0.0),
+            window: [0.0, 0.0],
+        };
+        layout.window = layout.canvas_size();
+        layout
+    }
+
+    /// The window is now this many pixels. A minimized window can be
+    /// reported as empty, and is ignored.
+    pub fn resize(&mut self, w: u32, h: u32) {
+        if w > 0 && h > 0 {
+            self.window = [w as f64, h as f64];
+        }
+    }
+
+    pub fn window_size(&self) -> [f64; 2] { self.window }
+
+    /// Width of the frame around the grid in the board texture.
+    pub fn frame(&self) -> f64 { self.frame }
+
+    fn cell_w(&self) -> f64 { self.cell.w() as f64 }
+    fn cell_h(&self) -> f64 { self.cell.h() as f64 }
+
+    // A board and its preview panel.
+    fn slot_w(&self) -> f64 {
+        panel_width(self.cell_w()) + PANEL_GAP + self.frame * 2.0 +
+            self.cell_w() * self.dimensions.w() as f64
+    }
+
+    // Around and between the boards.
+    fn margin(&self) -> f64 { self.cell_w() }
+
+    // Above the board's frame, for the text there.
+    fn header(&self) -> f64 { self.cell_h() }
+
+    // Below the board's frame, for the HUD.
+    fn footer(&self) -> f64 { self.cell_h() * 2.0 }
+
+    /// Size of everything at a scale of one.
+    pub fn canvas_size(&self) -> [f64; 2] {
+        [
+            self.margin() * 3.0 + self.slot_w() * 2.0,
+            self.header() + self.frame * 2.0 + self.cell_h() * self.dimensions.h() as f64 + self.footer(),
+        ]
+    }
+
+    /// Window pixels to a view unit: as large as the canvas can be while
+    /// still fitting in the window.
+    pub fn scale(&self) -> f64 {
+        let canvas = self.canvas_size();
+        (self.window[0] / canvas[0]).min(self.window[1] / canvas[1])
+    }
+
+    /// The whole window in view units. At least the size of the canvas, and
+    /// larger along whichever side the window has room to spare.
+    pub fn view_size(&self) -> [f64; 2] {
+        let scale = self.scale();
+        [self.window[0] / scale, self.window[1] / scale]
+    }
+
+    /// Top left of the canvas.
+    pub fn origin(&self) -> PixelPosition {
+        let (view, canvas) = (self.view_size(), self.canvas_size());
+        PixelPosition::new((view[0] - canvas[0]) / 2.0, (view[1] - canvas[1]) / 2.0)
+    }
+
+    /// Top of the canvas, where text above the boards goes.
+    pub fn top(&self) -> f64 {
+        self.origin().y()
+    }
+
+    // Top left of the grid of a board whose slot starts at x on the canvas,
+    // with its preview panel to the left.
+    fn grid_at(&self, x: f64) -> PixelPosition {
+        let origin = self.origin();
+        PixelPosition::new(
+            origin.x() + x + panel_width(self.cell_w()) + PANEL_GAP + self.frame,
+            origin.y() + self.header() + self.frame)
+    }
+
+    /// Top left of the left player's grid.
+    pub fn left(&self) -> PixelPosition {
+        self.grid_at(self.margin())
+    }
+
+    /// Top left of the right player's grid, which has its preview panel to
+    /// the right.
+    pub fn right(&self) -> PixelPosition {
+        let origin = self.origin();
+        PixelPosition::new(
+            origin.x() + self.margin() * 2.0 + self.slot_w() + self.frame,
+            origin.y() + self.header() + self.frame)
+    }
+
+    /// Top left of the grid of a board played alone.
+    pub fn center(&self) -> PixelPosition {
+        self.grid_at((self.canvas_size()[0] - self.slot_w()) / 2.0)
+    }
+
+    /// Draw in view units rather than window pixels. The view size of the
+    /// result is the whole window, for centering things in it.
+    pub fn apply(&self, c: &Context) -> Context {
+        let scale = self.scale();
+        c.scale(scale, scale).store_view()
+    }
+}
@@ -3,12 +3,47 @@
 extern crate bitflags;
 
 pub mod textures;
+pub mod themes;
 mod block_grid;
 mod values;
 pub mod board;
 pub mod board_renderer;
 pub mod human_player;
 mod wrapper_types;
+pub mod font;
+pub mod hud;
+pub mod layout;
+pub mod gravity;
+pub mod effects;
+pub mod projectile;
+pub mod menu;
+pub mod mode;
+pub mod match_controller;
+pub mod versus;
+pub mod progression;
+pub mod endless;
+pub mod puzzle;
+pub mod solver;
+pub mod puzzle_mode;
+pub mod records;
+pub mod time_attack;
+pub mod training;
+pub mod controls;
+pub mod controls_screen;
+pub mod settings;
+pub mod input;
+pub mod seats;
+pub mod gamepads;
+pub mod lockstep;
+pub mod net;
+pub mod rollback;
+pub mod online;
+pub mod broadcast;
+pub mod spectate;
+pub mod lobby;
+pub mod matchmaking;
+pub mod profiles;
+pub mod players_screen;
 
 pub use self::board::*;
 pub use self::values::*;
@@ -16,6 +51,7 @@ pub use self::block_grid::*;
 
 extern crate piston_window;
 extern crate sdl2_window;
+extern crate sdl2;
 extern crate uuid;
 extern crate graphics;
 extern crate find_folder;
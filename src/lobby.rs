@@ -0,0 +1,425 @@
+extern crate rand;
+
+use std::collections::{BTreeMap,VecDeque};
+use std::fs::{self,OpenOptions};
+use std::io::Write;
+use std::net::{IpAddr,SocketAddr};
+use std::path::PathBuf;
+
+use self::rand::{Rng,SeedableRng,XorShiftRng};
+
+use net::{Connection,Host};
+
+pub const DEFAULT_LOBBY_PORT: u16 = 7700;
+
+/// A line sent between the lobby server and a game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LobbyMessage {
+    // From a game: wait for a match under this name, hosting it on this
+    // port if asked. Sent again after a match to wait for another.
+    Hello { name: String, port: u16 },
+    // From the lobby: everyone waiting, whenever that changes.
+    Waiting(Vec<String>),
+    // From the lobby: host a match with these rules.
+    Host { id: u32, seed: u64, best_of: u32, opponent: String },
+    // From the lobby: join a match hosted at `address:port`.
+    Join { id: u32, address: String, opponent: String },
+    // From a game: who won a match it played.
+    Result { id: u32, winner: String },
+    // From the lobby: the last message was refused.
+    Error(String),
+}
+
+impl LobbyMessage {
+    /// Parse a line, without its newline: `hello <name> <port>`, `waiting
+    /// [name ...]`, `host <id> <seed> <best of> <opponent>`, `join <id>
+    /// <address> <opponent>`, `result <id> <winner>` or `error <reason>`.
+    /// Names can't contain spaces.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let error = || format!("Bad message `{}`", line);
+
+        match (words.get(0), words.len()) {
+            (Some(&"hello"), 3) => {
+                let port = try!(words[2].parse().map_err(|_| error()));
+                Ok(LobbyMessage::Hello { name: words[1].to_string(), port: port })
+            },
+            (Some(&"waiting"), _) => {
+                Ok(LobbyMessage::Waiting(words[1..].iter().map(|name| name.to_string()).collect()))
+            },
+            (Some(&"host"), 5) => {
+                match (words[1].parse(), words[2].parse(), words[3].parse()) {
+                    (Ok(id), Ok(seed), Ok(best_of)) => Ok(LobbyMessage::Host {
+                        id: id,
+                        seed: seed,
+                        best_of: best_of,
+                        opponent: words[4].to_string(),
+                    }),
+                    _ => Err(error()),
+                }
+            },
+            (Some(&"join"), 4) => {
+                let id = try!(words[1].parse().map_err(|_| error()));
+                Ok(LobbyMessage::Join {
+                    id: id,
+                    address: words[2].to_string(),
+                    opponent: words[3].to_string(),
+                })
+            },
+            (Some(&"result"), 3) => {
+                let id = try!(words[1].parse().map_err(|_| error()));
+                Ok(LobbyMessage::Result { id: id, winner: words[2].to_string() })
+            },
+            (Some(&"error"), n) if n >= 2 => Ok(LobbyMessage::Error(words[1..].join(" "))),
+            _ => Err(error()),
+        }
+    }
+
+    /// Inverse of parse.
+    pub fn to_line(&self) -> String {
+        match *self {
+            LobbyMessage::Hello { ref name, port } => format!("hello {} {}", name, port),
+            LobbyMessage::Waiting(ref names) => {
+                let mut line = "waiting".to_string();
+                for name in names {
+                    line.push(' ');
+                    line.push_str(name);
+                }
+                line
+            },
+            LobbyMessage::Host { id, seed, best_of, ref opponent } =>
+                format!("host {} {} {} {}", id, seed, best_of, opponent),
+            LobbyMessage::Join { id, ref address, ref opponent } =>
+                format!("join {} {} {}", id, address, opponent),
+            LobbyMessage::Result { id, ref winner } => format!("result {} {}", id, winner),
+            LobbyMessage::Error(ref reason) => format!("error {}", reason),
+        }
+    }
+}
+
+/// Identifies a connection to the lobby.
+pub type ClientId = u32;
+
+/// How a match went. There is no winner if the players disagree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResult {
+    pub id: u32,
+    pub host: String,
+    pub guest: String,
+    pub winner: Option<String>,
+}
+
+impl MatchResult {
+    /// `<id> <host> <guest> <winner>`, with `disputed` for no winner.
+    pub fn to_line(&self) -> String {
+        let winner = self.winner.as_ref().map(|name| &name[..]).unwrap_or("disputed");
+        format!("{} {} {} {}", self.id, self.host, self.guest, winner)
+    }
+}
+
+struct Player {
+    name: String,
+    // Where the player's game can be reached for hosting.
+    address: String,
+}
+
+struct Match {
+    host: String,
+    guest: String,
+    // Players yet to leave the match, by waiting for another or going.
+    playing: Vec<ClientId>,
+    // Winner as reported by each player.
+    reports: BTreeMap<ClientId, String>,
+}
+
+/// Pairs waiting players and collects results, knowing nothing of the
+/// network. Each call returns the messages to send, and to whom.
+pub struct Lobby {
+    best_of: u32,
+    rng: XorShiftRng,
+    players: BTreeMap<ClientId, Player>,
+    // First come, first paired.
+    waiting: VecDeque<ClientId>,
+    matches: BTreeMap<u32, Match>,
+    next_match: u32,
+    results: Vec<MatchResult>,
+}
+
+impl Lobby {
+    /// Matches are best of the given number of rounds, with seeds drawn
+    /// from a generator with the given seed.
+    pub fn new(best_of: u32, seed: u32) -> Self {
+        Lobby {
+            best_of: best_of,
+            rng: XorShiftRng::from_seed([seed, 0x9E37_79B9, 0x7F4A_7C15, 1]),
+            players: BTreeMap::new(),
+            waiting: VecDeque::new(),
+            matches: BTreeMap::new(),
+            next_match: 1,
+            results: Vec::new(),
+        }
+    }
+
+    /// Names of the players waiting, in the order they will be paired.
+    pub fn waiting(&self) -> Vec<String> {
+        self.waiting.iter().map(|client| self.players[client].name.clone()).collect()
+    }
+
+    /// Results of matches finished since the last call.
+    pub fn results(&mut self) -> Vec<MatchResult> {
+        self.results.drain(..).collect()
+    }
+
+    /// A message from a client connected from the given IP address.
+    pub fn handle(&mut self, client: ClientId, ip: &str, message: LobbyMessage) -> Vec<(ClientId, LobbyMessage)> {
+        let error = |reason: &str| vec!((client, LobbyMessage::Error(reason.to_string())));
+
+        match message {
+            LobbyMessage::Hello { name, port } => {
+                let taken = self.players.iter().any(|(&other, player)| other != client && player.name == name);
+                if taken {
+                    return error("That name is taken");
+                }
+                if self.waiting.contains(&client) {
+                    return error("Already waiting");
+                }
+
+                // Done with a match, whether or not the other player has
+                // reported it yet.
+                if let Some(id) = self.match_of(client) {
+                    self.leave(id, client);
+                }
+
+                let address = match ip.parse::<IpAddr>() {
+                    Ok(ip) => SocketAddr::new(ip, port).to_string(),
+                    Err(_) => format!("{}:{}", ip, port),
+                };
+                self.players.insert(client, Player {
+                    name: name,
+                    address: address,
+                });
+                self.waiting.push_back(client);
+                self.pair()
+            },
+            LobbyMessage::Result { id, winner } => {
+                let valid = match self.matches.get(&id) {
+                    Some(game) => game.playing.contains(&client) &&
+                        (game.host == winner || game.guest == winner),
+                    None => false,
+                };
+                if !valid {
+                    return error("No such match");
+                }
+
+                let finished = {
+                    let game = self.matches.get_mut(&id).unwrap();
+                    game.reports.insert(client, winner);
+                    game.reports.len() == 2
+                };
+                if finished {
+                    self.finish(id);
+                }
+                Vec::new()
+            },
+            _ => error("Unexpected message"),
+        }
+    }
+
+    /// A client has gone. A match it was in is settled by the reports made
+    /// so far, if there were any.
+    pub fn disconnect(&mut self, client: ClientId) -> Vec<(ClientId, LobbyMessage)> {
+        let was_waiting = self.waiting.contains(&client);
+        self.waiting.retain(|&other| other != client);
+
+        if let Some(id) = self.match_of(client) {
+            self.finish(id);
+        }
+        self.players.remove(&client);
+
+        if was_waiting {
+            self.waiting_list()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn match_of(&self, client: ClientId) -> Option<u32> {
+        self.matches.iter()
+            .find(|&(_, game)| game.playing.contains(&client))
+            .map(|(&id, _)| id)
+    }
+
+    // A player has moved on from a match, keeping any report they made. The
+    // match is settled once both have.
+    fn leave(&mut self, id: u32, client: ClientId) {
+        let empty = match self.matches.get_mut(&id) {
+            Some(game) => {
+                game.playing.retain(|&other| other != client);
+                game.playing.is_empty()
+            },
+            None => return,
+        };
+        if empty {
+            self.finish(id);
+        }
+    }
+
+    // Record a match, agreed or not. A match nobody reported is dropped.
+    fn finish(&mut self, id: u32) {
+        let game = match self.matches.remove(&id) {
+            Some(game) => game,
+            None => return,
+        };
+
+        let mut reports = game.reports.values();
+        let winner = match (reports.next(), reports.next()) {
+            (Some(first), None) => Some(first.clone()),
+            (Some(first), Some(second)) if first == second => Some(first.clone()),
+            (Some(_), Some(_)) => None,
+            (None, _) => return,
+        };
+
+        self.results.push(MatchResult {
+            id: id,
+            host: game.host,
+            guest: game.guest,
+            winner: winner,
+        });
+    }
+
+    // Start matches for everyone who can be paired, the first to arrive
+    // hosting.
+    fn pair(&mut self) -> Vec<(ClientId, LobbyMessage)> {
+        let mut messages = Vec::new();
+
+        while self.waiting.len() >= 2 {
+            let host = self.waiting.pop_front().unwrap();
+            let guest = self.waiting.pop_front().unwrap();
+            let id = self.next_match;
+            self.next_match += 1;
+
+            messages.push((host, LobbyMessage::Host {
+                id: id,
+                seed: self.rng.gen(),
+                best_of: self.best_of,
+                opponent: self.players[&guest].name.clone(),
+            }));
+            messages.push((guest, LobbyMessage::Join {
+                id: id,
+                address: self.players[&host].address.clone(),
+                opponent: self.players[&host].name.clone(),
+            }));
+
+            self.matches.insert(id, Match {
+                host: self.players[&host].name.clone(),
+                guest: self.players[&guest].name.clone(),
+                playing: vec!(host, guest),
+                reports: BTreeMap::new(),
+            });
+        }
+
+        messages.extend(self.waiting_list());
+        messages
+    }
+
+    fn waiting_list(&self) -> Vec<(ClientId, LobbyMessage)> {
+        let names = self.waiting();
+        self.waiting.iter().map(|&client| (client, LobbyMessage::Waiting(names.clone()))).collect()
+    }
+}
+
+/// Runs a lobby for games connecting over TCP, appending results to a file
+/// as matches finish.
+pub struct Server {
+    host: Host,
+    clients: BTreeMap<ClientId, Connection>,
+    next_client: ClientId,
+    lobby: Lobby,
+    results_path: Option<PathBuf>,
+}
+
+impl Server {
+    pub fn listen(port: u16, best_of: u32, results_path: Option<PathBuf>) -> Result<Self, String> {
+        Ok(Server {
+            host: try!(Host::listen(port)),
+            clients: BTreeMap::new(),
+            next_client: 1,
+            lobby: Lobby::new(best_of, rand::random()),
+            results_path: results_path,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.host.port()
+    }
+
+    /// Take in new connections and messages, and send the replies. Returns
+    /// results of matches finished since the last call.
+    pub fn poll(&mut self) -> Result<Vec<MatchResult>, String> {
+        while let Some(connection) = try!(self.host.accept()) {
+            self.clients.insert(self.next_client, connection);
+            self.next_client += 1;
+        }
+
+        let mut outgoing = Vec::new();
+        let mut gone = Vec::new();
+
+        for (&client, connection) in self.clients.iter_mut() {
+            let ip = connection.peer_ip().unwrap_or("127.0.0.1".to_string());
+
+            match connection.receive_lines() {
+                Ok(lines) => {
+                    for line in lines {
+                        match LobbyMessage::parse(&line) {
+                            Ok(message) => outgoing.extend(self.lobby.handle(client, &ip, message)),
+                            Err(e) => outgoing.push((client, LobbyMessage::Error(e))),
+                        }
+                    }
+                },
+                Err(_) => gone.push(client),
+            }
+        }
+
+        for client in gone {
+            self.clients.remove(&client);
+            outgoing.extend(self.lobby.disconnect(client));
+        }
+
+        // Losing a client while sending may mean more to tell the others.
+        let mut outgoing: VecDeque<_> = outgoing.into_iter().collect();
+        while let Some((client, message)) = outgoing.pop_front() {
+            let sent = match self.clients.get_mut(&client) {
+                Some(connection) => connection.send_line(&message.to_line()).is_ok(),
+                None => true,
+            };
+            if !sent {
+                self.clients.remove(&client);
+                outgoing.extend(self.lobby.disconnect(client));
+            }
+        }
+
+        let results = self.lobby.results();
+        if !results.is_empty() {
+            try!(self.save(&results));
+        }
+        Ok(results)
+    }
+
+    fn save(&self, results: &[MatchResult]) -> Result<(), String> {
+        let path = match self.results_path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            try!(fs::create_dir_all(dir).map_err(|e| e.to_string()));
+        }
+        let mut file = try!(OpenOptions::new().create(true).append(true).open(path)
+            .map_err(|e| format!("Could not open {}: {}", path.display(), e)));
+
+        for result in results {
+            try!(writeln!(file, "{}", result.to_line()).map_err(|e| e.to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,337 @@
+use board::Board;
+use board_renderer::RenderState;
+use controls::Action;
+use input::{self,Command};
+use match_controller::*;
+use settings::PlayerSettings;
+use values::*;
+
+/// Seconds simulated per tick. Both sides step by exactly this much, so
+/// that their boards stay the same.
+pub const TICK: f64 = 1.0 / 60.0;
+
+/// Ticks between a player's input and it being played, to give it time to
+/// reach the other side. Anything later than this is covered by rollback.
+pub const INPUT_DELAY: u64 = 2;
+
+/// Ticks between checks that both sides still agree.
+pub const HASH_INTERVAL: u64 = 60;
+
+/// Bumped whenever the messages or simulation change, so that different
+/// versions don't try to play each other.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// A line sent between the two sides of an online match.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    // Sent by both sides on connecting, with their soft drop speed. The
+    // host's seed and match length are the ones used.
+    Hello { version: u32, seed: u64, best_of: u32, soft_drop: f64 },
+    // A player's commands for a tick.
+    Input(u64, Vec<Command>),
+    // Checksum of both boards after a tick.
+    Hash(u64, u64),
+    // Sent by a spectator instead of a hello.
+    Watch { version: u32 },
+    // Sent by the host to spectators: the match being played.
+    Setup { version: u32, seed: u64, best_of: u32, left_soft_drop: f64, right_soft_drop: f64 },
+    // A round started on the given tick, with the score so far.
+    Round { round: u32, left_wins: u32, right_wins: u32, tick: u64 },
+    // Both players' commands for a tick both sides agree on.
+    Play(u64, Vec<Command>, Vec<Command>),
+    // The other side has quit.
+    Bye,
+}
+
+fn command_to_word(command: Command) -> String {
+    match command {
+        Command::Press(action) => format!("+{}", action.key()),
+        Command::Release(action) => format!("-{}", action.key()),
+    }
+}
+
+fn command_from_word(word: &str) -> Option<Command> {
+    let action = |key: &str| Action::all().into_iter().find(|action| action.key() == key);
+
+    if word.starts_with("+") {
+        action(&word[1..]).map(Command::Press)
+    } else if word.starts_with("-") {
+        action(&word[1..]).map(Command::Release)
+    } else {
+        None
+    }
+}
+
+impl Message {
+    /// Parse a line, without its newline: `hello 1 <seed> <best of> <soft
+    /// drop>`, `input <tick> [+action|-action ...]`, `hash <tick> <hex>`,
+    /// `watch <version>`, `setup <version> <seed> <best of> <soft drop>
+    /// <soft drop>`, `round <round> <wins> <wins> <tick>`, `play <tick>
+    /// [commands] / [commands]` or `bye`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let error = || format!("Bad message `{}`", line);
+
+        match (words.get(0), words.len()) {
+            (Some(&"hello"), 5) => {
+                match (words[1].parse(), words[2].parse(), words[3].parse(), words[4].parse()) {
+                    (Ok(version), Ok(seed), Ok(best_of), Ok(soft_drop)) => Ok(Message::Hello {
+                        version: version,
+                        seed: seed,
+                        best_of: best_of,
+                        soft_drop: soft_drop,
+                    }),
+                    _ => Err(error()),
+                }
+            },
+            (Some(&"input"), n) if n >= 2 => {
+                let tick = try!(words[1].parse().map_err(|_| error()));
+                let mut commands = Vec::new();
+
+                for word in &words[2..] {
+                    commands.push(try!(command_from_word(word).ok_or(error())));
+                }
+
+                Ok(Message::Input(tick, commands))
+            },
+            (Some(&"hash"), 3) => {
+                match (words[1].parse(), u64::from_str_radix(words[2], 16)) {
+                    (Ok(tick), Ok(hash)) => Ok(Message::Hash(tick, hash)),
+                    _ => Err(error()),
+                }
+            },
+            (Some(&"watch"), 2) => {
+                let version = try!(words[1].parse().map_err(|_| error()));
+                Ok(Message::Watch { version: version })
+            },
+            (Some(&"setup"), 6) => {
+                match (words[1].parse(), words[2].parse(), words[3].parse(), words[4].parse(), words[5].parse()) {
+                    (Ok(version), Ok(seed), Ok(best_of), Ok(left_soft_drop), Ok(right_soft_drop)) => Ok(Message::Setup {
+                        version: version,
+                        seed: seed,
+                        best_of: best_of,
+                        left_soft_drop: left_soft_drop,
+                        right_soft_drop: right_soft_drop,
+                    }),
+                    _ => Err(error()),
+                }
+            },
+            (Some(&"round"), 5) => {
+                match (words[1].parse(), words[2].parse(), words[3].parse(), words[4].parse()) {
+                    (Ok(round), Ok(left_wins), Ok(right_wins), Ok(tick)) => Ok(Message::Round {
+                        round: round,
+                        left_wins: left_wins,
+                        right_wins: right_wins,
+                        tick: tick,
+                    }),
+                    _ => Err(error()),
+                }
+            },
+            (Some(&"play"), n) if n >= 3 => {
+                let tick = try!(words[1].parse().map_err(|_| error()));
+                let split = try!(words.iter().position(|&word| word == "/").ok_or(error()));
+                let mut left = Vec::new();
+                let mut right = Vec::new();
+
+                for word in &words[2..split] {
+                    left.push(try!(command_from_word(word).ok_or(error())));
+                }
+                for word in &words[split + 1..] {
+                    right.push(try!(command_from_word(word).ok_or(error())));
+                }
+
+                Ok(Message::Play(tick, left, right))
+            },
+            (Some(&"bye"), 1) => Ok(Message::Bye),
+            _ => Err(error()),
+        }
+    }
+
+    /// Inverse of parse.
+    pub fn to_line(&self) -> String {
+        match *self {
+            Message::Hello { version, seed, best_of, soft_drop } =>
+                format!("hello {} {} {} {}", version, seed, best_of, soft_drop),
+            Message::Input(tick, ref commands) => {
+                let mut line = format!("input {}", tick);
+                for &command in commands {
+                    line.push(' ');
+                    line.push_str(&command_to_word(command));
+                }
+                line
+            },
+            Message::Hash(tick, hash) => format!("hash {} {:016x}", tick, hash),
+            Message::Watch { version } => format!("watch {}", version),
+            Message::Setup { version, seed, best_of, left_soft_drop, right_soft_drop } =>
+                format!("setup {} {} {} {} {}", version, seed, best_of, left_soft_drop, right_soft_drop),
+            Message::Round { round, left_wins, right_wins, tick } =>
+                format!("round {} {} {} {}", round, left_wins, right_wins, tick),
+            Message::Play(tick, ref left, ref right) => {
+                let mut line = format!("play {}", tick);
+                for &command in left {
+                    line.push(' ');
+                    line.push_str(&command_to_word(command));
+                }
+                line.push_str(" /");
+                for &command in right {
+                    line.push(' ');
+                    line.push_str(&command_to_word(command));
+                }
+                line
+            },
+            Message::Bye => "bye".to_string(),
+        }
+    }
+}
+
+/// Both boards of a versus match, stepped a tick at a time by commands
+/// alone. Two simulations with the same seed given the same commands stay
+/// the same. Boards don't wait on animations as they do locally, since the
+/// two sides draw at different speeds. Cloning one saves its state to go
+/// back to.
+#[derive(Clone)]
+pub struct Simulation {
+    dimensions: Dimension,
+    seed: u64,
+    left_board: Board,
+    right_board: Board,
+    left_settings: PlayerSettings,
+    right_settings: PlayerSettings,
+    controller: MatchController,
+    tick: u64,
+    // Tick the current round started on.
+    round_tick: u64,
+}
+
+impl Simulation {
+    pub fn new(dimensions: Dimension,
+               seed: u64,
+               best_of: u32,
+               left_settings: PlayerSettings,
+               right_settings: PlayerSettings) -> Self {
+
+        let mut simulation = Simulation {
+            dimensions: dimensions,
+            seed: seed,
+            left_board: Board::new(dimensions),
+            right_board: Board::new(dimensions),
+            left_settings: left_settings,
+            right_settings: right_settings,
+            controller: MatchController::new(best_of),
+            tick: 0,
+            round_tick: 0,
+        };
+        simulation.start_round();
+        simulation
+    }
+
+    // Both players get the same pieces, which change each round.
+    fn start_round(&mut self) {
+        let seed = self.seed.wrapping_add(self.controller.round() as u64);
+
+        self.left_board = Board::new(self.dimensions);
+        self.right_board = Board::new(self.dimensions);
+        self.left_board.set_seed(seed);
+        self.right_board.set_seed(seed);
+    }
+
+    /// Pick the match up at the start of a round, as described by a Round
+    /// message, for a spectator joining part way through.
+    pub fn resume(&mut self, round: u32, left_wins: u32, right_wins: u32, tick: u64) {
+        self.controller.resume(round, left_wins, right_wins);
+        self.tick = tick;
+        self.round_tick = tick;
+        self.start_round();
+    }
+
+    /// A Round message for the start of the current round.
+    pub fn round_start(&self) -> Message {
+        Message::Round {
+            round: self.controller.round(),
+            left_wins: self.controller.wins(Side::Left),
+            right_wins: self.controller.wins(Side::Right),
+            tick: self.round_tick,
+        }
+    }
+
+    /// A Setup message describing this match, for spectators.
+    pub fn setup(&self) -> Message {
+        Message::Setup {
+            version: PROTOCOL_VERSION,
+            seed: self.seed,
+            best_of: self.controller.best_of(),
+            left_soft_drop: self.left_settings.soft_drop,
+            right_soft_drop: self.right_settings.soft_drop,
+        }
+    }
+
+    /// The next tick to be played.
+    pub fn tick(&self) -> u64 { self.tick }
+    pub fn controller(&self) -> &MatchController { &self.controller }
+
+    pub fn board(&self, side: Side) -> &Board {
+        match side {
+            Side::Left => &self.left_board,
+            Side::Right => &self.right_board,
+        }
+    }
+
+    /// For rendering, which takes the board's events.
+    pub fn board_mut(&mut self, side: Side) -> &mut Board {
+        match side {
+            Side::Left => &mut self.left_board,
+            Side::Right => &mut self.right_board,
+        }
+    }
+
+    /// Throw away block events, for simulations that aren't drawn or whose
+    /// events have already been drawn.
+    pub fn clear_events(&mut self) {
+        self.left_board.consume_events();
+        self.right_board.consume_events();
+    }
+
+    /// Play a tick. Returns NextRound when the boards have been replaced.
+    pub fn step(&mut self, left: &[Command], right: &[Command]) -> Option<MatchEvent> {
+        let mut result = None;
+
+        if self.controller.is_playing() {
+            for &command in left {
+                input::apply(command, &mut self.left_board, &self.left_settings);
+            }
+            for &command in right {
+                input::apply(command, &mut self.right_board, &self.right_settings);
+            }
+
+            let settled = RenderState::new();
+            self.left_board.update(TICK, &settled);
+            self.right_board.update(TICK, &settled);
+
+            self.right_board.attack(self.left_board.take_attack());
+            self.left_board.attack(self.right_board.take_attack());
+
+            self.controller.report(self.left_board.is_game_over(), self.right_board.is_game_over());
+        } else {
+            result = self.controller.update(TICK);
+
+            if result == Some(MatchEvent::NextRound) {
+                self.start_round();
+            }
+        }
+
+        self.tick += 1;
+        if result == Some(MatchEvent::NextRound) {
+            self.round_tick = self.tick;
+        }
+        result
+    }
+
+    /// Checksum of both boards and the match score.
+    pub fn checksum(&self) -> u64 {
+        let left = self.left_board.checksum();
+        let right = self.right_board.checksum();
+        let wins = (self.controller.wins(Side::Left) as u64) << 8 | self.controller.wins(Side::Right) as u64;
+
+        left.rotate_left(1) ^ right ^ wins
+    }
+}
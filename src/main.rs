@@ -2,12 +2,47 @@
 extern crate bitflags;
 
 mod textures;
+mod themes;
 mod block_grid;
 mod values;
 mod board;
 mod human_player;
 mod board_renderer;
 mod wrapper_types;
+mod font;
+mod hud;
+mod layout;
+mod gravity;
+mod effects;
+mod projectile;
+mod menu;
+mod mode;
+mod match_controller;
+mod versus;
+mod progression;
+mod endless;
+mod puzzle;
+mod solver;
+mod puzzle_mode;
+mod records;
+mod time_attack;
+mod training;
+mod controls;
+mod controls_screen;
+mod settings;
+mod input;
+mod seats;
+mod gamepads;
+mod lockstep;
+mod net;
+mod rollback;
+mod online;
+mod broadcast;
+mod spectate;
+mod lobby;
+mod matchmaking;
+mod profiles;
+mod players_screen;
 
 extern crate piston_window;
 extern crate uuid;
@@ -16,82 +51,361 @@ extern crate find_folder;
 extern crate gfx;
 extern crate gfx_texture;
 extern crate sdl2_window;
+extern crate sdl2;
 
-use wrapper_types::GameWindow;
+use wrapper_types::{GameWindow,GameTextures};
 use piston_window::*;
 use std::rc::*;
 
 use textures::Textures;
 use values::*;
-use board::*;
 use human_player::*;
-use board_renderer::*;
+use font::Font;
+use layout::Layout;
+use menu::*;
+use mode::*;
+use versus::Versus;
+use endless::Endless;
+use puzzle_mode::PuzzleMode;
+use time_attack::TimeAttack;
+use training::Training;
+use controls::Controls;
+use controls_screen::ControlsScreen;
+use match_controller::Side;
+use seats::*;
+use gamepads::*;
+use online::Online;
+use spectate::Spectate;
+use matchmaking::Matchmaking;
+use players_screen::PlayersScreen;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ModeChoice {
+    Versus,
+    Endless,
+    TimeAttack,
+    Puzzle,
+    Training,
+    Host,
+    Join,
+    Watch,
+    Lobby,
+    Players,
+    Controls,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum PauseAction {
+    Resume,
+    Restart,
+    Quit,
+}
+
+const DEFAULT_BEST_OF: u32 = 3;
+
+// Size the window opens at. Everything is scaled to fit whatever size it
+// ends up, fullscreen included.
+const WINDOW_SIZE: (u32, u32) = (700, 570);
+
+// Restart is left out for modes that can't restart, like online play.
+fn new_pause_menu(title: &str, mode: &Mode) -> Menu<PauseAction> {
+    let mut items = vec!(("Resume", PauseAction::Resume));
+    if mode.can_restart() {
+        items.push(("Restart", PauseAction::Restart));
+    }
+    items.push(("Quit to title", PauseAction::Quit));
+
+    Menu::new(title, items)
+}
+
+// Value following a command line flag, such as `--port 7777`.
+fn arg(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+
+    args.windows(2)
+        .find(|pair| pair[0] == flag)
+        .map(|pair| pair[1].clone())
+}
+
+// Number of rounds in a match, from a `--best-of N` command line argument.
+fn best_of() -> u32 {
+    if let Some(value) = arg("--best-of") {
+        match value.parse::<u32>() {
+            Ok(n) if n % 2 == 1 => return n,
+            _ => println!("--best-of must be an odd number, using {}", DEFAULT_BEST_OF),
+        }
+    }
+
+    DEFAULT_BEST_OF
+}
+
+// Port to host online games on, from `--port N`.
+fn port() -> u16 {
+    if let Some(value) = arg("--port") {
+        match value.parse::<u16>() {
+            Ok(n) => return n,
+            _ => println!("--port must be a port number, using {}", net::DEFAULT_PORT),
+        }
+    }
+
+    net::DEFAULT_PORT
+}
+
+// Ticks of the other player's input to guess before waiting for it, from
+// `--rollback N`. Zero always waits.
+fn max_rollback() -> u64 {
+    if let Some(value) = arg("--rollback") {
+        match value.parse::<u64>() {
+            Ok(n) => return n,
+            _ => println!("--rollback must be a number of ticks, using {}", rollback::DEFAULT_MAX_ROLLBACK),
+        }
+    }
+
+    rollback::DEFAULT_MAX_ROLLBACK
+}
+
+// Made-up network trouble for online play, from `--latency MS`, `--jitter MS`
+// and `--loss PERCENT`.
+fn conditions() -> net::Conditions {
+    let number = |flag: &str, scale: f64| {
+        arg(flag).and_then(|value| match value.parse::<f64>() {
+            Ok(n) if n >= 0.0 => Some(n / scale),
+            _ => {
+                println!("{} must be a positive number, ignoring it", flag);
+                None
+            },
+        }).unwrap_or(0.0)
+    };
+
+    net::Conditions {
+        latency: number("--latency", 1000.0),
+        jitter: number("--jitter", 1000.0),
+        loss: number("--loss", 100.0),
+    }
+}
+
+// Name to go by in the lobby, from `--name NAME`, or the login name.
+fn player_name() -> String {
+    arg("--name")
+        .or(std::env::var("USER").ok())
+        .unwrap_or("player".to_string())
+}
+
+// Index of the theme named by `--theme NAME`, or of the default theme.
+fn initial_theme(themes: &[themes::Theme]) -> usize {
+    if let Some(name) = arg("--theme") {
+        match themes.iter().position(|theme| theme.name() == name) {
+            Some(i) => return i,
+            None => println!("No theme called {}, using the default", name),
+        }
+    }
+
+    0
+}
+
+// Where everything goes for boards drawn with some textures, in a window of
+// some size.
+fn new_layout(textures: &GameTextures, dimensions: Dimension, size: [f64; 2]) -> Layout {
+    // Use an arbitrary block to get the cell dimensions, as the renderer
+    // does.
+    let cell = textures.get(Block::new(Color::Blue, false).to_texture_name()).get_size();
+    let board = textures.get("board".to_string()).get_size();
+
+    let mut layout = Layout::new(dimensions, Dimension::from_tuple(cell), Dimension::from_tuple(board));
+    layout.resize(size[0] as u32, size[1] as u32);
+    layout
+}
+
+// Top left of an image centered in the window, in view units.
+fn centered<T: ImageSize>(layout: &Layout, image: &T) -> (f64, f64) {
+    let (w, h) = image.get_size();
+    let view = layout.view_size();
+
+    ((view[0] - w as f64) / 2.0, (view[1] - h as f64) / 2.0)
+}
+
+// Switch between a window and the whole screen.
+fn set_fullscreen(window: &GameWindow, fullscreen: bool) {
+    use sdl2::video::FullscreenType;
+
+    let mode = if fullscreen { FullscreenType::Desktop } else { FullscreenType::Off };
+    if window.window.borrow_mut().window.set_fullscreen(mode).is_err() {
+        println!("Could not switch fullscreen {}", if fullscreen { "on" } else { "off" });
+    }
+}
 
 fn main() {
     // TODO: Get width + height from board
     let dimensions = Dimension::new(6, 13);
-    let margin = 16.0;
-    let gutter = 64.0;
-    let cell = 32.0;
-
-    let board_width = cell * (dimensions.w() + 1) as f64 + margin;
-    let left_x = gutter;
-    let right_x = gutter + board_width + gutter;
-    let total_width = right_x + board_width + gutter;
-    let total_height = gutter * 2.0 + cell * dimensions.h() as f64;
+    let mut fullscreen = std::env::args().any(|arg| arg == "--fullscreen");
 
     let window: GameWindow =
-        WindowSettings::new("Puzzlefight II: Jungle Mayhem", (total_width as u32, total_height as u32))
-        .exit_on_esc(true)
+        WindowSettings::new("Puzzlefight II: Jungle Mayhem", WINDOW_SIZE)
+        .exit_on_esc(false)
+        .fullscreen(fullscreen)
         .build()
         .unwrap();
 
-    let _ = window.window.borrow_mut().init_joysticks();
+    let themes = textures::themes();
+    let mut theme = initial_theme(&themes);
+    let mut textures = Rc::new(Textures::new(&window, &themes[theme], &themes[0]));
 
-    let mut left_board = Board::new(dimensions);
-    let mut right_board = Board::new(dimensions);
+    let mut layout = {
+        let size = window.window.borrow().size();
+        new_layout(&textures, dimensions, [size.width as f64, size.height as f64])
+    };
 
-    let textures = Rc::new(Textures::new(&window));
-    let mut left_board_renderer = BoardRenderer::new(
-        textures.clone(),
-        PixelPosition::new(left_x, gutter),
-        dimensions
-        );
-    let mut right_board_renderer = BoardRenderer::new(
-        textures.clone(),
-        PixelPosition::new(right_x, gutter),
-        dimensions
-        );
+    let start_mode = |choice, left: &HumanPlayer, right: &HumanPlayer, layout: Layout, textures: &Rc<GameTextures>| -> Box<Mode> {
+        match choice {
+            ModeChoice::Versus => Box::new(Versus::new(
+                textures.clone(),
+                dimensions,
+                layout,
+                best_of()
+            )),
+            ModeChoice::Endless => Box::new(Endless::new(
+                textures.clone(),
+                dimensions,
+                layout
+            )),
+            ModeChoice::TimeAttack => Box::new(TimeAttack::new(
+                textures.clone(),
+                dimensions,
+                layout
+            )),
+            ModeChoice::Puzzle => Box::new(PuzzleMode::new(
+                textures.clone(),
+                dimensions,
+                layout
+            )),
+            ModeChoice::Training => Box::new(Training::new(
+                textures.clone(),
+                dimensions,
+                layout
+            )),
+            ModeChoice::Host => Box::new(Online::host(
+                textures.clone(),
+                dimensions,
+                layout,
+                best_of(),
+                port(),
+                left.controls().settings(),
+                max_rollback(),
+                conditions()
+            )),
+            ModeChoice::Join => Box::new(Online::join(
+                textures.clone(),
+                dimensions,
+                layout,
+                &arg("--join").unwrap_or("127.0.0.1".to_string()),
+                left.controls().settings(),
+                max_rollback(),
+                conditions()
+            )),
+            ModeChoice::Watch => Box::new(Spectate::new(
+                textures.clone(),
+                dimensions,
+                layout,
+                &arg("--join").unwrap_or("127.0.0.1".to_string())
+            )),
+            ModeChoice::Lobby => Box::new(Matchmaking::new(
+                textures.clone(),
+                dimensions,
+                layout,
+                &arg("--lobby").unwrap_or("127.0.0.1".to_string()),
+                &player_name(),
+                port(),
+                left.controls().settings(),
+                max_rollback(),
+                conditions()
+            )),
+            ModeChoice::Players => Box::new(PlayersScreen::new(layout)),
+            ModeChoice::Controls => Box::new(ControlsScreen::new(
+                Controls::new(left.controls().clone(), right.controls().clone()),
+                layout
+            )),
+        }
+    };
+    let mut mode: Option<Box<Mode>> = None;
 
-    let mut left_player = HumanPlayer::new(true);
-    let mut right_player = HumanPlayer::new(false);
+    let controls = Controls::load();
+    let mut left_player = HumanPlayer::new(controls.player(Side::Left));
+    let mut right_player = HumanPlayer::new(controls.player(Side::Right));
 
-    let mut left_render_state = RenderState::new();
-    let mut right_render_state = RenderState::new();
+    let mut gamepads = Gamepads::new();
+    let profiles = Profiles::load();
+    let mut seats = Seats::new();
 
     let mut start_screen = true;
-    let splash = textures.get("splash.png".to_string());
-    let ferns = textures.get("ferns.png".to_string());
-    let to_start = textures.get("press-to-start.png".to_string());
+    let mut splash = textures.get("splash".to_string());
+    let mut ferns = textures.get("ferns".to_string());
+    let mut to_start = textures.get("press-to-start".to_string());
     let mut d = 0.0;
     let mut blink = true;
 
+    let font = Font::new(4.0);
+    // The list of modes is too long for the big font.
+    let menu_font = Font::new(2.5);
+    let small_font = Font::new(2.0);
+    let mut pause_menu: Option<Menu<PauseAction>> = None;
+    let mut mode_menu: Option<Menu<ModeChoice>> = None;
+
     for e in window {
+        e.resize(|w, h| layout.resize(w, h));
+
+        if let Some(Button::Keyboard(Key::F11)) = e.press_args() {
+            fullscreen = !fullscreen;
+            set_fullscreen(&e, fullscreen);
+        }
+
         if start_screen {
             e.draw_2d(|c, g| {
                 use graphics::*;
 
                 // Black background
                 clear([0.0, 0.0, 0.0, 1.0], g);
-                image(&*splash, c.transform, g);
+                let c = layout.apply(&c);
+                let (splash_x, splash_y) = centered(&layout, &*splash);
+                image(&*splash, c.trans(splash_x, splash_y).transform, g);
 
                 if blink {
-                    let c = c.trans((total_width - to_start.get_size().0 as f64) / 2.0, 200.0);
+                    let x = (layout.view_size()[0] - to_start.get_size().0 as f64) / 2.0;
+                    let c = c.trans(x, splash_y + 200.0);
                     image(&*to_start, c.transform, g);
                 }
+
+                let bottom = layout.origin().y() + layout.canvas_size()[1];
+                let text = format!("Theme: {} (F9 to change)", themes[theme].name());
+                small_font.draw(&text, [1.0, 1.0, 1.0, 1.0],
+                    c.trans(layout.left().x(), bottom - small_font.height() * 2.0).transform, g);
             });
-            if let Some(_) = e.release_args() {
-                start_screen = false;
+            if let Some(button) = e.release_args() {
+                if button == Button::Keyboard(Key::Escape) {
+                    e.window.borrow_mut().set_should_close(true);
+                } else if button == Button::Keyboard(Key::F9) {
+                    // Modes started from now on draw with the new theme.
+                    theme = (theme + 1) % themes.len();
+                    textures = Rc::new(Textures::new(&e, &themes[theme], &themes[0]));
+                    layout = new_layout(&textures, dimensions, layout.window_size());
+                    splash = textures.get("splash".to_string());
+                    ferns = textures.get("ferns".to_string());
+                    to_start = textures.get("press-to-start".to_string());
+                } else {
+                    start_screen = false;
+                    mode_menu = Some(Menu::new("Select mode", vec!(
+                        ("Versus", ModeChoice::Versus),
+                        ("Endless", ModeChoice::Endless),
+                        ("Time attack", ModeChoice::TimeAttack),
+                        ("Puzzle", ModeChoice::Puzzle),
+                        ("Training", ModeChoice::Training),
+                        ("Host online", ModeChoice::Host),
+                        ("Join online", ModeChoice::Join),
+                        ("Watch online", ModeChoice::Watch),
+                        ("Find match", ModeChoice::Lobby),
+                        ("Players", ModeChoice::Players),
+                        ("Controls", ModeChoice::Controls),
+                    )));
+                }
             }
             e.update(|args| {
                 d += args.dt;
@@ -100,33 +414,154 @@ fn main() {
                     blink = !blink;
                 }
             });
+        } else if mode_menu.is_some() {
+            let mut choice = None;
+
+            {
+                let menu = mode_menu.as_mut().unwrap();
+
+                e.draw_2d(|c, g| {
+                    use graphics::*;
+
+                    clear([0.0, 0.0, 0.0, 1.0], g);
+                    let c = layout.apply(&c);
+                    let (splash_x, splash_y) = centered(&layout, &*splash);
+                    image(&*splash, c.trans(splash_x, splash_y).transform, g);
+                    menu.render(&menu_font, &c, g);
+
+                    for (i, &(side, name)) in [(Side::Left, "Left"), (Side::Right, "Right")].iter().enumerate() {
+                        let pad = match seats.gamepad(side) {
+                            Some(gamepad) => gamepad.name.clone(),
+                            None => "press a gamepad button to join".to_string(),
+                        };
+                        let bottom = layout.origin().y() + layout.canvas_size()[1];
+                        let y = bottom - small_font.height() * (4 - 2 * i) as f64;
+
+                        small_font.draw(&format!("{}: {}", name, pad), [1.0, 1.0, 1.0, 1.0],
+                            c.trans(layout.left().x(), y).transform, g);
+                    }
+                });
+
+                for command in vec!(left_player.update_menu(&e), right_player.update_menu(&e)) {
+                    choice = match command {
+                        Some(MenuCommand::Pause) => {
+                            start_screen = true;
+                            None
+                        },
+                        Some(command) => menu.handle(command).or(choice),
+                        None => choice,
+                    }
+                }
+            }
+
+            if let Some(choice) = choice {
+                mode = Some(start_mode(choice, &left_player, &right_player, layout, &textures));
+                mode_menu = None;
+            }
+            if start_screen {
+                mode_menu = None;
+            }
         } else {
             e.draw_2d(|c, g| {
                 use graphics::*;
 
                 // Black background
                 clear([0.0, 0.0, 0.0, 1.0], g);
-                image(&*ferns, c.transform, g);
+                let c = layout.apply(&c);
+                let (x, y) = centered(&layout, &*ferns);
+                image(&*ferns, c.trans(x, y).transform, g);
             });
 
-            left_player.update(&e, &mut left_board);
-            right_player.update(&e, &mut right_board);
+            let mut action = None;
 
-            e.update(|args| {
-                left_board.update(args.dt, &mut right_board, &left_render_state);
-                right_board.update(args.dt, &mut left_board, &right_render_state);
-            });
+            if pause_menu.is_some() {
+                let menu = pause_menu.as_mut().unwrap();
 
-            // TODO: This return code pattern sucks
-            match right_board_renderer.render(&e, &mut right_board) {
-                Some(state) => { right_render_state = state },
+                for command in vec!(left_player.update_menu(&e), right_player.update_menu(&e)) {
+                    action = match command {
+                        Some(MenuCommand::Pause) => Some(PauseAction::Resume),
+                        Some(command) => menu.handle(command).or(action),
+                        None => action,
+                    }
+                }
+            }
+
+            let event = match mode {
+                Some(ref mut mode) => {
+                    mode.event(&e, &mut left_player, &mut right_player, pause_menu.is_some())
+                },
+                None => Some(ModeEvent::Finished),
+            };
+
+            match event {
+                Some(ModeEvent::Pause) => {
+                    if let Some(ref mode) = mode {
+                        pause_menu = Some(new_pause_menu("Paused", &**mode));
+                    }
+                },
+                Some(ModeEvent::Finished) => {
+                    mode = None;
+                    start_screen = true;
+                },
                 None => {}
             }
-            // TODO: This return code pattern sucks
-            match left_board_renderer.render(&e, &mut left_board) {
-                Some(state) => { left_render_state = state },
+
+            match action {
+                Some(PauseAction::Resume) => {
+                    pause_menu = None;
+                },
+                Some(PauseAction::Restart) => {
+                    pause_menu = None;
+                    if let Some(ref mut mode) = mode {
+                        mode.restart();
+                    }
+                },
+                Some(PauseAction::Quit) => {
+                    pause_menu = None;
+                    mode = None;
+                    start_screen = true;
+                },
                 None => {}
             }
+
+            if let Some(ref menu) = pause_menu {
+                e.draw_2d(|c, g| {
+                    menu.render(&font, &layout.apply(&c), g);
+                });
+            }
+        }
+
+        // After everyone else has seen this event, so the press that joins a
+        // seat doesn't also do something.
+        let mut seat_events = Vec::new();
+        for device in gamepads.poll(&e) {
+            match device {
+                DeviceEvent::Connected(gamepad) => seats.connect(gamepad),
+                DeviceEvent::Disconnected(id) => seat_events.extend(seats.disconnect(id)),
+            }
+        }
+        if let Some(event) = press_event(&e) {
+            seat_events.extend(seats.handle(event));
+        }
+
+        for event in seat_events {
+            let side = match event {
+                SeatEvent::Joined(side) | SeatEvent::Lost(side) => side,
+            };
+            let pad = seats.pad(side, &profiles);
+
+            match side {
+                Side::Left => left_player.set_pad(pad),
+                Side::Right => right_player.set_pad(pad),
+            }
+
+            if let SeatEvent::Lost(_) = event {
+                if let Some(ref mode) = mode {
+                    if pause_menu.is_none() {
+                        pause_menu = Some(new_pause_menu("Controller unplugged", &**mode));
+                    }
+                }
+            }
         }
     }
 }
@@ -0,0 +1,138 @@
+// Seconds the result banner is shown between rounds, and at the end of the
+// match.
+const ROUND_BANNER: f64 = 3.0;
+const MATCH_BANNER: f64 = 5.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MatchState {
+    Playing,
+    // Winner of the round (None for a draw), and time the banner has left.
+    RoundOver(Option<Side>, f64),
+    // Winner of the match, and time the banner has left.
+    MatchOver(Side, f64),
+    Finished,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MatchEvent {
+    // Both boards should be reset for the next round.
+    NextRound,
+    // The match is over and the final banner has been shown.
+    Finished,
+}
+
+/// Tracks round wins for a best-of-N match between two boards. Knows nothing
+/// about boards or rendering: it is told when a round ends and reports when
+/// the caller should start the next round.
+#[derive(Clone)]
+pub struct MatchController {
+    best_of: u32,
+    left_wins: u32,
+    right_wins: u32,
+    round: u32,
+    state: MatchState,
+}
+
+impl MatchController {
+    pub fn new(best_of: u32) -> Self {
+        assert!(best_of % 2 == 1, "Matches must be best of an odd number of rounds");
+
+        MatchController {
+            best_of: best_of,
+            left_wins: 0,
+            right_wins: 0,
+            round: 1,
+            state: MatchState::Playing,
+        }
+    }
+
+    pub fn best_of(&self) -> u32 { self.best_of }
+    pub fn round(&self) -> u32 { self.round }
+    pub fn state(&self) -> MatchState { self.state }
+
+    pub fn wins(&self, side: Side) -> u32 {
+        match side {
+            Side::Left  => self.left_wins,
+            Side::Right => self.right_wins,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state == MatchState::Playing
+    }
+
+    /// Pick up a match at the start of the given round, with the score so
+    /// far.
+    pub fn resume(&mut self, round: u32, left_wins: u32, right_wins: u32) {
+        self.round = round;
+        self.left_wins = left_wins;
+        self.right_wins = right_wins;
+        self.state = MatchState::Playing;
+    }
+
+    /// Rounds needed to take the match.
+    pub fn wins_needed(&self) -> u32 {
+        self.best_of / 2 + 1
+    }
+
+    /// Record which boards have lost. Does nothing unless a round is in
+    /// progress and at least one board lost. If both lost at once the round
+    /// is a draw and is replayed.
+    pub fn report(&mut self, left_lost: bool, right_lost: bool) {
+        if !self.is_playing() || !(left_lost || right_lost) {
+            return;
+        }
+
+        let winner = match (left_lost, right_lost) {
+            (true, false) => Some(Side::Right),
+            (false, true) => Some(Side::Left),
+            _             => None,
+        };
+
+        match winner {
+            Some(Side::Left)  => self.left_wins += 1,
+            Some(Side::Right) => self.right_wins += 1,
+            None => {}
+        }
+
+        self.state = match winner {
+            Some(side) if self.wins(side) >= self.wins_needed() => {
+                MatchState::MatchOver(side, MATCH_BANNER)
+            },
+            _ => MatchState::RoundOver(winner, ROUND_BANNER),
+        }
+    }
+
+    /// Count down the result banner. Returns an event when the banner is
+    /// done.
+    pub fn update(&mut self, dt: f64) -> Option<MatchEvent> {
+        match self.state {
+            MatchState::RoundOver(winner, t) => {
+                if t - dt <= 0.0 {
+                    self.round += 1;
+                    self.state = MatchState::Playing;
+                    Some(MatchEvent::NextRound)
+                } else {
+                    self.state = MatchState::RoundOver(winner, t - dt);
+                    None
+                }
+            },
+            MatchState::MatchOver(winner, t) => {
+                if t - dt <= 0.0 {
+                    self.state = MatchState::Finished;
+                    Some(MatchEvent::Finished)
+                } else {
+                    self.state = MatchState::MatchOver(winner, t - dt);
+                    None
+                }
+            },
+            _ => None,
+        }
+    }
+}
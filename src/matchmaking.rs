@@ -0,0 +1,249 @@
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use human_player::HumanPlayer;
+use lobby::*;
+use menu::MenuCommand;
+use net::*;
+use online::Online;
+use settings::PlayerSettings;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use versus::render_banner;
+use wrapper_types::*;
+
+// A match found through the lobby.
+struct Game {
+    id: u32,
+    opponent: String,
+    online: Online,
+}
+
+/// Find opponents through a lobby server. Waits in the lobby until paired,
+/// plays the match online, reports who won, then waits for the next one.
+pub struct Matchmaking {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+    settings: PlayerSettings,
+    max_rollback: u64,
+    conditions: Conditions,
+
+    name: String,
+    // Port to host matches on, if it's free.
+    port: u16,
+    lobby: Option<Connection>,
+    // Listening before joining the lobby, so that a guest sent here straight
+    // away can connect.
+    listener: Option<Host>,
+    waiting: Vec<String>,
+    game: Option<Game>,
+    // Why the lobby can't be used, if it can't.
+    error: Option<String>,
+    font: Font,
+}
+
+impl Matchmaking {
+    /// Join the lobby at `host:port` under the given name, hosting matches
+    /// on the given port, or any free one if it is taken. Blocks until
+    /// connected or given up.
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout,
+               address: &str,
+               name: &str,
+               port: u16,
+               settings: PlayerSettings,
+               max_rollback: u64,
+               conditions: Conditions) -> Self {
+
+        let mut matchmaking = Matchmaking {
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+            settings: settings,
+            max_rollback: max_rollback,
+            conditions: conditions,
+
+            // Names are single words.
+            name: name.split_whitespace().collect::<Vec<_>>().join("_"),
+            port: port,
+            lobby: None,
+            listener: None,
+            waiting: Vec::new(),
+            game: None,
+            error: None,
+            font: Font::new(3.0),
+        };
+
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_LOBBY_PORT)
+        };
+        match Connection::connect(&address) {
+            Ok(connection) => {
+                matchmaking.lobby = Some(connection);
+                matchmaking.wait();
+            },
+            Err(e) => matchmaking.error = Some(e),
+        }
+
+        matchmaking
+    }
+
+    // Listen for an opponent and tell the lobby we're waiting.
+    fn wait(&mut self) {
+        let result = Host::listen(self.port).or_else(|_| Host::listen(0)).and_then(|listener| {
+            let hello = LobbyMessage::Hello { name: self.name.clone(), port: listener.port() };
+            self.listener = Some(listener);
+            self.send(&hello)
+        });
+
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    fn send(&mut self, message: &LobbyMessage) -> Result<(), String> {
+        match self.lobby {
+            Some(ref mut lobby) => lobby.send_line(&message.to_line()),
+            None => Ok(()),
+        }
+    }
+
+    fn receive(&mut self) -> Result<(), String> {
+        let lines = match self.lobby {
+            Some(ref mut lobby) => try!(lobby.receive_lines()),
+            None => return Ok(()),
+        };
+
+        for line in lines {
+            match try!(LobbyMessage::parse(&line)) {
+                LobbyMessage::Waiting(names) => self.waiting = names,
+                LobbyMessage::Host { id, seed, best_of, opponent } => {
+                    let listener = try!(self.listener.take().ok_or("Not listening".to_string()));
+                    let mut online = Online::host_on(
+                        self.textures.clone(), self.dimensions, self.layout,
+                        best_of, listener, self.settings, self.max_rollback, self.conditions);
+                    online.set_seed(seed);
+
+                    self.game = Some(Game { id: id, opponent: opponent, online: online });
+                },
+                LobbyMessage::Join { id, address, opponent } => {
+                    self.listener = None;
+                    let online = Online::join(
+                        self.textures.clone(), self.dimensions, self.layout,
+                        &address, self.settings, self.max_rollback, self.conditions);
+
+                    self.game = Some(Game { id: id, opponent: opponent, online: online });
+                },
+                LobbyMessage::Error(reason) => return Err(reason),
+                message => return Err(format!("Unexpected `{}`", message.to_line())),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Report the match just played and wait for another.
+    fn finish_game(&mut self) {
+        let game = match self.game.take() {
+            Some(game) => game,
+            None => return,
+        };
+
+        let result = match game.online.winner() {
+            Some(side) => {
+                let winner = if side == game.online.side() { self.name.clone() } else { game.opponent.clone() };
+                self.send(&LobbyMessage::Result { id: game.id, winner: winner })
+            },
+            None => Ok(()),
+        };
+        drop(game);
+
+        match result {
+            Ok(()) => self.wait(),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn render(&self, e: &GameWindow) {
+        let font = &self.font;
+        let status = match self.error {
+            Some(ref error) => format!("{} - press pause to leave", error),
+            None => "Waiting for an opponent...".to_string(),
+        };
+        let names = &self.waiting;
+        let layout = &self.layout;
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+            render_banner(font, &status, [1.0, 1.0, 1.0, 1.0], &c, g);
+
+            let size = c.get_view_size();
+            for (i, name) in names.iter().enumerate() {
+                let y = (size[1] + font.height() * 3.0) / 2.0 + font.height() * (2 * i + 1) as f64;
+                let c = c.trans((size[0] - font.width(name)) / 2.0, y);
+                font.draw(name, [0.8, 0.8, 0.8, 1.0], c.transform, g);
+            }
+        });
+    }
+}
+
+impl Mode for Matchmaking {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        // Kept up to date for the next match, while another is played.
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if self.game.is_some() {
+            let result = self.game.as_mut().unwrap().online.event(e, left_player, right_player, paused);
+
+            // Back to the lobby rather than the title screen.
+            if result == Some(ModeEvent::Finished) {
+                self.finish_game();
+                return None;
+            }
+            return result;
+        }
+
+        let mut result = None;
+
+        if !paused {
+            for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                if command == Some(MenuCommand::Pause) {
+                    result = Some(if self.error.is_some() { ModeEvent::Finished } else { ModeEvent::Pause });
+                }
+            }
+        }
+
+        if self.error.is_none() {
+            if let Err(reason) = self.receive() {
+                self.error = Some(reason);
+            }
+        }
+
+        if self.game.is_none() {
+            self.render(e);
+        }
+
+        result
+    }
+
+    /// Nothing to restart in the lobby, and matches can't be restarted.
+    fn restart(&mut self) {}
+
+    fn can_restart(&self) -> bool {
+        false
+    }
+}
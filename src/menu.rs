@@ -0,0 +1,79 @@
+use graphics::{Graphics,Context,Transformed,rectangle};
+
+use font::Font;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MenuCommand {
+    Up,
+    Down,
+    Select,
+    Pause,
+}
+
+/// A vertical list of labelled options, one of which is selected. T is the
+/// value handed back when an option is chosen.
+pub struct Menu<T> {
+    title: String,
+    items: Vec<(String, T)>,
+    selected: usize,
+}
+
+impl<T: Copy> Menu<T> {
+    pub fn new(title: &str, items: Vec<(&str, T)>) -> Self {
+        Menu {
+            title: title.to_string(),
+            items: items.into_iter().map(|(label, x)| (label.to_string(), x)).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> T {
+        self.items[self.selected].1
+    }
+
+    /// Move the selection or choose an option. Returns the chosen value if
+    /// the command was Select. Pause is left to the caller to interpret.
+    pub fn handle(&mut self, command: MenuCommand) -> Option<T> {
+        let n = self.items.len();
+
+        match command {
+            MenuCommand::Up => {
+                self.selected = (self.selected + n - 1) % n;
+                None
+            },
+            MenuCommand::Down => {
+                self.selected = (self.selected + 1) % n;
+                None
+            },
+            MenuCommand::Select => Some(self.selected()),
+            MenuCommand::Pause => None,
+        }
+    }
+
+    /// Dim everything drawn so far and draw the menu centered over it.
+    pub fn render<G: Graphics>(&self, font: &Font, c: &Context, g: &mut G) {
+        let size = c.get_view_size();
+        let (w, h) = (size[0], size[1]);
+
+        rectangle([0.0, 0.0, 0.0, 0.7], [0.0, 0.0, w, h], c.transform, g);
+
+        let line = font.height() * 2.0;
+        let total = line * (self.items.len() + 2) as f64;
+        let top = (h - total) / 2.0;
+
+        let x = (w - font.width(&self.title)) / 2.0;
+        font.draw(&self.title, [1.0, 1.0, 1.0, 1.0], c.trans(x, top).transform, g);
+
+        for (i, &(ref label, _)) in self.items.iter().enumerate() {
+            let (text, color) = if i == self.selected {
+                (format!("> {} <", label), [1.0, 0.8, 0.1, 1.0])
+            } else {
+                (label.clone(), [0.8, 0.8, 0.8, 1.0])
+            };
+
+            let x = (w - font.width(&text)) / 2.0;
+            let y = top + line * (i + 2) as f64;
+            font.draw(&text, color, c.trans(x, y).transform, g);
+        }
+    }
+}
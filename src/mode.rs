@@ -0,0 +1,31 @@
+use human_player::HumanPlayer;
+use wrapper_types::GameWindow;
+
+/// Reported by a game mode back to the main loop.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ModeEvent {
+    // A player asked to pause.
+    Pause,
+    // The mode has run its course; return to the title screen.
+    Finished,
+}
+
+/// A way to play, selected from the title screen. The main loop owns the
+/// players and the pause menu, and hands every window event to the mode.
+pub trait Mode {
+    /// Handle input, simulation and drawing for a single window event.
+    /// Input and simulation are skipped while paused.
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent>;
+
+    /// Start the current round over.
+    fn restart(&mut self);
+
+    /// Whether restart does anything, so the pause menu can offer it.
+    fn can_restart(&self) -> bool {
+        true
+    }
+}
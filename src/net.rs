@@ -0,0 +1,229 @@
+extern crate rand;
+
+use std::collections::VecDeque;
+use std::io::{self,Read,Write};
+use std::net::{TcpListener,TcpStream,ToSocketAddrs};
+use std::time::Duration;
+
+use self::rand::{Rng,SeedableRng,XorShiftRng};
+
+use lockstep::Message;
+
+pub const DEFAULT_PORT: u16 = 7777;
+
+// Give up connecting after this long.
+const CONNECT_TIMEOUT: u64 = 5;
+
+// Seconds before a lost packet is sent again.
+const RETRANSMIT: f64 = 0.2;
+
+/// Waits for the other player to connect, without blocking.
+pub struct Host {
+    listener: TcpListener,
+}
+
+impl Host {
+    pub fn listen(port: u16) -> Result<Self, String> {
+        let listener = try!(TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| format!("Could not listen on port {}: {}", port, e)));
+        try!(listener.set_nonblocking(true).map_err(|e| e.to_string()));
+
+        Ok(Host {
+            listener: listener,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.listener.local_addr().map(|addr| addr.port()).unwrap_or(0)
+    }
+
+    /// The other player's connection, once they have connected.
+    pub fn accept(&self) -> Result<Option<Connection>, String> {
+        match self.listener.accept() {
+            Ok((stream, _)) => Connection::new(stream).map(Some),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// A line-based, non-blocking connection to the other player.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    closed: bool,
+}
+
+impl Connection {
+    /// Connect to a host given as `host:port`, or just `host` for the
+    /// default port.
+    pub fn connect(address: &str) -> Result<Self, String> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_PORT)
+        };
+        let error = |e: io::Error| format!("Could not connect to {}: {}", address, e);
+
+        let addr = try!(try!(address.to_socket_addrs().map_err(&error))
+            .next()
+            .ok_or(format!("Could not find {}", address)));
+        let stream = try!(TcpStream::connect_timeout(&addr, Duration::from_secs(CONNECT_TIMEOUT))
+            .map_err(&error));
+
+        Connection::new(stream)
+    }
+
+    fn new(stream: TcpStream) -> Result<Self, String> {
+        try!(stream.set_nonblocking(true).map_err(|e| e.to_string()));
+        try!(stream.set_nodelay(true).map_err(|e| e.to_string()));
+
+        Ok(Connection {
+            stream: stream,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closed: false,
+        })
+    }
+
+    /// The address of the other end, without the port.
+    pub fn peer_ip(&self) -> Option<String> {
+        self.stream.peer_addr().ok().map(|addr| addr.ip().to_string())
+    }
+
+    /// Queue a message, sending as much as the network will take.
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        self.send_line(&message.to_line())
+    }
+
+    /// Queue a line of text, without its newline.
+    pub fn send_line(&mut self, line: &str) -> Result<(), String> {
+        self.outgoing.extend(line.bytes());
+        self.outgoing.push(b'\n');
+        self.flush()
+    }
+
+    /// Send whatever is still queued.
+    pub fn flush(&mut self) -> Result<(), String> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err("Connection closed".to_string()),
+                Ok(n) => { self.outgoing.drain(..n); },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Messages that have arrived since the last call. Fails once the
+    /// connection has closed and every message has been taken.
+    pub fn receive(&mut self) -> Result<Vec<Message>, String> {
+        let mut messages = Vec::new();
+
+        for line in try!(self.receive_lines()) {
+            messages.push(try!(Message::parse(&line)));
+        }
+
+        Ok(messages)
+    }
+
+    /// Lines of text that have arrived since the last call, trimmed. Fails
+    /// once the connection has closed and every line has been taken.
+    pub fn receive_lines(&mut self) -> Result<Vec<String>, String> {
+        let mut buffer = [0; 4096];
+
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.incoming.extend(&buffer[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.incoming.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.incoming.drain(..end + 1).collect();
+            let line = try!(String::from_utf8(line).map_err(|_| "Garbled message".to_string()));
+            lines.push(line.trim().to_string());
+        }
+
+        if self.closed && lines.is_empty() {
+            return Err("Connection closed".to_string());
+        }
+        Ok(lines)
+    }
+}
+
+/// Made-up network trouble, for trying out online play on one computer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Conditions {
+    /// Seconds every message takes to arrive.
+    pub latency: f64,
+    /// Up to this many more seconds, at random.
+    pub jitter: f64,
+    /// Chance from 0 to 1 that a message is lost and has to be sent again.
+    pub loss: f64,
+}
+
+impl Conditions {
+    pub fn is_perfect(&self) -> bool {
+        self.latency <= 0.0 && self.jitter <= 0.0 && self.loss <= 0.0
+    }
+}
+
+impl Default for Conditions {
+    fn default() -> Self {
+        Conditions { latency: 0.0, jitter: 0.0, loss: 0.0 }
+    }
+}
+
+/// Holds messages back as if they had crossed a network with the given
+/// conditions. Like TCP, lost messages are sent again and nothing arrives
+/// out of order, so loss shows up as a stall.
+pub struct Delayed {
+    conditions: Conditions,
+    rng: XorShiftRng,
+    queue: VecDeque<(f64, Message)>,
+}
+
+impl Delayed {
+    /// The seed picks which messages are slow or lost, so that tests can
+    /// repeat a run.
+    pub fn new(conditions: Conditions, seed: u32) -> Self {
+        Delayed {
+            conditions: conditions,
+            rng: XorShiftRng::from_seed([seed, 0x9E37_79B9, 0x7F4A_7C15, 1]),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Send a message at the given time, in seconds.
+    pub fn push(&mut self, now: f64, message: Message) {
+        let mut delay = self.conditions.latency + self.conditions.jitter * self.rng.gen::<f64>();
+        while self.conditions.loss > 0.0 && self.rng.gen::<f64>() < self.conditions.loss.min(0.9) {
+            delay += RETRANSMIT;
+        }
+
+        let last = self.queue.back().map(|&(at, _)| at).unwrap_or(now);
+        self.queue.push_back(((now + delay).max(last), message));
+    }
+
+    /// Messages that have arrived by the given time.
+    pub fn ready(&mut self, now: f64) -> Vec<Message> {
+        let mut messages = Vec::new();
+
+        while self.queue.front().map(|&(at, _)| at <= now).unwrap_or(false) {
+            messages.push(self.queue.pop_front().unwrap().1);
+        }
+
+        messages
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
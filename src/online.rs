@@ -0,0 +1,506 @@
+extern crate rand;
+
+use std::mem;
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board_renderer::*;
+use broadcast::Broadcast;
+use controls::Action;
+use human_player::HumanPlayer;
+use input::Command;
+use lockstep::*;
+use match_controller::*;
+use net::*;
+use rollback::Rollback;
+use settings::PlayerSettings;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use versus::{render_banner,render_match};
+use wrapper_types::*;
+
+enum Phase {
+    // Waiting for the other player to connect.
+    Listening,
+    // Connected, waiting for the other player's hello.
+    Greeting(Connection),
+    Playing(Connection, Rollback),
+    // The match can't go on, for the reason given.
+    Over(String),
+}
+
+/// Versus against a player on another computer. The host plays on the left
+/// and picks the seed and match length. Both local players' controls drive
+/// the local side. Play doesn't wait for the other player's input unless it
+/// is more than a few ticks late, and is rolled back when it arrives. Made-up
+/// latency and loss can be added to what this side sends, to try that out.
+/// The host also takes spectators, who are sent the match as it is agreed.
+pub struct Online {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    side: Side,
+    seed: u64,
+    best_of: u32,
+    settings: PlayerSettings,
+    max_rollback: u64,
+    phase: Phase,
+
+    // Only the host listens, for the other player and then spectators.
+    listener: Option<Host>,
+    // Connections that haven't said whether they play or watch yet.
+    visitors: Vec<Connection>,
+    broadcast: Broadcast,
+
+    // What this side sends, held back by the made-up network conditions.
+    outgoing: Delayed,
+    // Seconds since the mode started, for the delayed messages.
+    clock: f64,
+
+    left_renderer: GameBoardRenderer,
+    right_renderer: GameBoardRenderer,
+    // Seconds of play not simulated yet.
+    accumulator: f64,
+    font: Font,
+}
+
+impl Online {
+    fn new(textures: Rc<GameTextures>,
+           dimensions: Dimension,
+           layout: Layout,
+           side: Side,
+           best_of: u32,
+           settings: PlayerSettings,
+           max_rollback: u64,
+           conditions: Conditions) -> Self {
+
+        let seed = rand::random();
+
+        Online {
+            left_renderer: BoardRenderer::new(textures.clone(), layout.left(), dimensions),
+            right_renderer: BoardRenderer::with_preview_on(textures.clone(), layout.right(), dimensions, Side::Right),
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+
+            side: side,
+            seed: seed,
+            best_of: best_of,
+            settings: settings,
+            max_rollback: max_rollback,
+            phase: Phase::Over(String::new()),
+
+            listener: None,
+            visitors: Vec::new(),
+            broadcast: Broadcast::new(),
+
+            outgoing: Delayed::new(conditions, seed as u32),
+            clock: 0.0,
+
+            accumulator: 0.0,
+            font: Font::new(3.0),
+        }
+    }
+
+    /// Wait for a player to join on the given port.
+    pub fn host(textures: Rc<GameTextures>,
+                dimensions: Dimension,
+                layout: Layout,
+                best_of: u32,
+                port: u16,
+                settings: PlayerSettings,
+                max_rollback: u64,
+                conditions: Conditions) -> Self {
+
+        match Host::listen(port) {
+            Ok(listener) => Online::host_on(textures, dimensions, layout,
+                                            best_of, listener, settings, max_rollback, conditions),
+            Err(e) => {
+                let mut online = Online::new(textures, dimensions, layout,
+                                             Side::Left, best_of, settings, max_rollback, conditions);
+                online.phase = Phase::Over(e);
+                online
+            },
+        }
+    }
+
+    /// Wait for a player to join on a port already being listened on.
+    pub fn host_on(textures: Rc<GameTextures>,
+                   dimensions: Dimension,
+                   layout: Layout,
+                   best_of: u32,
+                   listener: Host,
+                   settings: PlayerSettings,
+                   max_rollback: u64,
+                   conditions: Conditions) -> Self {
+
+        let mut online = Online::new(textures, dimensions, layout,
+                                     Side::Left, best_of, settings, max_rollback, conditions);
+        online.listener = Some(listener);
+        online.phase = Phase::Listening;
+        online
+    }
+
+    /// Play with the given seed rather than a random one. Only the host's
+    /// seed is used, and only if set before the other player joins.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn side(&self) -> Side {
+        self.side
+    }
+
+    /// The winner, once the match has been won.
+    pub fn winner(&self) -> Option<Side> {
+        match self.phase {
+            Phase::Playing(_, ref rollback) => {
+                let controller = rollback.confirmed().controller();
+
+                vec!(Side::Left, Side::Right).into_iter()
+                    .find(|&side| controller.wins(side) >= controller.wins_needed())
+            },
+            _ => None,
+        }
+    }
+
+    /// Join a host at `host:port`. Blocks until connected or given up.
+    pub fn join(textures: Rc<GameTextures>,
+                dimensions: Dimension,
+                layout: Layout,
+                address: &str,
+                settings: PlayerSettings,
+                max_rollback: u64,
+                conditions: Conditions) -> Self {
+
+        let mut online = Online::new(textures, dimensions, layout,
+                                     Side::Right, 1, settings, max_rollback, conditions);
+        let phase = match Connection::connect(address).and_then(|c| online.greet(c)) {
+            Ok(connection) => Phase::Greeting(connection),
+            Err(e) => Phase::Over(e),
+        };
+        online.phase = phase;
+        online
+    }
+
+    fn greet(&self, mut connection: Connection) -> Result<Connection, String> {
+        try!(connection.send(&Message::Hello {
+            version: PROTOCOL_VERSION,
+            seed: self.seed,
+            best_of: self.best_of,
+            soft_drop: self.settings.soft_drop,
+        }));
+
+        Ok(connection)
+    }
+
+    // Take in new connections to the host. Those who ask to watch are added
+    // to the broadcast. If a player is wanted, returns the first to say
+    // hello, with everything they sent. Anyone else is turned away.
+    fn welcome(&mut self, want_player: bool) -> Result<Option<(Connection, Vec<Message>)>, String> {
+        if let Some(ref listener) = self.listener {
+            while let Some(connection) = try!(listener.accept()) {
+                self.visitors.push(connection);
+            }
+        }
+
+        let mut player = None;
+
+        for mut visitor in mem::replace(&mut self.visitors, Vec::new()) {
+            // Those who leave before saying anything are forgotten.
+            let messages = match visitor.receive() {
+                Ok(messages) => messages,
+                Err(_) => continue,
+            };
+
+            match messages.first().cloned() {
+                None => self.visitors.push(visitor),
+                Some(Message::Watch { version }) if version == PROTOCOL_VERSION => {
+                    self.broadcast.add(visitor);
+                },
+                Some(Message::Hello { .. }) if want_player && player.is_none() => {
+                    player = Some((visitor, messages));
+                },
+                Some(_) => {
+                    let _ = visitor.send(&Message::Bye);
+                },
+            }
+        }
+
+        Ok(player)
+    }
+
+    // Start playing once the other player's hello has arrived.
+    fn greeted(&mut self, connection: Connection, messages: Vec<Message>) -> Result<Phase, String> {
+        let mut messages = messages.into_iter();
+
+        match messages.next() {
+            Some(Message::Hello { version, seed, best_of, soft_drop }) => {
+                let mut rollback = try!(self.start(version, seed, best_of, soft_drop));
+                for message in messages {
+                    try!(rollback.receive(message));
+                }
+                Ok(Phase::Playing(connection, rollback))
+            },
+            Some(Message::Bye) => Err("Opponent left".to_string()),
+            Some(message) => Err(format!("Expected hello, got `{}`", message.to_line())),
+            None => Ok(Phase::Greeting(connection)),
+        }
+    }
+
+    // Set up the match from the other player's hello.
+    fn start(&mut self, version: u32, seed: u64, best_of: u32, soft_drop: f64) -> Result<Rollback, String> {
+        if version != PROTOCOL_VERSION {
+            return Err("The other player has a different version of the game".to_string());
+        }
+        if best_of % 2 == 0 || soft_drop < 1.0 {
+            return Err("The other player sent a bad hello".to_string());
+        }
+
+        if self.side == Side::Right {
+            self.seed = seed;
+            self.best_of = best_of;
+        }
+
+        let theirs = PlayerSettings {
+            soft_drop: soft_drop,
+            .. PlayerSettings::default()
+        };
+        let (left, right) = match self.side {
+            Side::Left => (self.settings, theirs),
+            Side::Right => (theirs, self.settings),
+        };
+
+        let simulation = Simulation::new(self.dimensions, self.seed, self.best_of, left, right);
+        self.broadcast.start(&simulation);
+        self.accumulator = 0.0;
+        Ok(Rollback::new(self.side, simulation, self.max_rollback))
+    }
+
+    fn reset_renderers(&mut self) {
+        self.left_renderer = BoardRenderer::new(self.textures.clone(), self.layout.left(), self.dimensions);
+        self.right_renderer = BoardRenderer::with_preview_on(self.textures.clone(), self.layout.right(), self.dimensions, Side::Right);
+    }
+
+    fn advance(&mut self,
+               phase: Phase,
+               e: &GameWindow,
+               left_player: &mut HumanPlayer,
+               right_player: &mut HumanPlayer,
+               paused: bool,
+               result: &mut Option<ModeEvent>) -> Result<Phase, String> {
+
+        match phase {
+            Phase::Listening => {
+                match try!(self.welcome(true)) {
+                    Some((connection, messages)) => {
+                        let connection = try!(self.greet(connection));
+                        self.greeted(connection, messages)
+                    },
+                    None => Ok(Phase::Listening),
+                }
+            },
+            Phase::Greeting(mut connection) => {
+                let messages = try!(connection.receive());
+                self.greeted(connection, messages)
+            },
+            Phase::Playing(mut connection, mut rollback) => {
+                // The other side can't be paused, so play goes on under the
+                // pause menu without local input.
+                if !paused {
+                    let mut commands = left_player.commands(e);
+                    commands.extend(right_player.commands(e));
+
+                    for command in commands {
+                        if command == Command::Press(Action::Pause) {
+                            *result = Some(ModeEvent::Pause);
+                        }
+                        rollback.input(command);
+                    }
+                }
+
+                for message in try!(connection.receive()) {
+                    try!(rollback.receive(message));
+                }
+
+                let rollbacks = rollback.rollbacks();
+                let mut events = Vec::new();
+
+                while self.accumulator >= TICK && rollback.can_step() {
+                    self.accumulator -= TICK;
+                    events.push(try!(rollback.step()));
+                }
+                // Take in input that arrived with nothing left to play.
+                events.push(try!(rollback.confirm()));
+
+                for event in events {
+                    match event {
+                        Some(MatchEvent::NextRound) => self.reset_renderers(),
+                        Some(MatchEvent::Finished) => *result = Some(ModeEvent::Finished),
+                        None => {}
+                    }
+                }
+
+                // The boards were replayed without events, so animations
+                // from the wrong guess are dropped.
+                if rollback.rollbacks() != rollbacks {
+                    self.left_renderer.resync();
+                    self.right_renderer.resync();
+                }
+
+                // Don't build up time to catch up on while the other side is
+                // behind.
+                self.accumulator = self.accumulator.min(TICK * INPUT_DELAY as f64);
+
+                try!(self.welcome(false));
+                for message in rollback.played() {
+                    self.broadcast.record(message);
+                }
+                self.broadcast.flush();
+
+                for message in rollback.outgoing() {
+                    self.outgoing.push(self.clock, message);
+                }
+                for message in self.outgoing.ready(self.clock) {
+                    try!(connection.send(&message));
+                }
+                try!(connection.flush());
+
+                Ok(Phase::Playing(connection, rollback))
+            },
+            Phase::Over(reason) => {
+                if !paused {
+                    for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                        if command.is_some() {
+                            *result = Some(ModeEvent::Finished);
+                        }
+                    }
+                }
+
+                Ok(Phase::Over(reason))
+            },
+        }
+    }
+
+    fn status(&self) -> Option<String> {
+        match self.phase {
+            Phase::Listening => {
+                let port = self.listener.as_ref().map(|listener| listener.port()).unwrap_or(0);
+                Some(format!("Waiting for a player on port {}", port))
+            },
+            Phase::Greeting(_) => Some("Connecting...".to_string()),
+            Phase::Playing(_, ref rollback) => {
+                if rollback.can_step() || self.accumulator < TICK * INPUT_DELAY as f64 {
+                    None
+                } else {
+                    Some("Waiting for the other player...".to_string())
+                }
+            },
+            Phase::Over(ref reason) => Some(format!("{} - press pause to leave", reason)),
+        }
+    }
+
+    fn render(&mut self, e: &GameWindow) {
+        self.left_renderer.set_layout(self.layout.left(), self.layout.scale());
+        self.right_renderer.set_layout(self.layout.right(), self.layout.scale());
+        self.left_renderer.set_squash(self.settings.squash);
+        self.right_renderer.set_squash(self.settings.squash);
+
+        if let Phase::Playing(_, ref mut rollback) = self.phase {
+            let simulation = rollback.simulation_mut();
+
+            self.right_renderer.render(e, simulation.board_mut(Side::Right));
+            self.left_renderer.render(e, simulation.board_mut(Side::Left));
+            BoardRenderer::exchange_attacks(e, &mut self.left_renderer, &mut self.right_renderer);
+        }
+
+        let font = &self.font;
+        let status = self.status();
+        let controller = match self.phase {
+            Phase::Playing(_, ref rollback) => Some(rollback.simulation().controller()),
+            _ => None,
+        };
+        let layout = &self.layout;
+
+        let watching = match self.broadcast.len() {
+            0 => None,
+            n => Some(format!("{} watching", n)),
+        };
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+
+            if let Some(controller) = controller {
+                render_match(font, controller, layout, &c, g);
+            }
+
+            if let Some(ref text) = watching {
+                let size = c.get_view_size();
+                let c = c.trans((size[0] - font.width(text)) / 2.0, size[1] - font.height() * 2.0);
+                font.draw(text, [0.8, 0.8, 0.8, 1.0], c.transform, g);
+            }
+
+            if let Some(ref text) = status {
+                render_banner(font, text, [1.0, 1.0, 1.0, 1.0], &c, g);
+            }
+        });
+    }
+}
+
+impl Mode for Online {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        {
+            let clock = &mut self.clock;
+            let accumulator = &mut self.accumulator;
+            e.update(|args| {
+                *clock += args.dt;
+                *accumulator += args.dt;
+            });
+        }
+
+        let phase = mem::replace(&mut self.phase, Phase::Over(String::new()));
+        self.phase = match self.advance(phase, e, left_player, right_player, paused, &mut result) {
+            Ok(phase) => phase,
+            Err(reason) => Phase::Over(reason),
+        };
+
+        self.render(e);
+
+        result
+    }
+
+    /// A round can't be restarted without the other player agreeing, so
+    /// this does nothing.
+    fn restart(&mut self) {}
+
+    fn can_restart(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for Online {
+    fn drop(&mut self) {
+        match self.phase {
+            Phase::Greeting(ref mut connection) | Phase::Playing(ref mut connection, _) => {
+                let _ = connection.send(&Message::Bye);
+            },
+            _ => {}
+        }
+    }
+}
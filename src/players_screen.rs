@@ -0,0 +1,260 @@
+use piston_window::*;
+
+use controls::Controls;
+use human_player::HumanPlayer;
+use match_controller::Side;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use profiles::*;
+use wrapper_types::*;
+
+// Top left of the list of seats.
+const LIST_X: f64 = 24.0;
+const LIST_Y: f64 = 48.0;
+
+// Left edge of the leaderboard.
+const LADDER_X: f64 = 340.0;
+
+// Profiles shown on the leaderboard.
+const LADDER_LENGTH: usize = 15;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Row {
+    Seat(Side),
+    New,
+    Done,
+}
+
+/// Pick who is sitting in each seat, make new profiles, and see the
+/// leaderboard. Selecting a seat moves on to the next profile no one else
+/// is using, then back to a guest. A seated profile brings its own
+/// controls, and versus matches between two profiles are rated.
+pub struct PlayersScreen {
+    profiles: PlayerProfiles,
+    rows: Vec<Row>,
+    selected: usize,
+    // The name of a new profile, while it is typed.
+    typing: Option<String>,
+    // Text from the key that started typing arrives after it, and is not
+    // part of the name.
+    accept_text: bool,
+    error: Option<String>,
+    font: Font,
+    layout: Layout,
+}
+
+impl PlayersScreen {
+    pub fn new(layout: Layout) -> Self {
+        PlayersScreen {
+            profiles: PlayerProfiles::load(),
+            rows: vec!(Row::Seat(Side::Left), Row::Seat(Side::Right), Row::New, Row::Done),
+            selected: 0,
+            typing: None,
+            accept_text: false,
+            error: None,
+            font: Font::new(2.0),
+            layout: layout,
+        }
+    }
+
+    // The next free profile after the current one, or a guest after the
+    // last one.
+    fn next_profile(&self, current: Option<&str>, other: &HumanPlayer) -> Option<String> {
+        let names: Vec<String> = self.profiles.names().into_iter()
+            .filter(|name| Some(&name[..]) != other.profile())
+            .collect();
+
+        match current.and_then(|current| names.iter().position(|name| name == current)) {
+            Some(i) => names.get(i + 1).cloned(),
+            None => names.first().cloned(),
+        }
+    }
+
+    // Sit the next profile in a seat whose controls don't clash with the
+    // other seat's, skipping any that do. If even a guest's clash, the seat
+    // is left as it is.
+    fn seat_next(&mut self, player: &mut HumanPlayer, other: &HumanPlayer, side: Side) {
+        let mut profile = self.next_profile(player.profile(), other);
+        self.error = None;
+
+        loop {
+            match self.seat(player, other, side, profile.clone()) {
+                Ok(()) => return,
+                Err(e) => {
+                    let name = profile.as_ref().map(|name| &name[..]).unwrap_or("Guest").to_string();
+                    self.error = Some(format!("{} can't sit there: {}", name, e));
+                },
+            }
+
+            if profile.is_none() {
+                return;
+            }
+            profile = self.next_profile(profile.as_ref().map(|name| &name[..]), other);
+        }
+    }
+
+    fn seat(&self, player: &mut HumanPlayer, other: &HumanPlayer, side: Side, profile: Option<String>) -> Result<(), String> {
+        let controls = match profile.as_ref().and_then(|name| self.profiles.get(name)) {
+            Some(profile) => profile.controls().player(side).clone(),
+            None => Controls::load().player(side).clone(),
+        };
+
+        let seating = match side {
+            Side::Left => Controls::new(controls.clone(), other.controls().clone()),
+            Side::Right => Controls::new(other.controls().clone(), controls.clone()),
+        };
+        try!(seating.validate());
+
+        player.set_controls(&controls);
+        player.set_profile(profile);
+        Ok(())
+    }
+
+    // Take in typing. Returns the name once it has been entered.
+    fn type_name(&mut self, e: &GameWindow) -> Option<String> {
+        if let Some(button) = e.press_args() {
+            self.accept_text = true;
+
+            match button {
+                Button::Keyboard(Key::Backspace) => {
+                    if let Some(ref mut name) = self.typing {
+                        name.pop();
+                    }
+                },
+                Button::Keyboard(Key::Return) => return self.typing.take(),
+                Button::Keyboard(Key::Escape) => self.typing = None,
+                _ => {}
+            }
+        }
+
+        if let Some(text) = e.text_args() {
+            if let (true, Some(name)) = (self.accept_text, self.typing.as_mut()) {
+                for c in text.chars() {
+                    if (c.is_ascii_alphanumeric() || c == '_' || c == '-') && name.len() < MAX_NAME {
+                        name.push(c);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn render(&self, e: &GameWindow, left_player: &HumanPlayer, right_player: &HumanPlayer) {
+        let font = &self.font;
+        let line = font.height() * 2.0;
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let grey = [0.8, 0.8, 0.8, 1.0];
+        let highlight = [1.0, 0.8, 0.1, 1.0];
+
+        let mut labels = vec!(
+            ("Players".to_string(), LIST_X, LIST_Y, highlight),
+            ("Ladder".to_string(), LADDER_X, LIST_Y, highlight),
+        );
+
+        for (i, &row) in self.rows.iter().enumerate() {
+            let color = if i == self.selected { highlight } else { white };
+            let y = LIST_Y + line * (i + 1) as f64;
+
+            let text = match row {
+                Row::Seat(side) => {
+                    let (label, player) = match side {
+                        Side::Left => ("Left", left_player),
+                        Side::Right => ("Right", right_player),
+                    };
+                    format!("{}: {}", label, player.profile().unwrap_or("Guest"))
+                },
+                Row::New => match self.typing {
+                    Some(ref name) => format!("Name: {}_", name),
+                    None => "New profile".to_string(),
+                },
+                Row::Done => "Done".to_string(),
+            };
+            labels.push((text, LIST_X, y, color));
+        }
+
+        if let Some(ref error) = self.error {
+            let y = LIST_Y + line * (self.rows.len() + 2) as f64;
+            labels.push((error.clone(), LIST_X, y, [1.0, 0.3, 0.3, 1.0]));
+        }
+
+        for (i, profile) in self.profiles.leaderboard().iter().take(LADDER_LENGTH).enumerate() {
+            let text = format!("{}. {} {:.0} {}-{}",
+                               i + 1, profile.name(), profile.rating(), profile.wins(), profile.losses());
+            labels.push((text, LADDER_X, LIST_Y + line * (i + 1) as f64, grey));
+        }
+
+        let layout = &self.layout;
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let origin = layout.origin();
+            let c = layout.apply(&c).trans(origin.x(), origin.y());
+
+            for &(ref text, x, y, color) in &labels {
+                font.draw(text, color, c.trans(x, y).transform, g);
+            }
+        });
+    }
+}
+
+impl Mode for PlayersScreen {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            let mut command = None;
+            for c in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                command = c.or(command);
+            }
+
+            if self.typing.is_some() {
+                // Keys are letters for now, not menu commands.
+                if let Some(name) = self.type_name(e) {
+                    self.error = self.profiles.create(&name, Controls::load()).err();
+                }
+            } else {
+                let n = self.rows.len();
+                match command {
+                    Some(MenuCommand::Up) => self.selected = (self.selected + n - 1) % n,
+                    Some(MenuCommand::Down) => self.selected = (self.selected + 1) % n,
+                    Some(MenuCommand::Select) => match self.rows[self.selected] {
+                        Row::Seat(Side::Left) => self.seat_next(left_player, right_player, Side::Left),
+                        Row::Seat(Side::Right) => self.seat_next(right_player, left_player, Side::Right),
+                        Row::New => {
+                            self.typing = Some(String::new());
+                            self.accept_text = false;
+                            self.error = None;
+                        },
+                        Row::Done => {
+                            result = Some(ModeEvent::Finished);
+                        },
+                    },
+                    Some(MenuCommand::Pause) => {
+                        result = Some(ModeEvent::Finished);
+                    },
+                    None => {}
+                }
+            }
+        }
+
+        self.render(e, left_player, right_player);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.selected = 0;
+        self.typing = None;
+    }
+}
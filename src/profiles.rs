@@ -0,0 +1,387 @@
+use std::fs::{self,File};
+use std::io::{Read,Write};
+use std::path::{Path,PathBuf};
+
+use controls::{Controls,PlayerControls};
+use match_controller::Side;
+use records::data_dir;
+
+/// Version of the profile file format. Profiles written by a newer version
+/// of the game are left alone rather than misread.
+pub const PROFILE_VERSION: u32 = 1;
+
+/// Rating of a new profile.
+pub const STARTING_RATING: f64 = 1500.0;
+
+/// Most a rating can change by in one match.
+pub const K_FACTOR: f64 = 32.0;
+
+/// Longest name a profile can have.
+pub const MAX_NAME: usize = 12;
+
+/// Chance, from 0 to 1, of a player with `rating` beating one with
+/// `opponent`.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// Elo ratings of the winner and loser of a match, after it. Beating a
+/// stronger player is worth more than beating a weaker one.
+pub fn elo(winner: f64, loser: f64) -> (f64, f64) {
+    let change = K_FACTOR * (1.0 - expected_score(winner, loser));
+    (winner + change, loser - change)
+}
+
+/// Names are short and safe to use as file names: letters, digits, `_` and
+/// `-`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= MAX_NAME &&
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// A match in a profile's history, written `won bob 2-1 1516`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchRecord {
+    pub opponent: String,
+    pub won: bool,
+    // Rounds won and lost.
+    pub wins: u32,
+    pub losses: u32,
+    // Rating after the match.
+    pub rating: f64,
+}
+
+impl MatchRecord {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 4 {
+            return Err(format!("Expected `won|lost OPPONENT WINS-LOSSES RATING`, got `{}`", line));
+        }
+
+        let won = match words[0] {
+            "won" => true,
+            "lost" => false,
+            x => return Err(format!("Expected won or lost, got `{}`", x)),
+        };
+
+        let rounds: Vec<&str> = words[2].split('-').collect();
+        let (wins, losses) = match (rounds.get(0).and_then(|x| x.parse().ok()),
+                                    rounds.get(1).and_then(|x| x.parse().ok())) {
+            (Some(wins), Some(losses)) if rounds.len() == 2 => (wins, losses),
+            _ => return Err(format!("`{}` is not a score", words[2])),
+        };
+
+        let rating = match words[3].parse::<f64>() {
+            Ok(x) if x.is_finite() => x,
+            _ => return Err(format!("`{}` is not a rating", words[3])),
+        };
+
+        Ok(MatchRecord {
+            opponent: words[1].to_string(),
+            won: won,
+            wins: wins,
+            losses: losses,
+            rating: rating,
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("{} {} {}-{} {}",
+                if self.won { "won" } else { "lost" },
+                self.opponent, self.wins, self.losses, self.rating)
+    }
+}
+
+/// A named player at this computer, with their own controls, settings,
+/// rating and match history. Kept in `profiles/NAME.ini` in the data
+/// directory:
+///
+/// ```text
+/// version = 1
+/// rating = 1516
+///
+/// [left]
+/// clockwise = key S, button 0 1
+/// ...
+///
+/// [history]
+/// won bob 2-1 1516
+/// ```
+///
+/// The `[left]` and `[right]` sections are as in controls.ini, for whichever
+/// side the player sits on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerProfile {
+    name: String,
+    rating: f64,
+    controls: Controls,
+    history: Vec<MatchRecord>,
+}
+
+impl PlayerProfile {
+    pub fn new(name: &str, controls: Controls) -> Self {
+        PlayerProfile {
+            name: name.to_string(),
+            rating: STARTING_RATING,
+            controls: controls,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+    pub fn rating(&self) -> f64 { self.rating }
+    pub fn controls(&self) -> &Controls { &self.controls }
+    pub fn history(&self) -> &[MatchRecord] { &self.history }
+
+    pub fn wins(&self) -> usize {
+        self.history.iter().filter(|record| record.won).count()
+    }
+
+    pub fn losses(&self) -> usize {
+        self.history.len() - self.wins()
+    }
+
+    pub fn parse(name: &str, contents: &str) -> Result<Self, String> {
+        let mut version = None;
+        let mut rating = STARTING_RATING;
+        let mut history = Vec::new();
+        // The [left] and [right] sections, handed on to Controls.
+        let mut controls = String::new();
+        let mut section: Option<String> = None;
+
+        for raw in contents.lines() {
+            let line = raw.trim();
+            if line.starts_with("[") && line.ends_with("]") {
+                section = Some(line[1..line.len() - 1].to_string());
+            }
+
+            match section.as_ref().map(|s| &s[..]) {
+                Some("history") => {
+                    if !line.is_empty() && !line.starts_with("#") && !line.starts_with("[") {
+                        history.push(try!(MatchRecord::parse(line)));
+                    }
+                },
+                Some(_) => {
+                    controls.push_str(raw);
+                    controls.push('\n');
+                },
+                None => {
+                    if line.is_empty() || line.starts_with("#") {
+                        continue;
+                    }
+
+                    let (key, value) = match line.find('=') {
+                        Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                        None => return Err(format!("Expected `key = value`, got `{}`", line)),
+                    };
+
+                    match key {
+                        "version" => version = Some(try!(value.parse::<u32>()
+                            .map_err(|_| format!("`{}` is not a version", value)))),
+                        "rating" => rating = match value.parse::<f64>() {
+                            Ok(x) if x.is_finite() => x,
+                            _ => return Err(format!("`{}` is not a rating", value)),
+                        },
+                        _ => return Err(format!("Unknown key `{}`", key)),
+                    }
+                },
+            }
+        }
+
+        match version {
+            None => return Err("No version".to_string()),
+            Some(version) if version > PROFILE_VERSION => {
+                return Err(format!("Written by a newer version of the game (profile version {})", version));
+            },
+            _ => {}
+        }
+
+        Ok(PlayerProfile {
+            name: name.to_string(),
+            rating: rating,
+            controls: try!(Controls::parse(&controls)),
+            history: history,
+        })
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = format!(
+            "# Player profile for {}\n\
+             version = {}\n\
+             rating = {}\n\n",
+            self.name, PROFILE_VERSION, self.rating);
+
+        config.push_str(&self.controls.to_config());
+
+        config.push_str("\n[history]\n");
+        for record in &self.history {
+            config.push_str(&record.to_line());
+            config.push('\n');
+        }
+
+        config
+    }
+}
+
+/// Every profile in a directory. Unreadable profiles are reported and
+/// skipped, and never overwritten.
+pub struct PlayerProfiles {
+    dir: Option<PathBuf>,
+    profiles: Vec<PlayerProfile>,
+}
+
+impl PlayerProfiles {
+    /// Profiles from the data directory, or none that are never saved if
+    /// there is no data directory.
+    pub fn load() -> Self {
+        match data_dir() {
+            Some(dir) => PlayerProfiles::load_from(&dir.join("profiles")),
+            None => PlayerProfiles {
+                dir: None,
+                profiles: Vec::new(),
+            },
+        }
+    }
+
+    pub fn load_from(dir: &Path) -> Self {
+        let mut profiles = Vec::new();
+
+        let paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect(),
+            Err(_) => Vec::new(),
+        };
+
+        for path in paths {
+            let name = match (path.file_stem().and_then(|s| s.to_str()), path.extension()) {
+                (Some(name), Some(extension)) if extension == "ini" && valid_name(name) => name.to_string(),
+                _ => continue,
+            };
+
+            let mut contents = String::new();
+            let profile = File::open(&path)
+                .and_then(|mut f| f.read_to_string(&mut contents))
+                .map_err(|e| e.to_string())
+                .and_then(|_| PlayerProfile::parse(&name, &contents));
+
+            match profile {
+                Ok(profile) => profiles.push(profile),
+                Err(e) => println!("{}: {}, skipping it", path.display(), e),
+            }
+        }
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+        PlayerProfiles {
+            dir: Some(dir.to_path_buf()),
+            profiles: profiles,
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PlayerProfile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<&mut PlayerProfile, String> {
+        self.profiles.iter_mut()
+            .find(|profile| profile.name == name)
+            .ok_or(format!("No profile called {}", name))
+    }
+
+    /// Start a new profile with the given controls, and save it.
+    pub fn create(&mut self, name: &str, controls: Controls) -> Result<(), String> {
+        if !valid_name(name) {
+            return Err(format!("Names are up to {} letters, digits, _ or -", MAX_NAME));
+        }
+        // Including ones that couldn't be read.
+        let taken = self.get(name).is_some() ||
+            self.path(name).map_or(false, |path| path.exists());
+        if taken {
+            return Err(format!("{} already exists", name));
+        }
+
+        let profile = PlayerProfile::new(name, controls);
+        try!(self.save(&profile));
+        self.profiles.push(profile);
+        self.profiles.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    /// Change the controls a profile uses on one side, and save it. Refused
+    /// if it would leave the other side with an action unbound.
+    pub fn set_controls(&mut self, name: &str, side: Side, controls: PlayerControls) -> Result<(), String> {
+        let profile = {
+            let profile = try!(self.get_mut(name));
+            let mut changed = profile.controls.clone();
+            changed.set_player(side, controls);
+            try!(changed.validate().map_err(|e| format!("{}: {}", name, e)));
+
+            profile.controls = changed;
+            profile.clone()
+        };
+
+        self.save(&profile)
+    }
+
+    /// Rate a match between two profiles and add it to both their
+    /// histories, saving them.
+    pub fn record(&mut self, winner: &str, loser: &str, wins: u32, losses: u32) -> Result<(), String> {
+        if winner == loser {
+            return Err("A player can't play themselves".to_string());
+        }
+
+        let (winner_rating, loser_rating) = elo(
+            try!(self.get_mut(winner)).rating,
+            try!(self.get_mut(loser)).rating);
+
+        let results = vec!(
+            (winner, loser, true, wins, losses, winner_rating),
+            (loser, winner, false, losses, wins, loser_rating),
+        );
+        for (name, opponent, won, wins, losses, rating) in results {
+            let profile = {
+                let profile = try!(self.get_mut(name));
+                profile.rating = rating;
+                profile.history.push(MatchRecord {
+                    opponent: opponent.to_string(),
+                    won: won,
+                    wins: wins,
+                    losses: losses,
+                    rating: rating,
+                });
+                profile.clone()
+            };
+            try!(self.save(&profile));
+        }
+
+        Ok(())
+    }
+
+    /// Profiles from highest rated down.
+    pub fn leaderboard(&self) -> Vec<&PlayerProfile> {
+        let mut profiles: Vec<&PlayerProfile> = self.profiles.iter().collect();
+        profiles.sort_by(|a, b| b.rating.partial_cmp(&a.rating).unwrap().then(a.name.cmp(&b.name)));
+        profiles
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.ini", name)))
+    }
+
+    fn save(&self, profile: &PlayerProfile) -> Result<(), String> {
+        let path = match self.path(&profile.name) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            try!(fs::create_dir_all(dir).map_err(|e| e.to_string()));
+        }
+
+        File::create(&path)
+            .and_then(|mut f| f.write_all(profile.to_config().as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,72 @@
+use std::cmp::max;
+
+// Seconds spent on each level.
+const LEVEL_DURATION: f64 = 30.0;
+
+// Step interval at level 1, each level multiplies it by SPEEDUP down to a
+// floor of MIN_INTERVAL.
+const BASE_INTERVAL: f64 = 0.8;
+const SPEEDUP: f64 = 0.85;
+const MIN_INTERVAL: f64 = 0.1;
+
+// Seconds between garbage drops from the simulated opponent.
+const GARBAGE_INTERVAL: f64 = 15.0;
+
+/// Difficulty curve for single player endless mode. Stands in for an
+/// opponent: sends garbage on a timer, growing with the level. Attacks sent
+/// by the player count against the next drop.
+pub struct Progression {
+    elapsed: f64,
+    garbage_timer: f64,
+    counter: u32,
+}
+
+impl Progression {
+    pub fn new() -> Self {
+        Progression {
+            elapsed: 0.0,
+            garbage_timer: 0.0,
+            counter: 0,
+        }
+    }
+
+    pub fn elapsed(&self) -> f64 { self.elapsed }
+
+    pub fn level(&self) -> u32 {
+        1 + (self.elapsed / LEVEL_DURATION) as u32
+    }
+
+    /// Seconds between steps of the falling piece for the current level.
+    pub fn step_interval(&self) -> f64 {
+        let interval = BASE_INTERVAL * SPEEDUP.powi(self.level() as i32 - 1);
+
+        interval.max(MIN_INTERVAL)
+    }
+
+    /// Size of the next garbage drop, before any counter.
+    pub fn garbage_size(&self) -> u32 {
+        self.level() + 1
+    }
+
+    /// Record attack strength sent by the player.
+    pub fn counter(&mut self, strength: u32) {
+        self.counter += strength;
+    }
+
+    /// Advance the clock. Returns the number of sprinkles to drop on the
+    /// player, which is zero most of the time.
+    pub fn update(&mut self, dt: f64) -> u32 {
+        self.elapsed += dt;
+        self.garbage_timer += dt;
+
+        if self.garbage_timer < GARBAGE_INTERVAL {
+            return 0;
+        }
+
+        self.garbage_timer -= GARBAGE_INTERVAL;
+
+        let size = max(self.garbage_size() as i32 - self.counter as i32, 0) as u32;
+        self.counter = 0;
+        size
+    }
+}
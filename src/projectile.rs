@@ -0,0 +1,67 @@
+use values::*;
+
+/// Seconds an attack takes to reach the board it was sent to.
+pub const FLIGHT_TIME: f64 = 0.6;
+
+/// Radius of the smallest attack in flight, how much each point of strength
+/// adds, and the largest any attack gets.
+pub const MIN_RADIUS: f64 = 4.0;
+pub const RADIUS_PER_STRENGTH: f64 = 0.5;
+pub const MAX_RADIUS: f64 = 16.0;
+
+/// How far a flight arcs up from a straight line at its highest, as a
+/// fraction of the distance flown.
+pub const ARC: f64 = 0.25;
+
+/// An attack flying from where a combo broke on one board to the warning
+/// gauge of the other. Positions are in view units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Projectile {
+    from: PixelPosition,
+    to: PixelPosition,
+    strength: u32,
+    age: f64,
+}
+
+impl Projectile {
+    pub fn new(from: PixelPosition, to: PixelPosition, strength: u32) -> Self {
+        Projectile {
+            from: from,
+            to: to,
+            strength: strength,
+            age: 0.0,
+        }
+    }
+
+    pub fn strength(&self) -> u32 { self.strength }
+    pub fn age(&self) -> f64 { self.age }
+
+    pub fn update(&mut self, dt: f64) {
+        self.age += dt;
+    }
+
+    pub fn has_landed(&self) -> bool {
+        self.age >= FLIGHT_TIME
+    }
+
+    /// Larger for stronger attacks.
+    pub fn radius(&self) -> f64 {
+        (MIN_RADIUS + self.strength as f64 * RADIUS_PER_STRENGTH).min(MAX_RADIUS)
+    }
+
+    /// Where the attack is some seconds into its flight. It starts slowly,
+    /// speeds up across the middle and slows into the gauge.
+    pub fn position_at(&self, t: f64) -> PixelPosition {
+        let f = (t / FLIGHT_TIME).max(0.0).min(1.0);
+        let f = if f < 0.5 { 2.0 * f * f } else { 1.0 - 2.0 * (1.0 - f) * (1.0 - f) };
+
+        let (dx, dy) = (self.to.x() - self.from.x(), self.to.y() - self.from.y());
+        let lift = ARC * (dx * dx + dy * dy).sqrt() * 4.0 * f * (1.0 - f);
+
+        PixelPosition::new(self.from.x() + dx * f, self.from.y() + dy * f - lift)
+    }
+
+    pub fn position(&self) -> PixelPosition {
+        self.position_at(self.age)
+    }
+}
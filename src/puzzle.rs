@@ -0,0 +1,187 @@
+use std::fs::{self,File};
+use std::io::Read;
+use std::path::Path;
+
+use values::*;
+use board::Board;
+
+/// What a puzzle asks of the player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Goal {
+    // Leave no blocks on the board.
+    ClearAll,
+    // Send at least this much attack strength in total.
+    Damage(u32),
+    // Trigger a chain at least this many breaks deep.
+    Chain(u32),
+}
+
+impl Goal {
+    pub fn is_met(&self, board: &Board) -> bool {
+        match *self {
+            Goal::ClearAll => board.grid().blocks().is_empty(),
+            Goal::Damage(x) => board.stats().attack_sent >= x,
+            Goal::Chain(x) => board.stats().best_chain >= x,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match *self {
+            Goal::ClearAll => "Clear all blocks".to_string(),
+            Goal::Damage(x) => format!("Send {} damage", x),
+            Goal::Chain(x) => format!("Make a {} chain", x),
+        }
+    }
+}
+
+/// An authored challenge: a starting grid, a fixed sequence of pieces and a
+/// goal to reach with them.
+///
+/// Puzzle files are plain text. Blank lines and lines starting with # are
+/// ignored until the grid, which runs to the end of the file:
+///
+/// ```text
+/// name: Warm up
+/// goal: clear            (or "damage 6", or "chain 3")
+/// pieces: Rr GY
+/// grid:
+/// RR  GG
+/// ```
+///
+/// Pieces and the grid use the add_blocks notation. Each piece is two
+/// characters, the first being the bottom block as it appears.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    pub goal: Goal,
+    pieces: Vec<(Block, Block)>,
+    grid: Vec<String>,
+}
+
+impl Puzzle {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut contents = String::new();
+
+        try!(File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)));
+
+        Puzzle::parse(&contents)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut goal = None;
+        let mut pieces = None;
+        let mut grid: Option<Vec<String>> = None;
+
+        for line in contents.lines() {
+            if let Some(ref mut grid) = grid {
+                grid.push(line.trim_right().to_string());
+                continue;
+            }
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+
+            let (key, value) = match line.find(':') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("Expected `key: value`, got `{}`", line)),
+            };
+
+            match key {
+                "name"   => name = Some(value.to_string()),
+                "goal"   => goal = Some(try!(parse_goal(value))),
+                "pieces" => pieces = Some(try!(parse_pieces(value))),
+                "grid"   => grid = Some(Vec::new()),
+                _ => return Err(format!("Unknown key `{}`", key)),
+            }
+        }
+
+        Ok(Puzzle {
+            name: try!(name.ok_or("Missing name".to_string())),
+            goal: try!(goal.ok_or("Missing goal".to_string())),
+            pieces: try!(pieces.ok_or("Missing pieces".to_string())),
+            grid: try!(grid.ok_or("Missing grid".to_string())),
+        })
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Fresh pieces for an attempt at the puzzle. Blocks are new each time,
+    /// so they are not confused with those from a previous attempt.
+    pub fn pieces(&self) -> Vec<Piece> {
+        self.pieces.iter().map(|&(bottom, top)| {
+            Piece::new(fresh(bottom), fresh(top))
+        }).collect()
+    }
+
+    /// A board set up with the starting grid and piece sequence.
+    pub fn board(&self, dimensions: Dimension) -> Board {
+        let mut board = Board::new(dimensions);
+
+        // The last line is the floor, so trailing blank lines would leave the
+        // grid hanging in the air.
+        let mut grid = self.grid.clone();
+        while grid.last().map_or(false, |line| line.is_empty()) {
+            grid.pop();
+        }
+
+        board.add_blocks(grid);
+        board.fuse_blocks();
+        board.set_piece_sequence(self.pieces());
+        board
+    }
+}
+
+/// Load every .txt puzzle in a directory, ordered by file name.
+pub fn load_all(dir: &Path) -> Result<Vec<Puzzle>, String> {
+    let entries = try!(fs::read_dir(dir)
+        .map_err(|e| format!("Could not list {}: {}", dir.display(), e)));
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| Puzzle::load(path)).collect()
+}
+
+fn fresh(block: Block) -> Block {
+    Block::new(block.color, block.breaker())
+}
+
+fn parse_goal(value: &str) -> Result<Goal, String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+
+    let amount = |i: usize| -> Result<u32, String> {
+        words.get(i)
+            .and_then(|x| x.parse().ok())
+            .ok_or(format!("Goal `{}` needs a number", value))
+    };
+
+    match words.get(0) {
+        Some(&"clear")  => Ok(Goal::ClearAll),
+        Some(&"damage") => Ok(Goal::Damage(try!(amount(1)))),
+        Some(&"chain")  => Ok(Goal::Chain(try!(amount(1)))),
+        _ => Err(format!("Unknown goal `{}`", value)),
+    }
+}
+
+fn parse_pieces(value: &str) -> Result<Vec<(Block, Block)>, String> {
+    value.split_whitespace().map(|piece| {
+        let blocks: Vec<Option<Block>> = piece.chars().map(Block::from_char).collect();
+
+        match (blocks.len(), blocks.get(0), blocks.get(1)) {
+            (2, Some(&Some(bottom)), Some(&Some(top))) => Ok((bottom, top)),
+            _ => Err(format!("Bad piece `{}`", piece)),
+        }
+    }).collect()
+}
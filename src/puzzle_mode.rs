@@ -0,0 +1,193 @@
+extern crate find_folder;
+
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board::Board;
+use board_renderer::*;
+use human_player::HumanPlayer;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use puzzle::{self,Puzzle};
+use versus::{TEXT_MARGIN,render_banner};
+use wrapper_types::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Attempt {
+    Playing,
+    Solved,
+    Failed,
+}
+
+/// Work through the authored puzzles in assets/puzzles in order. A puzzle
+/// is checked whenever the board settles: solved as soon as the goal is
+/// met, failed once the pieces run out without meeting it.
+pub struct PuzzleMode {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    puzzles: Vec<Puzzle>,
+    index: usize,
+    attempt: Attempt,
+
+    board: Board,
+    renderer: GameBoardRenderer,
+    render_state: RenderState,
+    font: Font,
+}
+
+impl PuzzleMode {
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout) -> Self {
+
+        let dir = find_folder::Search::ParentsThenKids(3, 3)
+            .for_folder("assets/puzzles").ok()
+            .expect("No assets/puzzles directory found");
+
+        let puzzles = puzzle::load_all(&dir).unwrap_or_else(|e| panic!("{}", e));
+        assert!(!puzzles.is_empty(), "No puzzles found in {}", dir.display());
+
+        let board = puzzles[0].board(dimensions);
+
+        PuzzleMode {
+            renderer: BoardRenderer::new(textures.clone(), layout.center(), dimensions),
+            render_state: RenderState::new(),
+            board: board,
+            font: Font::new(3.0),
+
+            puzzles: puzzles,
+            index: 0,
+            attempt: Attempt::Playing,
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+        }
+    }
+
+    fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.index]
+    }
+
+    fn check(&mut self) {
+        if self.puzzle().goal.is_met(&self.board) {
+            self.attempt = Attempt::Solved;
+        } else if self.board.is_out_of_pieces() || self.board.is_game_over() {
+            self.attempt = Attempt::Failed;
+        }
+    }
+
+    fn render_hud(&self, e: &GameWindow) {
+        let font = &self.font;
+        let puzzle = self.puzzle();
+        let layout = self.layout;
+        let remaining = self.board.pieces_remaining().unwrap_or(0);
+        let header = vec!(
+            format!("Puzzle {}/{}: {}", self.index + 1, self.puzzles.len(), puzzle.name),
+            format!("{} with {} pieces ({} left)", puzzle.goal.describe(), puzzle.piece_count(), remaining),
+        );
+        let banner = match self.attempt {
+            Attempt::Playing => None,
+            Attempt::Solved if self.index + 1 == self.puzzles.len() => Some("All puzzles solved!"),
+            Attempt::Solved => Some("Solved! Press to continue"),
+            Attempt::Failed => Some("Failed! Press to retry"),
+        };
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+
+            for (i, text) in header.iter().enumerate() {
+                let y = layout.top() + TEXT_MARGIN + font.height() * 1.5 * i as f64;
+                font.draw(text, [1.0, 1.0, 1.0, 1.0], c.trans(layout.center().x(), y).transform, g);
+            }
+
+            if let Some(text) = banner {
+                render_banner(font, text, [1.0, 0.8, 0.1, 1.0], &c, g);
+            }
+        });
+    }
+}
+
+impl Mode for PuzzleMode {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            match self.attempt {
+                Attempt::Playing => {
+                    let left = left_player.update(e, &mut self.board);
+                    let right = right_player.update(e, &mut self.board);
+
+                    if left.or(right) == Some(MenuCommand::Pause) {
+                        result = Some(ModeEvent::Pause);
+                    }
+
+                    let mut settled = false;
+                    {
+                        let board = &mut self.board;
+                        let render_state = &self.render_state;
+
+                        e.update(|args| {
+                            board.update(args.dt, render_state);
+                            board.take_attack();
+                            settled = board.is_waiting_for_piece() || board.is_game_over();
+                        });
+                    }
+
+                    if settled {
+                        self.check();
+                    }
+                },
+                attempt => {
+                    let mut pressed = false;
+                    for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                        pressed |= command.is_some();
+                    }
+
+                    if pressed {
+                        if attempt == Attempt::Solved {
+                            if self.index + 1 == self.puzzles.len() {
+                                result = Some(ModeEvent::Finished);
+                            } else {
+                                self.index += 1;
+                            }
+                        }
+                        self.restart();
+                    }
+                }
+            }
+        }
+
+        self.renderer.set_paused(paused);
+        self.renderer.set_layout(self.layout.center(), self.layout.scale());
+        self.renderer.set_squash(left_player.controls().settings().squash);
+
+        self.renderer.render_into(e, &mut self.board, &mut self.render_state);
+
+        self.render_hud(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.board = self.puzzle().board(self.dimensions);
+        self.renderer = BoardRenderer::new(self.textures.clone(), self.layout.center(), self.dimensions);
+        self.render_state = RenderState::new();
+        self.attempt = Attempt::Playing;
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self,File};
+use std::io::{Read,Write};
+use std::path::{Path,PathBuf};
+
+/// Where personal bests and other saved data live: ~/.puzzlefighter
+pub fn data_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".puzzlefighter"))
+}
+
+/// Personal bests, stored one `key=value` per line. A missing or unreadable
+/// file just means there are no records yet; records are never worth
+/// crashing the game over.
+#[derive(Clone, Debug)]
+pub struct Records {
+    path: Option<PathBuf>,
+    values: BTreeMap<String, u32>,
+}
+
+impl Records {
+    /// Records from the data directory, or empty ones that are never saved
+    /// if there is no data directory.
+    pub fn load() -> Self {
+        match data_dir() {
+            Some(dir) => Records::load_from(&dir.join("records.txt")),
+            None => Records {
+                path: None,
+                values: BTreeMap::new(),
+            }
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        let mut contents = String::new();
+        let _ = File::open(path).and_then(|mut f| f.read_to_string(&mut contents));
+
+        let mut values = BTreeMap::new();
+        for line in contents.lines() {
+            if let Some(i) = line.find('=') {
+                if let Ok(value) = line[i + 1..].trim().parse() {
+                    values.insert(line[..i].trim().to_string(), value);
+                }
+            }
+        }
+
+        Records {
+            path: Some(path.to_path_buf()),
+            values: values,
+        }
+    }
+
+    pub fn best(&self, key: &str) -> Option<u32> {
+        self.values.get(key).cloned()
+    }
+
+    /// Record a result, saving it if it beats the previous best. Returns
+    /// true for a new best.
+    pub fn submit(&mut self, key: &str, value: u32) -> bool {
+        if self.best(key).map_or(false, |best| best >= value) {
+            return false;
+        }
+
+        self.values.insert(key.to_string(), value);
+        if let Err(e) = self.save() {
+            println!("Could not save records: {}", e);
+        }
+        true
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            try!(fs::create_dir_all(dir).map_err(|e| e.to_string()));
+        }
+
+        let mut contents = String::new();
+        for (key, value) in &self.values {
+            contents.push_str(&format!("{}={}\n", key, value));
+        }
+
+        File::create(path)
+            .and_then(|mut f| f.write_all(contents.as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use controls::Action;
+use input::Command;
+use lockstep::*;
+use match_controller::*;
+
+/// Ticks ahead of the other player's known input that play can guess, by
+/// default. Zero waits for every input, like plain lockstep.
+pub const DEFAULT_MAX_ROLLBACK: u64 = 8;
+
+/// Runs one side of an online match. Each side plays its own commands a
+/// couple of ticks after they happen and sends them to the other. Rather
+/// than wait for the other player's commands, play carries on guessing that
+/// they did nothing. When their real commands turn up and the guess was
+/// wrong, play goes back to the last tick both sides agree on and is played
+/// again. Checksums of the agreed state are swapped now and then to catch
+/// the two sides drifting apart.
+pub struct Rollback {
+    side: Side,
+    max_rollback: u64,
+    // The last tick with both players' commands known, and what is on screen,
+    // played on from there with guesses.
+    confirmed: Simulation,
+    current: Simulation,
+    // Set when a guess already played turned out wrong.
+    mispredicted: bool,
+    rollbacks: u32,
+    // Commands waiting to be scheduled with the next tick played.
+    pending: Vec<Command>,
+    // Commands for ticks from the confirmed one on.
+    local: BTreeMap<u64, Vec<Command>>,
+    remote: BTreeMap<u64, Vec<Command>>,
+    // Checksums from each side not yet matched with the other's.
+    local_hashes: BTreeMap<u64, u64>,
+    remote_hashes: BTreeMap<u64, u64>,
+    outbox: Vec<Message>,
+    // Agreed ticks and round starts, for spectators.
+    played: Vec<Message>,
+}
+
+impl Rollback {
+    /// Play the given side of a simulation, guessing up to max_rollback
+    /// ticks ahead. Nobody has input for the first few ticks.
+    pub fn new(side: Side, simulation: Simulation, max_rollback: u64) -> Self {
+        let mut local = BTreeMap::new();
+        let mut remote = BTreeMap::new();
+
+        for tick in 0..INPUT_DELAY {
+            local.insert(simulation.tick() + tick, Vec::new());
+            remote.insert(simulation.tick() + tick, Vec::new());
+        }
+
+        Rollback {
+            side: side,
+            max_rollback: max_rollback,
+            confirmed: simulation.clone(),
+            current: simulation,
+            mispredicted: false,
+            rollbacks: 0,
+            pending: Vec::new(),
+            local: local,
+            remote: remote,
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+            outbox: Vec::new(),
+            played: Vec::new(),
+        }
+    }
+
+    pub fn side(&self) -> Side { self.side }
+
+    /// The state both sides agree on.
+    pub fn confirmed(&self) -> &Simulation { &self.confirmed }
+
+    /// The state on screen, which may include guesses.
+    pub fn simulation(&self) -> &Simulation { &self.current }
+    pub fn simulation_mut(&mut self) -> &mut Simulation { &mut self.current }
+
+    /// Times play has been rewound to fix a wrong guess.
+    pub fn rollbacks(&self) -> u32 { self.rollbacks }
+
+    /// A command from the local player. The other side can't be paused, so
+    /// pausing just lets go of turbo, as it does locally.
+    pub fn input(&mut self, command: Command) {
+        match command {
+            Command::Press(Action::Pause) => self.pending.push(Command::Release(Action::Turbo)),
+            Command::Release(Action::Pause) => {},
+            _ => self.pending.push(command),
+        }
+    }
+
+    /// Whether the next tick can be played, either because the other
+    /// player's commands for it are known or because it isn't too far ahead
+    /// to guess.
+    pub fn can_step(&self) -> bool {
+        let tick = self.current.tick();
+
+        self.remote.contains_key(&tick) || tick - self.confirmed.tick() < self.max_rollback
+    }
+
+    /// Catch up on ticks whose commands have all arrived, then play the next
+    /// tick if possible, sending the local commands gathered since the last
+    /// one. Returns events from newly agreed ticks. Fails if the sides no
+    /// longer agree.
+    pub fn step(&mut self) -> Result<Option<MatchEvent>, String> {
+        let event = try!(self.confirm());
+
+        if self.can_step() {
+            let tick = self.current.tick();
+            let commands: Vec<Command> = self.pending.drain(..).collect();
+            self.outbox.push(Message::Input(tick + INPUT_DELAY, commands.clone()));
+            self.local.insert(tick + INPUT_DELAY, commands);
+
+            self.play_current();
+        }
+
+        Ok(event)
+    }
+
+    // Play the next tick on screen, guessing the other player did nothing if
+    // their commands aren't known.
+    fn play_current(&mut self) {
+        let tick = self.current.tick();
+        let local = self.local.get(&tick).cloned().unwrap_or(Vec::new());
+        let remote = self.remote.get(&tick).cloned().unwrap_or(Vec::new());
+
+        match self.side {
+            Side::Left => self.current.step(&local, &remote),
+            Side::Right => self.current.step(&remote, &local),
+        };
+    }
+
+    /// Play agreed ticks, and if a guess was wrong, go back and play the
+    /// ticks since again. Returns the last event from the agreed ticks.
+    pub fn confirm(&mut self) -> Result<Option<MatchEvent>, String> {
+        let mut event = None;
+
+        loop {
+            let tick = self.confirmed.tick();
+            if tick >= self.current.tick() || !self.remote.contains_key(&tick) {
+                break;
+            }
+
+            let local = self.local.remove(&tick).unwrap_or(Vec::new());
+            let remote = self.remote.remove(&tick).unwrap_or(Vec::new());
+            let (left, right) = match self.side {
+                Side::Left => (local, remote),
+                Side::Right => (remote, local),
+            };
+            let result = self.confirmed.step(&left, &right);
+            self.confirmed.clear_events();
+            event = result.or(event);
+
+            self.played.push(Message::Play(tick, left, right));
+            if result == Some(MatchEvent::NextRound) {
+                self.played.push(self.confirmed.round_start());
+            }
+
+            if tick % HASH_INTERVAL == 0 {
+                let hash = self.confirmed.checksum();
+                self.outbox.push(Message::Hash(tick, hash));
+                self.local_hashes.insert(tick, hash);
+                try!(self.compare(tick));
+            }
+        }
+
+        if self.mispredicted {
+            let tick = self.current.tick();
+
+            self.current = self.confirmed.clone();
+            while self.current.tick() < tick {
+                self.play_current();
+            }
+
+            // What was drawn came from the wrong guess. The renderers catch
+            // up with the boards as they are, rather than replaying these.
+            self.current.clear_events();
+            self.mispredicted = false;
+            self.rollbacks += 1;
+        }
+
+        Ok(event)
+    }
+
+    /// Take in a message from the other side.
+    pub fn receive(&mut self, message: Message) -> Result<(), String> {
+        match message {
+            Message::Input(tick, commands) => {
+                if tick < self.confirmed.tick() {
+                    return Err(format!("Input for tick {} arrived after it was played", tick));
+                }
+
+                // Play guessed that nothing happened.
+                if tick < self.current.tick() && !commands.is_empty() {
+                    self.mispredicted = true;
+                }
+                self.remote.insert(tick, commands);
+            },
+            Message::Hash(tick, hash) => {
+                self.remote_hashes.insert(tick, hash);
+                try!(self.compare(tick));
+            },
+            Message::Bye => return Err("Opponent left".to_string()),
+            message => return Err(format!("Unexpected `{}`", message.to_line())),
+        }
+
+        Ok(())
+    }
+
+    fn compare(&mut self, tick: u64) -> Result<(), String> {
+        if let (Some(&local), Some(&remote)) = (self.local_hashes.get(&tick), self.remote_hashes.get(&tick)) {
+            if local != remote {
+                return Err(format!("Out of sync at tick {}", tick));
+            }
+            self.local_hashes.remove(&tick);
+            self.remote_hashes.remove(&tick);
+        }
+
+        Ok(())
+    }
+
+    /// Messages to send to the other side, oldest first.
+    pub fn outgoing(&mut self) -> Vec<Message> {
+        self.outbox.drain(..).collect()
+    }
+
+    /// Play and Round messages for ticks agreed since the last call, oldest
+    /// first.
+    pub fn played(&mut self) -> Vec<Message> {
+        self.played.drain(..).collect()
+    }
+}
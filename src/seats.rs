@@ -0,0 +1,350 @@
+use std::fs::File;
+use std::io::{Read,Write};
+use std::path::{Path,PathBuf};
+
+use controls::Binding;
+use input::InputEvent;
+use match_controller::Side;
+use records::data_dir;
+
+/// How one kind of controller's buttons and axes map onto the standard
+/// layout that gamepad bindings are written for. Anything not listed maps
+/// to itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    // Lower case pieces of device names this profile is for.
+    matches: Vec<String>,
+    // (device, standard) pairs.
+    buttons: Vec<(u8, u8)>,
+    axes: Vec<(u8, u8)>,
+}
+
+impl Profile {
+    pub fn new(name: &str) -> Self {
+        Profile {
+            name: name.to_string(),
+            matches: Vec::new(),
+            buttons: Vec::new(),
+            axes: Vec::new(),
+        }
+    }
+
+    fn is_for(&self, device: &str) -> bool {
+        let device = device.to_lowercase();
+        self.matches.iter().any(|x| device.contains(&x[..]))
+    }
+
+    pub fn button(&self, button: u8) -> u8 {
+        self.buttons.iter().find(|x| x.0 == button).map(|x| x.1).unwrap_or(button)
+    }
+
+    pub fn axis(&self, axis: u8) -> u8 {
+        self.axes.iter().find(|x| x.0 == axis).map(|x| x.1).unwrap_or(axis)
+    }
+}
+
+/// Controller profiles, kept in gamepads.ini next to controls.ini. Devices
+/// no profile matches use the standard layout: a generic USB pad, with the
+/// face buttons numbered clockwise from the top and start on 9.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        let profile = |name: &str, matches: Vec<&str>, buttons: Vec<(u8, u8)>| {
+            Profile {
+                matches: matches.into_iter().map(|x| x.to_string()).collect(),
+                buttons: buttons,
+                .. Profile::new(name)
+            }
+        };
+
+        Profiles {
+            profiles: vec!(
+                profile("Xbox", vec!("xbox", "x-box", "microsoft"),
+                    vec!((0, 2), (1, 1), (2, 3), (3, 0), (6, 8), (7, 9))),
+                profile("PlayStation", vec!("playstation", "sony", "wireless controller"),
+                    vec!((0, 2), (1, 1), (2, 0), (3, 3))),
+            ),
+        }
+    }
+}
+
+impl Profiles {
+    pub fn path() -> Option<PathBuf> {
+        data_dir().map(|dir| dir.join("gamepads.ini"))
+    }
+
+    /// Profiles from the config file, written out with the defaults if there
+    /// is no file yet.
+    pub fn load() -> Self {
+        let path = match Profiles::path() {
+            Some(path) => path,
+            None => return Profiles::default(),
+        };
+
+        if !path.exists() {
+            let profiles = Profiles::default();
+            if let Err(e) = profiles.save_to(&path) {
+                println!("Could not write default gamepad profiles: {}", e);
+            }
+            return profiles;
+        }
+
+        let mut contents = String::new();
+        let result = File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| e.to_string())
+            .and_then(|_| Profiles::parse(&contents));
+
+        match result {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                println!("{}: {}, using default gamepad profiles", path.display(), e);
+                Profiles::default()
+            }
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut profiles: Vec<Profile> = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+
+            if line.starts_with("[") && line.ends_with("]") {
+                profiles.push(Profile::new(&line[1..line.len() - 1]));
+                continue;
+            }
+
+            let profile = try!(profiles.last_mut()
+                .ok_or(format!("`{}` is not under a [profile]", line)));
+
+            let (key, value) = match line.find('=') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(format!("Expected `key = value`, got `{}`", line)),
+            };
+
+            if key == "match" {
+                profile.matches = value.split(',')
+                    .map(|x| x.trim().to_lowercase())
+                    .filter(|x| !x.is_empty())
+                    .collect();
+                continue;
+            }
+
+            let words: Vec<&str> = key.split_whitespace().collect();
+            let error = format!("Bad mapping `{}`", line);
+            let from: Option<u8> = if words.len() == 2 { words[1].parse().ok() } else { None };
+            let to: Option<u8> = value.parse().ok();
+            let pair = match (from, to) {
+                (Some(from), Some(to)) => (from, to),
+                _ => return Err(error),
+            };
+
+            match words[0] {
+                "button" => profile.buttons.push(pair),
+                "axis" => profile.axes.push(pair),
+                _ => return Err(error),
+            }
+        }
+
+        Ok(Profiles { profiles: profiles })
+    }
+
+    pub fn to_config(&self) -> String {
+        let mut config = String::from(
+            "# Gamepad layouts. Bindings in controls.ini are for a generic USB pad,\n\
+             # with face buttons 0 to 3 clockwise from the top and start on 9.\n\
+             # A profile is used for devices with any of its `match` words in\n\
+             # their name, and maps their buttons and axes onto that layout:\n\
+             # `button 0 = 2` makes the device's button 0 act as button 2.\n");
+
+        for profile in &self.profiles {
+            config.push_str(&format!("\n[{}]\nmatch = {}\n", profile.name, profile.matches.join(", ")));
+
+            for &(from, to) in &profile.buttons {
+                config.push_str(&format!("button {} = {}\n", from, to));
+            }
+            for &(from, to) in &profile.axes {
+                config.push_str(&format!("axis {} = {}\n", from, to));
+            }
+        }
+
+        config
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            try!(::std::fs::create_dir_all(dir).map_err(|e| e.to_string()));
+        }
+
+        File::create(path)
+            .and_then(|mut f| f.write_all(self.to_config().as_bytes()))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// The first profile for a device, or the standard layout.
+    pub fn for_device(&self, name: &str) -> Profile {
+        self.profiles.iter()
+            .find(|profile| profile.is_for(name))
+            .cloned()
+            .unwrap_or(Profile::new("Standard"))
+    }
+}
+
+/// A connected gamepad. The id is the one its input events carry.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gamepad {
+    pub id: i32,
+    pub name: String,
+}
+
+impl Gamepad {
+    pub fn new(id: i32, name: &str) -> Self {
+        Gamepad {
+            id: id,
+            name: name.to_string(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SeatEvent {
+    // A gamepad was given to the player on this side.
+    Joined(Side),
+    // The player on this side's gamepad was unplugged.
+    Lost(Side),
+}
+
+fn seat_number(side: Side) -> i32 {
+    match side {
+        Side::Left => 0,
+        Side::Right => 1,
+    }
+}
+
+/// Input from a seated gamepad, rewritten as the seat's pad in the standard
+/// layout. Gamepad bindings use seat numbers rather than device ids, so
+/// `button 0 1` is button 1 on whichever pad joined the left seat.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeatPad {
+    id: i32,
+    seat: i32,
+    profile: Profile,
+}
+
+impl SeatPad {
+    /// The event as the seat sees it. Keyboard input passes through, input
+    /// from other gamepads is None.
+    pub fn translate(&self, event: InputEvent) -> Option<InputEvent> {
+        let binding = |binding| match binding {
+            Binding::Key(_) => Some(binding),
+            Binding::Button(id, button) if id == self.id =>
+                Some(Binding::Button(self.seat, self.profile.button(button))),
+            Binding::Axis(id, axis, positive) if id == self.id =>
+                Some(Binding::Axis(self.seat, self.profile.axis(axis), positive)),
+            _ => None,
+        };
+
+        match event {
+            InputEvent::Press(b) => binding(b).map(InputEvent::Press),
+            InputEvent::Release(b) => binding(b).map(InputEvent::Release),
+            InputEvent::Axis(id, axis, position) if id == self.id =>
+                Some(InputEvent::Axis(self.seat, self.profile.axis(axis), position)),
+            InputEvent::Axis(..) => None,
+        }
+    }
+}
+
+/// Which gamepad each player is using. Gamepads start out unseated, and
+/// pressing a button on one gives it to the first player without one.
+/// Keyboards are always available to both players.
+pub struct Seats {
+    gamepads: Vec<Gamepad>,
+    left: Option<i32>,
+    right: Option<i32>,
+}
+
+impl Seats {
+    pub fn new() -> Self {
+        Seats {
+            gamepads: Vec::new(),
+            left: None,
+            right: None,
+        }
+    }
+
+    pub fn connect(&mut self, gamepad: Gamepad) {
+        self.disconnect(gamepad.id);
+        self.gamepads.push(gamepad);
+    }
+
+    /// Forget a gamepad, freeing its seat if it had one.
+    pub fn disconnect(&mut self, id: i32) -> Option<SeatEvent> {
+        self.gamepads.retain(|x| x.id != id);
+
+        for side in vec!(Side::Left, Side::Right) {
+            if *self.seat_mut(side) == Some(id) {
+                *self.seat_mut(side) = None;
+                return Some(SeatEvent::Lost(side));
+            }
+        }
+
+        None
+    }
+
+    /// Seat a connected gamepad if this is a button press on it and there
+    /// is a free seat.
+    pub fn handle(&mut self, event: InputEvent) -> Option<SeatEvent> {
+        let id = match event {
+            InputEvent::Press(Binding::Button(id, _)) => id,
+            _ => return None,
+        };
+
+        if !self.gamepads.iter().any(|x| x.id == id) ||
+            self.left == Some(id) || self.right == Some(id) {
+            return None;
+        }
+
+        for side in vec!(Side::Left, Side::Right) {
+            if self.seat_mut(side).is_none() {
+                *self.seat_mut(side) = Some(id);
+                return Some(SeatEvent::Joined(side));
+            }
+        }
+
+        None
+    }
+
+    pub fn gamepad(&self, side: Side) -> Option<&Gamepad> {
+        let id = match side {
+            Side::Left => self.left,
+            Side::Right => self.right,
+        };
+
+        id.and_then(|id| self.gamepads.iter().find(|x| x.id == id))
+    }
+
+    /// How to read the gamepad seated on a side, if any.
+    pub fn pad(&self, side: Side, profiles: &Profiles) -> Option<SeatPad> {
+        self.gamepad(side).map(|gamepad| SeatPad {
+            id: gamepad.id,
+            seat: seat_number(side),
+            profile: profiles.for_device(&gamepad.name),
+        })
+    }
+
+    fn seat_mut(&mut self, side: Side) -> &mut Option<i32> {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+}
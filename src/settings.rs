@@ -0,0 +1,88 @@
+use board::DEFAULT_PREVIEW;
+use gravity::SQUASH;
+
+/// Most upcoming pieces a player can ask to see.
+pub const MAX_PREVIEW: usize = 5;
+
+/// Most a player can have blocks squash on landing.
+pub const MAX_SQUASH: f64 = 0.5;
+
+/// How a player likes their input handled and their board shown. Times are
+/// in seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlayerSettings {
+    // Delayed auto shift: how long a button is held before it repeats.
+    pub das: f64,
+    // Auto repeat rate: time between repeats once repeating.
+    pub arr: f64,
+    // How many times faster than the starting speed pieces fall in turbo.
+    pub soft_drop: f64,
+    // How far a stick must be pushed, from 0 to 1, to count as a press.
+    pub dead_zone: f64,
+    // Upcoming pieces shown, counting the next piece.
+    pub preview: usize,
+    // How far blocks squash on landing at full speed, from 0 for not at all.
+    pub squash: f64,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        PlayerSettings {
+            das: 0.2,
+            arr: 0.05,
+            soft_drop: 16.0,
+            dead_zone: 0.8,
+            preview: DEFAULT_PREVIEW,
+            squash: SQUASH,
+        }
+    }
+}
+
+impl PlayerSettings {
+    /// Names of the settings, as used in config files.
+    pub fn keys() -> Vec<&'static str> {
+        vec!("das", "arr", "soft_drop", "dead_zone", "preview", "squash")
+    }
+
+    pub fn get(&self, key: &str) -> Option<f64> {
+        match key {
+            "das"       => Some(self.das),
+            "arr"       => Some(self.arr),
+            "soft_drop" => Some(self.soft_drop),
+            "dead_zone" => Some(self.dead_zone),
+            "preview"   => Some(self.preview as f64),
+            "squash"    => Some(self.squash),
+            _ => None,
+        }
+    }
+
+    /// Set a setting from its config file form, checking it is in range.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let x: f64 = try!(value.parse()
+            .map_err(|_| format!("`{}` for {} is not a number", value, key)));
+
+        if key == "preview" {
+            if x.fract() != 0.0 || x < 1.0 || x > MAX_PREVIEW as f64 {
+                return Err(format!("preview must be a whole number from 1 to {}, got {}", MAX_PREVIEW, value));
+            }
+            self.preview = x as usize;
+            return Ok(());
+        }
+
+        let (field, valid, range) = match key {
+            "das"       => (&mut self.das, x >= 0.0, "at least 0"),
+            "arr"       => (&mut self.arr, x > 0.0, "more than 0"),
+            "soft_drop" => (&mut self.soft_drop, x >= 1.0, "at least 1"),
+            "dead_zone" => (&mut self.dead_zone, x > 0.0 && x < 1.0, "between 0 and 1"),
+            "squash"    => (&mut self.squash, x >= 0.0 && x <= MAX_SQUASH, "from 0 to 0.5"),
+            _ => return Err(format!("Unknown setting `{}`", key)),
+        };
+
+        if !valid {
+            return Err(format!("{} must be {}, got {}", key, range, value));
+        }
+
+        *field = x;
+        Ok(())
+    }
+}
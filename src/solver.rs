@@ -0,0 +1,76 @@
+use values::*;
+use board::Board;
+use puzzle::Puzzle;
+
+/// Where to drop a piece: the column of its first block, and the direction
+/// of the second block from the first.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub x: i8,
+    pub direction: Direction,
+}
+
+/// Find placements for each of a puzzle's pieces that reach its goal, by
+/// trying every placement of every piece. Returns None if the puzzle can't
+/// be solved. Puzzles only have a handful of pieces, so brute force is fine.
+pub fn solve(puzzle: &Puzzle, dimensions: Dimension) -> Option<Vec<Placement>> {
+    let board = puzzle.board(dimensions);
+    let pieces = puzzle.pieces();
+
+    search(puzzle, &board, &pieces)
+}
+
+fn search(puzzle: &Puzzle, board: &Board, pieces: &[Piece]) -> Option<Vec<Placement>> {
+    if puzzle.goal.is_met(board) {
+        return Some(Vec::new());
+    }
+
+    let piece = match pieces.first() {
+        Some(piece) => *piece,
+        None => return None,
+    };
+
+    for placement in placements(board) {
+        let positioned = piece.dup_to(
+            GridPosition::new(placement.x, board.dimensions().h() as i8),
+            placement.direction);
+
+        if !fits(board, positioned) {
+            continue;
+        }
+
+        let mut attempt = board.clone();
+        attempt.play_piece(positioned);
+        attempt.consume_events();
+
+        if let Some(mut rest) = search(puzzle, &attempt, &pieces[1..]) {
+            rest.insert(0, placement);
+            return Some(rest);
+        }
+    }
+
+    None
+}
+
+fn placements(board: &Board) -> Vec<Placement> {
+    let mut result = Vec::new();
+
+    for direction in Direction::all() {
+        for x in 0..board.dimensions().w() {
+            result.push(Placement {
+                x: x as i8,
+                direction: direction,
+            });
+        }
+    }
+
+    result
+}
+
+// A piece fits if both blocks are on the board, in empty cells. Pieces move
+// above the visible area, so any column with room at the top is reachable.
+fn fits(board: &Board, piece: Piece) -> bool {
+    piece.blocks().iter().all(|pb| {
+        pb.x() >= 0 && pb.x() < board.dimensions().w() as i8 && board.grid().empty(*pb)
+    })
+}
@@ -0,0 +1,197 @@
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board_renderer::*;
+use broadcast::Spectator;
+use human_player::HumanPlayer;
+use lockstep::*;
+use match_controller::*;
+use menu::MenuCommand;
+use net::Connection;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use versus::{render_banner,render_match};
+use wrapper_types::*;
+
+/// Watch an online match being played, from its host. Nothing can be done
+/// but watch, and leave from the pause menu.
+pub struct Spectate {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    connection: Option<Connection>,
+    spectator: Spectator,
+    // Why watching stopped, if it has.
+    error: Option<String>,
+
+    left_renderer: GameBoardRenderer,
+    right_renderer: GameBoardRenderer,
+    font: Font,
+}
+
+impl Spectate {
+    /// Watch the match hosted at `host:port`. Blocks until connected or
+    /// given up.
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout,
+               address: &str) -> Self {
+
+        let mut spectate = Spectate {
+            left_renderer: BoardRenderer::new(textures.clone(), layout.left(), dimensions),
+            right_renderer: BoardRenderer::with_preview_on(textures.clone(), layout.right(), dimensions, Side::Right),
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+
+            connection: None,
+            spectator: Spectator::new(dimensions),
+            error: None,
+
+            font: Font::new(3.0),
+        };
+
+        let connection = Connection::connect(address).and_then(|mut connection| {
+            try!(connection.send(&Message::Watch { version: PROTOCOL_VERSION }));
+            Ok(connection)
+        });
+        match connection {
+            Ok(connection) => spectate.connection = Some(connection),
+            Err(e) => spectate.error = Some(e),
+        }
+
+        spectate
+    }
+
+    fn reset_renderers(&mut self) {
+        self.left_renderer = BoardRenderer::new(self.textures.clone(), self.layout.left(), self.dimensions);
+        self.right_renderer = BoardRenderer::with_preview_on(self.textures.clone(), self.layout.right(), self.dimensions, Side::Right);
+    }
+
+    // Take in what the host has sent.
+    fn receive(&mut self) -> Result<(), String> {
+        if let Some(ref mut connection) = self.connection {
+            for message in try!(connection.receive()) {
+                try!(self.spectator.receive(message));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn status(&self) -> Option<String> {
+        let started = self.spectator.simulation().is_some();
+
+        // What has arrived is shown out before any error.
+        match self.error {
+            Some(ref error) if !started || self.spectator.is_buffering() => {
+                Some(format!("{} - press pause to leave", error))
+            },
+            _ if !started => Some("Waiting for the match to start...".to_string()),
+            _ if self.spectator.is_buffering() => Some("Buffering...".to_string()),
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, e: &GameWindow) {
+        self.left_renderer.set_layout(self.layout.left(), self.layout.scale());
+        self.right_renderer.set_layout(self.layout.right(), self.layout.scale());
+
+        if let Some(simulation) = self.spectator.simulation_mut() {
+            self.right_renderer.render(e, simulation.board_mut(Side::Right));
+            self.left_renderer.render(e, simulation.board_mut(Side::Left));
+            BoardRenderer::exchange_attacks(e, &mut self.left_renderer, &mut self.right_renderer);
+        }
+
+        let font = &self.font;
+        let status = self.status();
+        let controller = self.spectator.simulation().map(|simulation| simulation.controller());
+        let layout = &self.layout;
+
+        e.draw_2d(|c, g| {
+            let c = layout.apply(&c);
+
+            if let Some(controller) = controller {
+                render_match(font, controller, layout, &c, g);
+            }
+
+            if let Some(ref text) = status {
+                render_banner(font, text, [1.0, 1.0, 1.0, 1.0], &c, g);
+            }
+        });
+    }
+}
+
+impl Mode for Spectate {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                if command == Some(MenuCommand::Pause) {
+                    result = Some(if self.error.is_some() { ModeEvent::Finished } else { ModeEvent::Pause });
+                }
+            }
+        }
+
+        // The match goes on under the pause menu.
+        if self.error.is_none() {
+            if let Err(reason) = self.receive() {
+                self.error = Some(reason);
+            }
+        }
+
+        let mut event = None;
+        {
+            let spectator = &mut self.spectator;
+            e.update(|args| {
+                event = spectator.update(args.dt);
+            });
+        }
+
+        if self.spectator.take_skipped() {
+            self.left_renderer.resync();
+            self.right_renderer.resync();
+        }
+        match event {
+            Some(MatchEvent::NextRound) => self.reset_renderers(),
+            Some(MatchEvent::Finished) => result = Some(ModeEvent::Finished),
+            None => {}
+        }
+
+        // Shown as the player watching from the left seat likes.
+        let squash = left_player.controls().settings().squash;
+        self.left_renderer.set_squash(squash);
+        self.right_renderer.set_squash(squash);
+        self.render(e);
+
+        result
+    }
+
+    /// Spectators can't restart anything.
+    fn restart(&mut self) {}
+
+    fn can_restart(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for Spectate {
+    fn drop(&mut self) {
+        if let Some(ref mut connection) = self.connection {
+            let _ = connection.send(&Message::Bye);
+        }
+    }
+}
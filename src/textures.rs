@@ -6,45 +6,65 @@ extern crate gfx_texture;
 
 use std::rc::Rc;
 use std::collections::HashMap;
-use std::fs;
+use std::path::Path;
 
 use gfx_texture::{Texture,TextureSettings,Flip};
 
+use themes::*;
 use wrapper_types::*;
 
 pub struct Textures<R> where R: gfx::Resources {
     textures: HashMap<String, Rc<Texture<R>>>,
 }
 
-impl Textures<gfx_device_gl::Resources> {
-    pub fn new(window: &GameWindow) -> Self {
-        let assets = find_folder::Search::ParentsThenKids(3, 3)
-            .for_folder("assets/gen").ok()
-            .expect("No assets/ directory found");
+/// Every theme to choose from, the default theme first, built from the
+/// generated images in assets/gen.
+pub fn themes() -> Vec<Theme> {
+    let assets = find_folder::Search::ParentsThenKids(3, 3)
+        .for_folder("assets").ok()
+        .expect("No assets/ directory found");
+
+    let default = Theme::from_images(DEFAULT_THEME, &assets.join("gen"))
+        .expect("Could not list contents of assets dir");
 
-        let paths = fs::read_dir(assets).ok()
-            .expect("Could not list contents of assets dir");
+    let mut themes = vec!(default);
+    themes.extend(find_themes(&theme_dirs(&assets)));
+    themes
+}
+
+impl Textures<gfx_device_gl::Resources> {
+    /// Load an image for every name the default theme has, from the given
+    /// theme where it has one that loads.
+    pub fn new(window: &GameWindow, theme: &Theme, default: &Theme) -> Self {
+        let load = |path: &Path| {
+            Texture::from_path(
+                &mut *window.factory.borrow_mut(),
+                path,
+                Flip::None,
+                &TextureSettings::new()
+            )
+        };
 
         let mut store = HashMap::new();
 
         // TODO: Do this off-thread or otherwise as part of a loading routine.
         // Currently shows an ugly white screen while it loads.
-        for filename in paths {
-            let filename = filename.unwrap();
-            if let Some(ext) = filename.path().extension() {
-                if ext == "png" {
-                    let texture = Texture::from_path(
-                            &mut *window.factory.borrow_mut(),
-                            filename.path(),
-                            Flip::None,
-                            &TextureSettings::new()
-                        )
-                        .ok()
-                        .expect(&format!("Could not load texture: {}", filename.path().display()));
-
-                    store.insert(filename.path().file_name().unwrap().to_str().unwrap().to_string(), Rc::new(texture));
-                }
-            }
+        for name in default.names() {
+            let themed = theme.image(name).and_then(|path| match load(path) {
+                Ok(texture) => Some(texture),
+                Err(e) => {
+                    println!("Could not load {} from theme {}, using the default: {}", path.display(), theme.name(), e);
+                    None
+                },
+            });
+
+            let texture = themed.unwrap_or_else(|| {
+                let path = default.image(name).unwrap();
+                load(path).ok()
+                    .expect(&format!("Could not load texture: {}", path.display()))
+            });
+
+            store.insert(name.to_string(), Rc::new(texture));
         }
 
         Textures {
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fs::{self,File};
+use std::io::Read;
+use std::path::{Path,PathBuf};
+
+use records::data_dir;
+
+/// File in a theme's directory listing its images.
+pub const MANIFEST: &'static str = "theme.ini";
+
+/// Name of the theme built from the game's own images.
+pub const DEFAULT_THEME: &'static str = "default";
+
+/// Images for blocks and backgrounds, by the logical names the game asks
+/// for: a block's texture name, such as `red`, `red_2`, `blue_breaker` or
+/// `green_bl`, or one of `board`, `splash`, `ferns` and `press-to-start`.
+/// A theme need not have an image for every name; the default theme's is
+/// used for any it lacks.
+///
+/// A theme pack is a directory with a `theme.ini` manifest:
+///
+/// ```ini
+/// name = Neon
+///
+/// [images]
+/// red = blocks/red.png
+/// board = frame.png
+/// ```
+///
+/// Image paths are relative to the theme's directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    name: String,
+    images: BTreeMap<String, PathBuf>,
+}
+
+impl Theme {
+    /// A theme of every PNG in a directory, each named for its file name
+    /// without the extension, as the default theme is.
+    pub fn from_images(name: &str, dir: &Path) -> Result<Self, String> {
+        let entries = try!(fs::read_dir(dir)
+            .map_err(|e| format!("Could not list {}: {}", dir.display(), e)));
+
+        let mut images = BTreeMap::new();
+        for entry in entries {
+            let path = try!(entry.map_err(|e| format!("Could not list {}: {}", dir.display(), e))).path();
+
+            if path.extension().map_or(false, |ext| ext == "png") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    images.insert(stem.to_string(), path.clone());
+                }
+            }
+        }
+
+        Ok(Theme {
+            name: name.to_string(),
+            images: images,
+        })
+    }
+
+    /// Read a theme pack's manifest. Images it lists that are not there are
+    /// left out, with a warning, to fall back on the default theme's.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST);
+        let mut contents = String::new();
+
+        try!(File::open(&path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| format!("Could not read {}: {}", path.display(), e)));
+
+        let mut theme = try!(Theme::parse(dir, &contents)
+            .map_err(|e| format!("{}: {}", path.display(), e)));
+
+        let missing: Vec<String> = theme.images.iter()
+            .filter(|&(_, image)| !image.exists())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in missing {
+            println!("Theme {} has no image for {} at {}, using the default",
+                     theme.name, name, theme.images[&name].display());
+            theme.images.remove(&name);
+        }
+
+        Ok(theme)
+    }
+
+    /// Parse a manifest for a theme pack in the given directory.
+    pub fn parse(dir: &Path, contents: &str) -> Result<Self, String> {
+        let mut name = None;
+        let mut images = BTreeMap::new();
+        let mut in_images = false;
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("#") {
+                continue;
+            }
+
+            if line.starts_with("[") && line.ends_with("]") {
+                match &line[1..line.len() - 1] {
+                    "images" => in_images = true,
+                    section => return Err(format!("Unknown section [{}]", section)),
+                }
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if !key.trim().is_empty() && !value.trim().is_empty() =>
+                    (key.trim(), value.trim()),
+                _ => return Err(format!("Expected `name = value`, got `{}`", line)),
+            };
+
+            if in_images {
+                images.insert(key.to_string(), dir.join(value));
+            } else if key == "name" {
+                name = Some(value.to_string());
+            } else {
+                return Err(format!("Unknown setting `{}`", key));
+            }
+        }
+
+        match name {
+            Some(name) => Ok(Theme {
+                name: name,
+                images: images,
+            }),
+            None => Err("No name given for the theme".to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str { &self.name }
+
+    /// Every logical name this theme has an image for.
+    pub fn names(&self) -> Vec<&str> {
+        self.images.keys().map(|name| &name[..]).collect()
+    }
+
+    pub fn image(&self, name: &str) -> Option<&Path> {
+        self.images.get(name).map(|path| path.as_path())
+    }
+}
+
+/// Where theme packs are looked for: the game's own, under the assets
+/// directory, then the player's, in the data directory.
+pub fn theme_dirs(assets: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec!(assets.join("themes"));
+    if let Some(dir) = data_dir() {
+        dirs.push(dir.join("themes"));
+    }
+    dirs
+}
+
+/// Theme packs in some directories, sorted by name: every subdirectory
+/// with a manifest. Broken packs, and any with the name of one found
+/// earlier or of the default theme, are reported and skipped.
+pub fn find_themes(dirs: &[PathBuf]) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = Vec::new();
+
+    for dir in dirs {
+        let mut packs: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.join(MANIFEST).exists())
+                .collect(),
+            Err(_) => continue,
+        };
+        packs.sort();
+
+        for pack in packs {
+            match Theme::load(&pack) {
+                Ok(ref theme) if theme.name == DEFAULT_THEME || themes.iter().any(|t| t.name == theme.name) => {
+                    println!("Skipping {}: there is already a theme called {}", pack.display(), theme.name);
+                },
+                Ok(theme) => themes.push(theme),
+                Err(e) => println!("Skipping theme: {}", e),
+            }
+        }
+    }
+
+    themes.sort_by(|a, b| a.name.cmp(&b.name));
+    themes
+}
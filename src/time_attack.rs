@@ -0,0 +1,183 @@
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board::Board;
+use board_renderer::*;
+use human_player::HumanPlayer;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use records::Records;
+use versus::{TEXT_MARGIN,render_breakdown};
+use wrapper_types::*;
+
+// Length of a run, in seconds.
+const TIME_LIMIT: f64 = 120.0;
+
+const RECORD_KEY: &'static str = "time_attack";
+
+/// Single player race to send as much attack strength as possible before the
+/// clock runs out. Nothing is sent back, so the only way to lose early is to
+/// top out.
+pub struct TimeAttack {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    board: Board,
+    renderer: GameBoardRenderer,
+    render_state: RenderState,
+    font: Font,
+
+    time_left: f64,
+    records: Records,
+    // Best before this run, and whether this run beat it. Set when the run
+    // ends.
+    previous_best: Option<u32>,
+    new_best: bool,
+    finished: bool,
+}
+
+impl TimeAttack {
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout) -> Self {
+
+        TimeAttack {
+            board: Board::new(dimensions),
+            renderer: BoardRenderer::new(textures.clone(), layout.center(), dimensions),
+            render_state: RenderState::new(),
+            font: Font::new(3.0),
+
+            time_left: TIME_LIMIT,
+            records: Records::load(),
+            previous_best: None,
+            new_best: false,
+            finished: false,
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+        }
+    }
+
+    fn finish(&mut self) {
+        let damage = self.board.stats().attack_sent;
+
+        self.finished = true;
+        self.previous_best = self.records.best(RECORD_KEY);
+        self.new_best = self.records.submit(RECORD_KEY, damage);
+    }
+
+    fn render_hud(&self, e: &GameWindow) {
+        let font = &self.font;
+        let stats = self.board.stats();
+        let layout = self.layout;
+        let seconds = self.time_left.ceil() as u32;
+        let best = if self.finished { self.previous_best } else { self.records.best(RECORD_KEY) };
+
+        let mut text = format!("Time {}:{:02}  Damage {}", seconds / 60, seconds % 60, stats.attack_sent);
+        if let Some(best) = best {
+            text.push_str(&format!("  Best {}", best));
+        }
+
+        let mut lines = vec!(
+            if self.time_left > 0.0 { "Game over!" } else { "Time up!" }.to_string(),
+            format!("Damage sent {}", stats.attack_sent),
+            format!("Largest gem {} blocks", stats.largest_gem),
+            format!("Best combo {} ({} chain)", stats.best_combo, stats.best_chain),
+        );
+        lines.push(if self.new_best {
+            "New personal best!".to_string()
+        } else {
+            format!("Personal best {}", best.unwrap_or(0))
+        });
+        let finished = self.finished;
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+
+            let top = c.trans(layout.center().x(), layout.top() + TEXT_MARGIN);
+            font.draw(&text, [1.0, 1.0, 1.0, 1.0], top.transform, g);
+
+            if finished {
+                render_breakdown(font, &lines, &c, g);
+            }
+        });
+    }
+}
+
+impl Mode for TimeAttack {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            if self.finished {
+                // Wait for a button before leaving the breakdown.
+                for command in vec!(left_player.update_menu(e), right_player.update_menu(e)) {
+                    if command.is_some() {
+                        result = Some(ModeEvent::Finished);
+                    }
+                }
+            } else {
+                let left = left_player.update(e, &mut self.board);
+                let right = right_player.update(e, &mut self.board);
+
+                if left.or(right) == Some(MenuCommand::Pause) {
+                    result = Some(ModeEvent::Pause);
+                }
+
+                let mut done = false;
+                {
+                    let board = &mut self.board;
+                    let render_state = &self.render_state;
+                    let time_left = &mut self.time_left;
+
+                    e.update(|args| {
+                        board.update(args.dt, render_state);
+                        board.take_attack();
+
+                        *time_left = (*time_left - args.dt).max(0.0);
+                        done = *time_left <= 0.0 || board.is_game_over();
+                    });
+                }
+
+                if done {
+                    self.finish();
+                }
+            }
+        }
+
+        self.renderer.set_paused(paused);
+        self.renderer.set_layout(self.layout.center(), self.layout.scale());
+        self.renderer.set_squash(left_player.controls().settings().squash);
+
+        self.renderer.render_into(e, &mut self.board, &mut self.render_state);
+
+        self.render_hud(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.board = Board::new(self.dimensions);
+        self.renderer = BoardRenderer::new(self.textures.clone(), self.layout.center(), self.dimensions);
+        self.render_state = RenderState::new();
+        self.time_left = TIME_LIMIT;
+        self.previous_best = None;
+        self.new_best = false;
+        self.finished = false;
+    }
+}
@@ -0,0 +1,220 @@
+use std::rc::Rc;
+
+use piston_window::*;
+
+use values::*;
+use board::Board;
+use board_renderer::*;
+use human_player::HumanPlayer;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use versus::TEXT_MARGIN;
+use wrapper_types::*;
+
+// Number of placements that can be undone.
+const HISTORY_LIMIT: usize = 50;
+
+const MAX_ATTACK: u32 = 36;
+
+/// A sandbox for practising setups. Nothing attacks the board unless asked
+/// to, and extra keys pick the next piece, undo placements, send attacks
+/// and toggle gravity:
+///
+/// ```text
+/// 1-4  Color of the first block, again to make it a breaker
+/// 5-8  Color of the second block
+/// U    Undo the last placement
+/// R    Redo
+/// -/=  Attack size
+/// T    Send an attack
+/// G    Toggle gravity
+/// L    Load blocks from board.txt
+/// ```
+pub struct Training {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    board: Board,
+    renderer: GameBoardRenderer,
+    render_state: RenderState,
+    font: Font,
+
+    attack_size: u32,
+    gravity: bool,
+}
+
+impl Training {
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout) -> Self {
+
+        let mut board = Board::new(dimensions);
+        board.set_history_limit(HISTORY_LIMIT);
+
+        Training {
+            board: board,
+            renderer: BoardRenderer::new(textures.clone(), layout.center(), dimensions),
+            render_state: RenderState::new(),
+            font: Font::new(2.0),
+
+            attack_size: 6,
+            gravity: true,
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+        }
+    }
+
+    fn handle_key(&mut self, key: Key) {
+        match key {
+            Key::D1 => self.set_next_block(0, Color::Red),
+            Key::D2 => self.set_next_block(0, Color::Green),
+            Key::D3 => self.set_next_block(0, Color::Blue),
+            Key::D4 => self.set_next_block(0, Color::Yellow),
+            Key::D5 => self.set_next_block(1, Color::Red),
+            Key::D6 => self.set_next_block(1, Color::Green),
+            Key::D7 => self.set_next_block(1, Color::Blue),
+            Key::D8 => self.set_next_block(1, Color::Yellow),
+            Key::U => { self.board.undo(); },
+            Key::R => { self.board.redo(); },
+            Key::Minus => self.attack_size = (self.attack_size - 1).max(1),
+            Key::Equals => self.attack_size = (self.attack_size + 1).min(MAX_ATTACK),
+            Key::T => self.board.attack(self.attack_size),
+            Key::G => {
+                self.gravity = !self.gravity;
+                self.board.set_gravity(self.gravity);
+            },
+            Key::L => self.load_board(),
+            _ => {}
+        }
+    }
+
+    // Choosing the color a block already has toggles it between a breaker
+    // and a normal block.
+    fn set_next_block(&mut self, index: usize, color: Color) {
+        if let Some(mut piece) = self.board.next_piece() {
+            let current = piece.blocks[index];
+            let breaker = current.color == color && !current.breaker();
+
+            piece.blocks[index] = Block::new(color, breaker);
+            self.board.set_next_piece(piece);
+        }
+    }
+
+    // Add the blocks in board.txt, unless it can't be read or won't fit, in
+    // which case the board is left as it is.
+    fn load_board(&mut self) {
+        match self.read_board() {
+            Ok(lines) => {
+                self.board.add_blocks(lines);
+                self.board.fuse_blocks();
+            },
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    fn read_board(&self) -> Result<Vec<String>, String> {
+        use std::io::Read;
+        use std::fs::File;
+
+        let mut contents = String::new();
+        try!(File::open("board.txt")
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| format!("Could not read board.txt: {}", e)));
+
+        let lines: Vec<String> = contents.lines().map(|line| line.to_string()).collect();
+        try!(self.board.check_blocks(&lines).map_err(|e| format!("Could not load board.txt: {}", e)));
+        Ok(lines)
+    }
+
+    fn render_hud(&self, e: &GameWindow) {
+        let font = &self.font;
+        let stats = self.board.stats();
+        let layout = self.layout;
+
+        let lines = vec!(
+            "1-4 First block".to_string(),
+            "5-8 Second block".to_string(),
+            "U/R Undo/redo".to_string(),
+            "-/= Attack size".to_string(),
+            "T   Send attack".to_string(),
+            "G   Gravity".to_string(),
+            "L   Load board.txt".to_string(),
+            String::new(),
+            format!("Attack {}", self.attack_size),
+            format!("Gravity {}", if self.gravity { "on" } else { "off" }),
+            format!("Undo {}  Redo {}", self.board.undo_count(), self.board.redo_count()),
+            String::new(),
+            format!("Last combo {}", stats.last_combo),
+            format!("Chain {}", stats.last_chain),
+            format!("Best combo {}", stats.best_combo),
+            format!("Total {}", stats.attack_sent),
+        );
+
+        e.draw_2d(|c, g| {
+            use graphics::*;
+
+            let c = layout.apply(&c);
+            let line = font.height() * 1.5;
+
+            for (i, text) in lines.iter().enumerate() {
+                let c = c.trans(TEXT_MARGIN, layout.top() + TEXT_MARGIN + line * i as f64);
+                font.draw(text, [1.0, 1.0, 1.0, 1.0], c.transform, g);
+            }
+        });
+    }
+}
+
+impl Mode for Training {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            let left = left_player.update(e, &mut self.board);
+            let right = right_player.update(e, &mut self.board);
+
+            if left.or(right) == Some(MenuCommand::Pause) {
+                result = Some(ModeEvent::Pause);
+            } else if let Some(Button::Keyboard(key)) = e.press_args() {
+                self.handle_key(key);
+            }
+
+            let board = &mut self.board;
+            let render_state = &self.render_state;
+
+            e.update(|args| {
+                board.update(args.dt, render_state);
+                board.take_attack();
+            });
+        }
+
+        self.renderer.set_paused(paused);
+        self.renderer.set_layout(self.layout.center(), self.layout.scale());
+        self.renderer.set_squash(left_player.controls().settings().squash);
+
+        self.renderer.render_into(e, &mut self.board, &mut self.render_state);
+
+        self.render_hud(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.board = Board::new(self.dimensions);
+        self.board.set_history_limit(HISTORY_LIMIT);
+        self.board.set_gravity(self.gravity);
+        self.renderer = BoardRenderer::new(self.textures.clone(), self.layout.center(), self.dimensions);
+        self.render_state = RenderState::new();
+    }
+}
@@ -121,6 +121,22 @@ impl Block {
         }
     }
 
+    /// Parse the single character notation used by board files: a capital
+    /// first letter of a color is a block, lower case is a breaker.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'R' => Some(Block::new(Color::Red, false)),
+            'G' => Some(Block::new(Color::Green, false)),
+            'B' => Some(Block::new(Color::Blue, false)),
+            'Y' => Some(Block::new(Color::Yellow, false)),
+            'r' => Some(Block::new(Color::Red, true)),
+            'g' => Some(Block::new(Color::Green, true)),
+            'b' => Some(Block::new(Color::Blue, true)),
+            'y' => Some(Block::new(Color::Yellow, true)),
+            _   => None
+        }
+    }
+
     pub fn new_with_age(color: Color, age: u8) -> Self {
         Block {
             id: Uuid::new_v4(),
@@ -135,6 +151,7 @@ impl Block {
         self.borders != SIDE_ALL
     }
 
+    /// The logical name of the image for this block, as themes know it.
     pub fn to_texture_name(&self) -> String {
         let name = if self.breaker {
             match self.color {
@@ -162,7 +179,7 @@ impl Block {
             }
         };
 
-        name.to_string() + &self.borders.to_texture_suffix() + ".png"
+        name.to_string() + &self.borders.to_texture_suffix()
     }
 }
 
@@ -314,12 +331,15 @@ impl Piece {
     }
 
     pub fn rand(x: i8, y: i8) -> Self {
-        use self::rand::*;
+        Piece::rand_with(x, y, &mut self::rand::thread_rng())
+    }
 
-        let mut rng = thread_rng();
+    /// A random piece from the given generator, so that seeded generators
+    /// give the same pieces every time.
+    pub fn rand_with<R: self::rand::Rng>(x: i8, y: i8, rng: &mut R) -> Self {
         let pos = GridPosition::new(x, y);
-        let block1 = Block::new(Color::rand(), rng.gen_weighted_bool(4));
-        let block2 = Block::new(Color::rand(), rng.gen_weighted_bool(4));
+        let block1 = Block::new(Color::rand_with(rng), rng.gen_weighted_bool(4));
+        let block2 = Block::new(Color::rand_with(rng), rng.gen_weighted_bool(4));
 
         Piece {
             blocks: [block1, block2],
@@ -389,7 +409,17 @@ impl Piece {
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum BlockEvent {
     Drop(PositionedBlock, PositionedBlock),
-    Explode(PositionedBlock, u32),
+    // A block broke, at some depth into the combo step, as part of a power
+    // gem of some number of blocks, or one for a block on its own.
+    Explode(PositionedBlock, u32, u32),
+    // A counter gem ticked down, and is shown as it is now. At age 0 it has
+    // become a normal gem.
+    Age(PositionedBlock),
+    // A combo finished, sending an attack of some strength.
+    Attack(u32),
+    // The board was replaced wholesale, such as by an undo. Anything drawn
+    // so far should be thrown away and redrawn from the grid.
+    Reset,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -481,15 +511,16 @@ pub enum Color {
 
 impl Color {
     pub fn rand() -> Self {
-        use self::rand::*;
+        Color::rand_with(&mut self::rand::thread_rng())
+    }
 
+    pub fn rand_with<R: self::rand::Rng>(rng: &mut R) -> Self {
         let all = vec![
             Color::Blue,
             Color::Red,
             Color::Green,
             Color::Yellow
         ];
-        let mut rng = rand::thread_rng();
 
         *rng.choose(&all).unwrap()
     }
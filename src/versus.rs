@@ -0,0 +1,253 @@
+use std::rc::Rc;
+
+use graphics::{Context,Graphics};
+use piston_window::*;
+
+use values::*;
+use board::Board;
+use board_renderer::*;
+use human_player::HumanPlayer;
+use match_controller::*;
+use menu::MenuCommand;
+use font::Font;
+use layout::Layout;
+use mode::*;
+use profiles::PlayerProfiles;
+use wrapper_types::*;
+
+/// Distance of on-canvas text, like win counters and scores, from the edges
+/// of the canvas.
+pub const TEXT_MARGIN: f64 = 8.0;
+
+/// Local two player head-to-head, played as a best-of-N match.
+pub struct Versus {
+    textures: Rc<GameTextures>,
+    dimensions: Dimension,
+    layout: Layout,
+
+    left_board: Board,
+    right_board: Board,
+    left_renderer: GameBoardRenderer,
+    right_renderer: GameBoardRenderer,
+    left_render_state: RenderState,
+    right_render_state: RenderState,
+
+    controller: MatchController,
+    font: Font,
+}
+
+impl Versus {
+    pub fn new(textures: Rc<GameTextures>,
+               dimensions: Dimension,
+               layout: Layout,
+               best_of: u32) -> Self {
+
+        Versus {
+            left_board: Board::new(dimensions),
+            right_board: Board::new(dimensions),
+            left_renderer: BoardRenderer::new(textures.clone(), layout.left(), dimensions),
+            right_renderer: BoardRenderer::with_preview_on(textures.clone(), layout.right(), dimensions, Side::Right),
+            left_render_state: RenderState::new(),
+            right_render_state: RenderState::new(),
+
+            textures: textures,
+            dimensions: dimensions,
+            layout: layout,
+
+            controller: MatchController::new(best_of),
+            font: Font::new(3.0),
+        }
+    }
+
+    /// Throw away both boards and start the current round over.
+    fn restart_round(&mut self) {
+        self.left_board = Board::new(self.dimensions);
+        self.right_board = Board::new(self.dimensions);
+        self.left_renderer = BoardRenderer::new(self.textures.clone(), self.layout.left(), self.dimensions);
+        self.right_renderer = BoardRenderer::with_preview_on(self.textures.clone(), self.layout.right(), self.dimensions, Side::Right);
+        self.left_render_state = RenderState::new();
+        self.right_render_state = RenderState::new();
+    }
+
+    /// Rate the match, if both players picked a profile.
+    fn record_result(&self, left: Option<&str>, right: Option<&str>) {
+        let (left, right) = match (left, right) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return,
+        };
+
+        let left_wins = self.controller.wins(Side::Left);
+        let right_wins = self.controller.wins(Side::Right);
+        let result = if left_wins > right_wins {
+            PlayerProfiles::load().record(left, right, left_wins, right_wins)
+        } else {
+            PlayerProfiles::load().record(right, left, right_wins, left_wins)
+        };
+
+        if let Err(e) = result {
+            println!("Could not record the match: {}", e);
+        }
+    }
+
+    fn render_scores(&self, e: &GameWindow) {
+        let font = &self.font;
+        let controller = &self.controller;
+        let layout = &self.layout;
+
+        e.draw_2d(|c, g| {
+            render_match(font, controller, layout, &layout.apply(&c), g);
+        });
+    }
+}
+
+/// Draw the wins above each board, and the result banner between rounds,
+/// through a context the layout has been applied to.
+pub fn render_match<G: Graphics>(font: &Font,
+                                 controller: &MatchController,
+                                 layout: &Layout,
+                                 c: &Context,
+                                 g: &mut G) {
+    use graphics::*;
+
+    let white = [1.0, 1.0, 1.0, 1.0];
+
+    for &(side, position) in [(Side::Left, layout.left()), (Side::Right, layout.right())].iter() {
+        let text = format!("Wins {}/{}", controller.wins(side), controller.wins_needed());
+        let c = c.trans(position.x(), layout.top() + TEXT_MARGIN);
+        font.draw(&text, white, c.transform, g);
+    }
+
+    let banner = match controller.state() {
+        MatchState::RoundOver(Some(side), _) => {
+            Some(format!("{} wins round {}!", side_name(side), controller.round()))
+        },
+        MatchState::RoundOver(None, _) => {
+            Some(format!("Round {} is a draw!", controller.round()))
+        },
+        MatchState::MatchOver(side, _) => {
+            Some(format!("{} wins the match!", side_name(side)))
+        },
+        _ => None,
+    };
+
+    if let Some(text) = banner {
+        render_banner(font, &text, [1.0, 0.8, 0.1, 1.0], c, g);
+    }
+}
+
+/// Draw text on a dark bar across the middle of the window.
+pub fn render_banner<G: Graphics>(font: &Font, text: &str, color: [f32; 4], c: &Context, g: &mut G) {
+    use graphics::*;
+
+    let size = c.get_view_size();
+    let (w, h) = (size[0], size[1]);
+    let bar = font.height() * 3.0;
+
+    rectangle([0.0, 0.0, 0.0, 0.8], [0.0, (h - bar) / 2.0, w, bar], c.transform, g);
+
+    let c = c.trans((w - font.width(text)) / 2.0, (h - font.height()) / 2.0);
+    font.draw(text, color, c.transform, g);
+}
+
+/// Draw lines of text, like an end of game breakdown, on a dark bar across
+/// the middle of the window.
+pub fn render_breakdown<G: Graphics>(font: &Font, lines: &[String], c: &Context, g: &mut G) {
+    use graphics::*;
+
+    let size = c.get_view_size();
+    let (w, h) = (size[0], size[1]);
+    let line = font.height() * 2.0;
+    let bar = line * (lines.len() + 1) as f64;
+
+    rectangle([0.0, 0.0, 0.0, 0.8], [0.0, (h - bar) / 2.0, w, bar], c.transform, g);
+
+    for (i, text) in lines.iter().enumerate() {
+        let y = (h - bar) / 2.0 + line * (i as f64 + 0.75);
+        let c = c.trans((w - font.width(text)) / 2.0, y);
+        font.draw(text, [1.0, 0.8, 0.1, 1.0], c.transform, g);
+    }
+}
+
+impl Mode for Versus {
+    fn event(&mut self,
+             e: &GameWindow,
+             left_player: &mut HumanPlayer,
+             right_player: &mut HumanPlayer,
+             paused: bool) -> Option<ModeEvent> {
+
+        let mut result = None;
+
+        e.resize(|w, h| self.layout.resize(w, h));
+
+        if !paused {
+            if self.controller.is_playing() {
+                let left = left_player.update(e, &mut self.left_board);
+                let right = right_player.update(e, &mut self.right_board);
+
+                if left.or(right) == Some(MenuCommand::Pause) {
+                    result = Some(ModeEvent::Pause);
+                }
+            }
+
+            let match_event = {
+                let left_board = &mut self.left_board;
+                let right_board = &mut self.right_board;
+                let left_render_state = &self.left_render_state;
+                let right_render_state = &self.right_render_state;
+                let controller = &mut self.controller;
+                let mut match_event = None;
+
+                e.update(|args| {
+                    if controller.is_playing() {
+                        left_board.update(args.dt, left_render_state);
+                        right_board.update(args.dt, right_render_state);
+
+                        right_board.attack(left_board.take_attack());
+                        left_board.attack(right_board.take_attack());
+
+                        controller.report(left_board.is_game_over(), right_board.is_game_over());
+                    } else {
+                        match_event = controller.update(args.dt);
+                    }
+                });
+
+                match_event
+            };
+
+            match match_event {
+                Some(MatchEvent::NextRound) => self.restart_round(),
+                Some(MatchEvent::Finished) => {
+                    self.record_result(left_player.profile(), right_player.profile());
+                    result = Some(ModeEvent::Finished);
+                },
+                None => {}
+            }
+        }
+
+        self.left_renderer.set_paused(paused);
+        self.right_renderer.set_paused(paused);
+        self.left_renderer.set_layout(self.layout.left(), self.layout.scale());
+        self.right_renderer.set_layout(self.layout.right(), self.layout.scale());
+        self.left_renderer.set_squash(left_player.controls().settings().squash);
+        self.right_renderer.set_squash(right_player.controls().settings().squash);
+
+        self.right_renderer.render_into(e, &mut self.right_board, &mut self.right_render_state);
+        self.left_renderer.render_into(e, &mut self.left_board, &mut self.left_render_state);
+        BoardRenderer::exchange_attacks(e, &mut self.left_renderer, &mut self.right_renderer);
+
+        self.render_scores(e);
+
+        result
+    }
+
+    fn restart(&mut self) {
+        self.restart_round();
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Left  => "Left",
+        Side::Right => "Right",
+    }
+}
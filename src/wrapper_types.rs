@@ -1,6 +1,11 @@
-
+extern crate gfx_device_gl;
 
 use sdl2_window::Sdl2Window;
-use piston_window::PistonWindow;
+use piston_window::{PistonWindow,Texture};
+
+use board_renderer::BoardRenderer;
+use textures::Textures;
 
 pub type GameWindow = PistonWindow<(), Sdl2Window>;
+pub type GameTextures = Textures<gfx_device_gl::Resources>;
+pub type GameBoardRenderer = BoardRenderer<Texture<gfx_device_gl::Resources>, gfx_device_gl::Resources>;
@@ -0,0 +1,42 @@
+use puzzlefighter::*;
+use each_frame_until_settled;
+
+#[test]
+fn grid_reports_aged_blocks() {
+    let mut grid = BlockGrid::new(Dimension::new(3, 1));
+    grid.set(PositionedBlock::new(Block::new_with_age(Color::Red, 2), GridPosition::new(0, 0)));
+    grid.set(PositionedBlock::new(Block::new(Color::Red, false), GridPosition::new(1, 0)));
+    grid.set(PositionedBlock::new(Block::new_with_age(Color::Blue, 1), GridPosition::new(2, 0)));
+
+    let aged: Vec<(i8, u8)> = grid.age().iter().map(|block| (block.x(), block.age())).collect();
+    assert_eq!(vec!((0, 1), (2, 0)), aged);
+
+    let aged: Vec<(i8, u8)> = grid.age().iter().map(|block| (block.x(), block.age())).collect();
+    assert_eq!(vec!((0, 0)), aged);
+    assert!(grid.age().is_empty());
+}
+
+#[test]
+fn counters_tick_down_each_turn() {
+    // Drop patterns are as wide as a real board.
+    let mut board = Board::new(Dimension::new(6, 13));
+    let piece = Piece::new(Block::new(Color::Blue, false), Block::new(Color::Blue, false));
+    board.set_piece_sequence(vec!(piece, piece, piece));
+    board.turbo(true);
+    board.attack(6);
+
+    // One turn for the counters to drop and one for them to age.
+    let mut aged = Vec::new();
+    for _ in 0..2 {
+        each_frame_until_settled(&mut board, |board, events| {
+            for event in events {
+                if let BlockEvent::Age(block) = event {
+                    assert_eq!(Some(block), board.grid().at(block.position()));
+                    aged.push(block.age());
+                }
+            }
+        });
+    }
+
+    assert_eq!(vec!(2; 6), aged);
+}
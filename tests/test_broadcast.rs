@@ -0,0 +1,135 @@
+use puzzlefighter::*;
+use puzzlefighter::broadcast::*;
+use puzzlefighter::controls::Action;
+use puzzlefighter::input::Command;
+use puzzlefighter::lockstep::*;
+use puzzlefighter::match_controller::*;
+use puzzlefighter::settings::PlayerSettings;
+
+fn simulation() -> Simulation {
+    let fast = PlayerSettings { soft_drop: 30.0, .. PlayerSettings::default() };
+    Simulation::new(Dimension::new(6, 13), 99, 3, fast, PlayerSettings::default())
+}
+
+// Left holds turbo and loses the first round while right does nothing.
+fn script(tick: u64) -> (Vec<Command>, Vec<Command>) {
+    match tick % 40 {
+        0 => (vec!(Command::Press(Action::Turbo)), vec!()),
+        20 => (vec!(Command::Press(Action::Clockwise)), vec!(Command::Press(Action::Left))),
+        _ => (vec!(), vec!()),
+    }
+}
+
+// Play the match for a number of ticks, recording it as the host would.
+fn play(simulation: &mut Simulation, broadcast: &mut Broadcast, ticks: u64) {
+    for _ in 0..ticks {
+        let tick = simulation.tick();
+        let (left, right) = script(tick);
+        let event = simulation.step(&left, &right);
+
+        broadcast.record(Message::Play(tick, left, right));
+        if event == Some(MatchEvent::NextRound) {
+            broadcast.record(simulation.round_start());
+        }
+    }
+}
+
+// Watch until everything sent has been shown.
+fn watch(spectator: &mut Spectator) {
+    for _ in 0..10000 {
+        spectator.update(TICK);
+    }
+}
+
+#[test]
+fn spectators_see_the_same_game() {
+    let mut host = simulation();
+    let mut broadcast = Broadcast::new();
+    let mut spectator = Spectator::new(Dimension::new(6, 13));
+
+    broadcast.start(&host);
+    for message in broadcast.greeting() {
+        spectator.receive(message).unwrap();
+    }
+    assert_eq!(0, spectator.simulation().unwrap().tick());
+
+    play(&mut host, &mut broadcast, 200);
+    for tick in 0..200 {
+        let (left, right) = script(tick);
+        spectator.receive(Message::Play(tick, left, right)).unwrap();
+    }
+
+    // Play is held back until enough has arrived.
+    spectator.update(TICK * 10.0);
+    assert!(spectator.simulation().unwrap().tick() < 200 - SPECTATOR_DELAY + 11);
+
+    watch(&mut spectator);
+    assert!(spectator.is_buffering());
+    assert_eq!(200, spectator.simulation().unwrap().tick());
+    assert_eq!(host.checksum(), spectator.simulation().unwrap().checksum());
+}
+
+#[test]
+fn late_joiners_start_from_the_round() {
+    let mut host = simulation();
+    let mut broadcast = Broadcast::new();
+    broadcast.start(&host);
+
+    // Into the second round.
+    let mut ticks = 0;
+    while host.controller().round() < 2 || host.tick() % 100 != 0 {
+        play(&mut host, &mut broadcast, 1);
+        ticks += 1;
+        assert!(ticks < 100000, "The first round never ended");
+    }
+
+    let greeting = broadcast.greeting();
+    match greeting[1] {
+        Message::Round { round: 2, left_wins: 0, right_wins: 1, tick } => assert!(tick > 0),
+        ref message => panic!("Expected the second round, got {:?}", message),
+    }
+
+    let mut spectator = Spectator::new(Dimension::new(6, 13));
+    for message in greeting {
+        spectator.receive(message).unwrap();
+    }
+    watch(&mut spectator);
+
+    assert_eq!(host.tick(), spectator.simulation().unwrap().tick());
+    assert_eq!(host.checksum(), spectator.simulation().unwrap().checksum());
+}
+
+#[test]
+fn far_behind_spectators_skip_ahead() {
+    let host = simulation();
+    let mut broadcast = Broadcast::new();
+    broadcast.start(&host);
+    for tick in 0..SPECTATOR_DELAY * 5 {
+        broadcast.record(Message::Play(tick, vec!(), vec!()));
+    }
+
+    let mut spectator = Spectator::new(Dimension::new(6, 13));
+    for message in broadcast.greeting() {
+        spectator.receive(message).unwrap();
+    }
+    spectator.update(0.0);
+
+    assert!(spectator.take_skipped());
+    assert_eq!(SPECTATOR_DELAY * 4, spectator.simulation().unwrap().tick());
+    assert!(!spectator.take_skipped());
+}
+
+#[test]
+fn missing_ticks_are_an_error() {
+    let host = simulation();
+    let mut spectator = Spectator::new(Dimension::new(6, 13));
+
+    assert!(spectator.receive(Message::Play(0, vec!(), vec!())).is_err());
+
+    spectator.receive(host.setup()).unwrap();
+    spectator.receive(host.round_start()).unwrap();
+    spectator.receive(Message::Play(0, vec!(), vec!())).unwrap();
+
+    assert_eq!(Err("Expected tick 1, got 2".to_string()), spectator.receive(Message::Play(2, vec!(), vec!())));
+    assert_eq!(Err("The host left".to_string()), spectator.receive(Message::Bye));
+}
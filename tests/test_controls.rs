@@ -0,0 +1,75 @@
+use puzzlefighter::controls::*;
+use puzzlefighter::match_controller::Side;
+
+#[test]
+fn defaults_round_trip_through_config() {
+    let controls = Controls::default();
+
+    assert!(controls.validate().is_ok());
+    assert_eq!(Ok(controls.clone()), Controls::parse(&controls.to_config()));
+}
+
+#[test]
+fn parse_bindings() {
+    assert_eq!(Ok(Binding::Key('w' as u32)), Binding::parse("key W"));
+    assert_eq!(Ok(Binding::Key(0x20)), Binding::parse("key space"));
+    assert_eq!(Ok(Binding::Key(0x4000_0052)), Binding::parse("key Up"));
+    assert_eq!(Ok(Binding::Key(1234)), Binding::parse("key #1234"));
+    assert_eq!(Ok(Binding::Button(1, 3)), Binding::parse("button 1 3"));
+    assert_eq!(Ok(Binding::Axis(0, 1, true)), Binding::parse("axis 0 1+"));
+    assert_eq!(Ok(Binding::Axis(2, 0, false)), Binding::parse(" axis 2 0- "));
+
+    assert!(Binding::parse("key Nope").is_err());
+    assert!(Binding::parse("button 1").is_err());
+    assert!(Binding::parse("axis 0 1").is_err());
+    assert!(Binding::parse("mouse 1").is_err());
+
+    assert_eq!("key Up", Binding::Key(0x4000_0052).to_config());
+    assert_eq!("axis 0 1-", Binding::Axis(0, 1, false).to_config());
+}
+
+#[test]
+fn config_errors() {
+    let config = Controls::default().to_config();
+
+    // Conflicting bindings
+    let conflict = config.replace("clockwise = key S", "clockwise = key W");
+    assert!(Controls::parse(&conflict).unwrap_err().contains("key W"));
+
+    // Unbound action
+    let unbound = config.replace("\npause = key Escape, button 0 9\n", "\n");
+    assert!(Controls::parse(&unbound).unwrap_err().contains("pause"));
+
+    assert!(Controls::parse("turbo = key C").is_err());
+    assert!(Controls::parse("[middle]").is_err());
+    assert!(Controls::parse("[left]\njump = key J").is_err());
+}
+
+#[test]
+fn rebinding_takes_the_binding_from_elsewhere() {
+    let mut controls = Controls::default();
+    let space = Binding::Key(0x20);
+
+    controls.rebind(Side::Left, Action::Turbo, space);
+
+    assert_eq!(Some(Action::Turbo), controls.player(Side::Left).action_for(space));
+    assert_eq!(None, controls.player(Side::Right).action_for(space));
+
+    // The old key is replaced, the gamepad binding kept.
+    let turbo = controls.player(Side::Left).bindings_for(Action::Turbo);
+    assert_eq!(vec!(Binding::Axis(0, 1, true), space), turbo);
+}
+
+#[test]
+fn rebinding_can_leave_an_action_unbound() {
+    let mut controls = Controls::default();
+
+    // Taking the right player's drop key and then its stick leaves nothing
+    // to drop with.
+    controls.rebind(Side::Left, Action::Turbo, Binding::Key(0x20));
+    assert_eq!(Ok(()), controls.validate());
+    controls.rebind(Side::Left, Action::Turbo, Binding::Axis(1, 1, true));
+
+    assert!(controls.player(Side::Right).bindings_for(Action::Turbo).is_empty());
+    assert_eq!(Err("Nothing is bound to right turbo".to_string()), controls.validate());
+}
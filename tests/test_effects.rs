@@ -0,0 +1,98 @@
+use puzzlefighter::*;
+use puzzlefighter::effects::*;
+use {make_board,run_until_settled};
+
+fn block_at(x: i8, y: i8) -> PositionedBlock {
+    PositionedBlock::new(Block::new(Color::Red, false), GridPosition::new(x, y))
+}
+
+#[test]
+fn explosions_report_gem_size() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      ",
+        "      ",
+        "   RR ",
+        "   RR "
+    );
+    board.set_piece_sequence(vec!(Piece::new(Block::new(Color::Red, true), Block::new(Color::Yellow, false))));
+    board.turbo(true);
+
+    let mut gems: Vec<u32> = run_until_settled(&mut board).into_iter().filter_map(|event| match event {
+        BlockEvent::Explode(_, _, gem) => Some(gem),
+        _ => None,
+    }).collect();
+
+    gems.sort();
+    assert_eq!(vec!(1, 4, 4, 4, 4), gems);
+}
+
+#[test]
+fn gems_shatter_and_blocks_fade() {
+    let mut effects = Effects::new(1);
+
+    let b = effects.explode(block_at(2, 0), 0, 1);
+    assert!(effects.fragments().is_empty());
+    assert!(b.time >= 0.4 && b.time <= 0.7 && b.scale >= 1.3 && b.scale <= 1.7);
+
+    let b = effects.explode(block_at(2, 3), 2, SHATTER_GEM);
+    assert_eq!(0.1, b.delay);
+    assert_eq!((FRAGMENTS_PER_SIDE * FRAGMENTS_PER_SIDE) as usize, effects.fragments().len());
+    assert!(effects.fragments().iter().all(|f| !f.is_visible()));
+
+    // Fragments wait for their block to break, then fly and fall away.
+    effects.update(0.05, 0);
+    assert!(effects.fragments().iter().all(|f| !f.is_visible() && f.y > 2.0));
+    for _ in 0..10 {
+        effects.update(0.05, 0);
+    }
+    assert!(effects.fragments().iter().all(|f| f.is_visible()));
+    assert!(effects.fragments().iter().all(|f| f.vy < 0.0));
+
+    effects.update(FRAGMENT_TIME, 0);
+    assert!(effects.fragments().is_empty());
+}
+
+#[test]
+fn shakes_and_flashes_follow_combos() {
+    let mut effects = Effects::new(1);
+    effects.update(0.01, 1);
+    assert_eq!((0.0, 0.0), effects.offset());
+    assert_eq!(0.0, effects.flash());
+
+    effects.hit(5);
+    effects.update(0.01, 2);
+    let (x, y) = effects.offset();
+    assert!(x.abs() <= 5.0 * SHAKE_PER_DAMAGE && y.abs() <= 5.0 * SHAKE_PER_DAMAGE);
+    assert!(x != 0.0 || y != 0.0);
+    assert!(effects.flash() > 0.0);
+
+    effects.hit(1000);
+    effects.update(0.0, 2);
+    let (x, y) = effects.offset();
+    assert!(x.abs() <= MAX_SHAKE && y.abs() <= MAX_SHAKE);
+
+    effects.update(SHAKE_TIME.max(FLASH_TIME), 2);
+    assert_eq!((0.0, 0.0), effects.offset());
+    assert_eq!(0.0, effects.flash());
+}
+
+#[test]
+fn effects_are_seeded() {
+    let blocks: Vec<PositionedBlock> = (0..4).map(|x| block_at(x, 0)).collect();
+    let play = |seed: u64| {
+        let mut effects = Effects::new(seed);
+        let mut offsets = Vec::new();
+        for (i, block) in blocks.iter().enumerate() {
+            effects.explode(*block, i as u32, 6);
+            effects.hit(10);
+            effects.update(0.05, 0);
+            offsets.push(effects.offset());
+        }
+        (effects.fragments().to_vec(), offsets)
+    };
+
+    assert_eq!(play(3), play(3));
+    assert!(play(3) != play(4));
+}
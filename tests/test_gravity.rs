@@ -0,0 +1,37 @@
+use puzzlefighter::gravity::*;
+use puzzlefighter::settings::*;
+
+#[test]
+fn falls_accelerate() {
+    assert_eq!(0.0, fall_time(0.0));
+
+    // Four times the height takes twice as long.
+    assert!((fall_time(12.0) - fall_time(3.0) * 2.0).abs() < 1e-9);
+
+    // Distance covered is half the acceleration times the time squared.
+    let t = fall_time(13.0);
+    assert!((GRAVITY * t * t / 2.0 - 13.0).abs() < 1e-9);
+    assert!(t < 1.0, "A board's height takes {} seconds", t);
+}
+
+#[test]
+fn landings_squash_with_speed() {
+    assert_eq!(0.0, squash(0.0, SQUASH));
+    assert_eq!(0.0, squash(13.0, 0.0));
+    assert!(squash(1.0, SQUASH) < squash(2.0, SQUASH));
+    assert_eq!(SQUASH, squash(FULL_SQUASH_HEIGHT, SQUASH));
+    assert_eq!(SQUASH, squash(13.0, SQUASH));
+}
+
+#[test]
+fn squash_setting_is_validated() {
+    let mut settings = PlayerSettings::default();
+    assert_eq!(SQUASH, settings.squash);
+
+    assert!(settings.set("squash", "0").is_ok());
+    assert_eq!(Some(0.0), settings.get("squash"));
+
+    assert!(settings.set("squash", "-0.1").is_err());
+    assert!(settings.set("squash", "0.6").is_err());
+    assert_eq!(0.0, settings.squash);
+}
@@ -0,0 +1,73 @@
+use puzzlefighter::*;
+use puzzlefighter::font::*;
+use puzzlefighter::hud::*;
+use {chain_board,each_frame_until_settled};
+
+// Run a board through a combo, feeding its explosions to a HUD as the
+// renderer would, and collect every chain shown along the way.
+fn play(board: &mut Board, hud: &mut Hud) -> Vec<u32> {
+    let mut chains = Vec::new();
+
+    each_frame_until_settled(board, |board, events| {
+        for event in events {
+            if let BlockEvent::Explode(block, _, _) = event {
+                hud.explode(block);
+            }
+        }
+        hud.update(0.01, board.stats(), board.chain(), board.pending_attack());
+
+        if let Some((chain, _)) = hud.chain() {
+            if chains.last() != Some(&chain) {
+                chains.push(chain);
+            }
+        }
+    });
+
+    chains
+}
+
+#[test]
+fn chains_and_damage_are_shown() {
+    let mut board = chain_board();
+    let mut hud = Hud::new();
+    let chains = play(&mut board, &mut hud);
+
+    assert_eq!(vec!(2), chains);
+    assert_eq!(board.stats().score, hud.score());
+    assert_eq!(Some(board.stats().last_combo), hud.sending());
+
+    let damage: u32 = hud.popups().iter().map(|popup| popup.damage).sum();
+    assert_eq!(board.stats().last_combo, damage);
+    for popup in hud.popups() {
+        assert!(popup.x >= 2.0 && popup.x <= 3.0 && popup.y >= 0.0 && popup.y <= 2.0,
+                "Popup away from the blocks: {:?}", popup);
+    }
+}
+
+#[test]
+fn hud_fades() {
+    let mut board = chain_board();
+    let mut hud = Hud::new();
+    play(&mut board, &mut hud);
+
+    hud.update(CHAIN_TIME, board.stats(), 0, 0);
+    assert_eq!(None, hud.chain());
+    assert_eq!(None, hud.sending());
+    assert!(hud.popups().is_empty());
+    assert_eq!(board.stats().score, hud.score());
+}
+
+#[test]
+fn font_asset_has_every_glyph() {
+    let font = Font::new(1.0);
+
+    for c in "ABCDEFGHIJKLMNOPQRSTUVWXYZabc0123456789!?.,:-+/<>()=%'_".chars() {
+        assert!(font.supports(c), "No glyph for `{}`", c);
+    }
+    assert!(!font.supports('~'));
+
+    assert!(parse_glyphs("A\n.#.\n").is_err());
+    assert!(parse_glyphs("AB\n").is_err());
+    let glyphs = parse_glyphs("; comment\n-\n.....\n.....\n.....\n#####\n.....\n.....\n.....\n").unwrap();
+    assert_eq!(vec!(true; GLYPH_W), glyphs[&'-'][3]);
+}
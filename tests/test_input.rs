@@ -0,0 +1,258 @@
+use puzzlefighter::*;
+use puzzlefighter::board_renderer::RenderState;
+use puzzlefighter::controls::*;
+use puzzlefighter::match_controller::Side;
+use puzzlefighter::input::*;
+use puzzlefighter::settings::*;
+
+// A frame length that adds up exactly in floating point.
+const FRAME: f64 = 1.0 / 64.0;
+
+// Hold a button for a number of frames, returning the frames on which it
+// repeated.
+fn repeat_frames(settings: &PlayerSettings, frames: u32) -> Vec<u32> {
+    let mut repeater = Repeater::new(settings);
+    let mut result = Vec::new();
+
+    repeater.press('x');
+    for frame in 1..frames + 1 {
+        for _ in repeater.update(FRAME) {
+            result.push(frame);
+        }
+    }
+
+    result
+}
+
+#[test]
+fn default_timings() {
+    let frames = repeat_frames(&PlayerSettings::default(), 64);
+
+    // 0.2s then every 0.05s, rounded up to whole frames.
+    assert_eq!(13, frames[0]);
+    assert_eq!(17, frames[1]);
+    assert_eq!(13, frames.len());
+}
+
+#[test]
+fn faster_timings() {
+    let settings = PlayerSettings {
+        das: 0.1,
+        arr: 0.02,
+        .. PlayerSettings::default()
+    };
+    let frames = repeat_frames(&settings, 64);
+
+    assert_eq!(7, frames[0]);
+    assert_eq!(9, frames[1]);
+    assert_eq!(29, frames.len());
+}
+
+#[test]
+fn zero_das_repeats_on_the_next_frame() {
+    let settings = PlayerSettings {
+        das: 0.0,
+        .. PlayerSettings::default()
+    };
+
+    assert_eq!(1, repeat_frames(&settings, 1)[0]);
+}
+
+#[test]
+fn release_stops_repeats() {
+    let mut repeater = Repeater::new(&PlayerSettings::default());
+
+    repeater.press('a');
+    repeater.press('b');
+    for _ in 0..13 {
+        repeater.update(FRAME);
+    }
+
+    repeater.release('a');
+    assert!(!repeater.is_held('a'));
+
+    let mut repeats = Vec::new();
+    for _ in 0..20 {
+        repeats.extend(repeater.update(FRAME));
+    }
+    assert!(!repeats.is_empty());
+    assert!(repeats.iter().all(|&x| x == 'b'));
+
+    // Pressing again starts the delay over.
+    repeater.press('b');
+    assert!(repeater.update(FRAME).is_empty());
+}
+
+#[test]
+fn settings_are_validated() {
+    let mut settings = PlayerSettings::default();
+
+    assert!(settings.set("das", "0.1").is_ok());
+    assert_eq!(0.1, settings.das);
+
+    assert!(settings.set("arr", "0").is_err());
+    assert!(settings.set("soft_drop", "0.5").is_err());
+    assert!(settings.set("dead_zone", "1.5").is_err());
+    assert!(settings.set("das", "soon").is_err());
+    assert!(settings.set("speed", "1").is_err());
+    assert_eq!(0.05, settings.arr);
+}
+
+#[test]
+fn settings_are_read_per_player() {
+    // The right player's section is last.
+    let config = Controls::default().to_config() + "das = 0.15\nsoft_drop = 32\n";
+    let controls = Controls::parse(&config).unwrap();
+
+    assert_eq!(PlayerSettings::default(), controls.player(Side::Left).settings());
+    assert_eq!(0.15, controls.player(Side::Right).settings().das);
+    assert_eq!(32.0, controls.player(Side::Right).settings().soft_drop);
+
+    assert_eq!(Ok(controls.clone()), Controls::parse(&controls.to_config()));
+}
+
+#[test]
+fn soft_drop_sets_turbo_speed() {
+    let render_state = RenderState::new();
+    let mut board = Board::new(Dimension::new(6, 13));
+
+    board.update(FRAME, &render_state);
+    let start = board.current_piece().unwrap().position.y();
+
+    // Half the starting speed of 0.8s per step.
+    board.set_soft_drop(2.0);
+    board.turbo(true);
+    for _ in 0..32 {
+        board.update(FRAME, &render_state);
+    }
+    assert_eq!(start - 1, board.current_piece().unwrap().position.y());
+}
+
+fn key(name: &str) -> Binding {
+    Binding::parse(&format!("key {}", name)).unwrap()
+}
+
+fn spawned_board() -> Board {
+    let mut board = Board::new(Dimension::new(6, 13));
+    board.update(FRAME, &RenderState::new());
+    board
+}
+
+fn piece_x(board: &Board) -> i8 {
+    board.current_piece().unwrap().position.x()
+}
+
+// Feed events to the left player's input, applying the commands to a board
+// and returning them.
+fn play(input: &mut PlayerInput, board: &mut Board, events: Vec<(f64, InputEvent)>) -> Vec<Command> {
+    let mut commands = Vec::new();
+
+    for (time, event) in events {
+        for command in input.handle(TimedEvent::new(time, event)) {
+            input.apply(command, board);
+            commands.push(command);
+        }
+    }
+
+    commands
+}
+
+#[test]
+fn tapping_moves_once() {
+    let controls = Controls::default();
+    let mut input = PlayerInput::new(controls.player(Side::Left));
+    let mut board = spawned_board();
+
+    let commands = play(&mut input, &mut board, vec!(
+        (0.0, InputEvent::Press(key("A"))),
+        (0.1, InputEvent::Release(key("A"))),
+    ));
+
+    assert_eq!(vec!(Command::Press(Action::Left), Command::Release(Action::Left)), commands);
+    assert_eq!(2, piece_x(&board));
+}
+
+#[test]
+fn holding_repeats_moves() {
+    let controls = Controls::default();
+    let mut input = PlayerInput::new(controls.player(Side::Left));
+    let mut board = spawned_board();
+
+    play(&mut input, &mut board, vec!((0.0, InputEvent::Press(key("D")))));
+
+    // Nothing repeats until the delay is up, 13 frames in.
+    let mut time = 0.0;
+    for _ in 0..12 {
+        time += FRAME;
+        for command in input.advance(time) {
+            input.apply(command, &mut board);
+        }
+    }
+    assert_eq!(4, piece_x(&board));
+
+    // Then the piece slides to the wall.
+    while time < 1.0 {
+        time += FRAME;
+        for command in input.advance(time) {
+            input.apply(command, &mut board);
+        }
+    }
+    assert_eq!(5, piece_x(&board));
+}
+
+#[test]
+fn unbound_input_is_ignored() {
+    let controls = Controls::default();
+    let mut input = PlayerInput::new(controls.player(Side::Left));
+    let mut board = spawned_board();
+
+    let commands = play(&mut input, &mut board, vec!(
+        (0.0, InputEvent::Press(key("Left"))),
+        (1.0, InputEvent::Press(key("Q"))),
+    ));
+
+    assert!(commands.is_empty());
+    assert_eq!(3, piece_x(&board));
+}
+
+#[test]
+fn stick_presses_past_the_dead_zone() {
+    let controls = Controls::default();
+    let mut input = PlayerInput::new(controls.player(Side::Left));
+    let mut board = spawned_board();
+
+    let commands = play(&mut input, &mut board, vec!(
+        (0.0, InputEvent::Axis(0, 0, -0.5)),
+        (0.01, InputEvent::Axis(0, 0, -0.9)),
+        (0.02, InputEvent::Axis(0, 0, -1.0)),
+        (0.03, InputEvent::Axis(0, 0, 0.9)),
+        (0.04, InputEvent::Axis(0, 0, 0.1)),
+    ));
+
+    assert_eq!(vec!(
+        Command::Press(Action::Left),
+        Command::Release(Action::Left),
+        Command::Press(Action::Right),
+        Command::Release(Action::Right),
+    ), commands);
+    assert_eq!(3, piece_x(&board));
+}
+
+#[test]
+fn turbo_and_pause() {
+    let controls = Controls::default();
+    let mut input = PlayerInput::new(controls.player(Side::Left));
+    let mut board = spawned_board();
+
+    play(&mut input, &mut board, vec!(
+        (0.0, InputEvent::Press(key("C"))),
+        (0.1, InputEvent::Press(key("D"))),
+    ));
+
+    let commands = input.handle(TimedEvent::new(0.2, InputEvent::Press(key("Escape"))));
+    assert_eq!(vec!(Command::Press(Action::Pause)), commands);
+    assert!(input.apply(commands[0], &mut board));
+
+    // Pausing forgets held buttons, so nothing repeats.
+    assert!(input.advance(5.0).is_empty());
+}
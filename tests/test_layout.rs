@@ -0,0 +1,82 @@
+use puzzlefighter::*;
+use puzzlefighter::layout::*;
+
+fn layout() -> Layout {
+    // The 6x13 board, its 32 pixel blocks and the frame in board.png.
+    Layout::new(Dimension::new(6, 13), Dimension::new(32, 32), Dimension::new(250, 472))
+}
+
+// Everything a board has left and right of its grid fits on the canvas,
+// without running into the other board.
+fn assert_fits(layout: &Layout) {
+    let origin = layout.origin();
+    let canvas = layout.canvas_size();
+    let (grid_w, frame, panel) = (6.0 * 32.0, layout.frame(), panel_width(32.0));
+
+    let left_edge = layout.left().x() - frame - PANEL_GAP - panel;
+    let left_end = layout.left().x() + grid_w + frame;
+    let right_edge = layout.right().x() - frame;
+    let right_end = layout.right().x() + grid_w + frame + PANEL_GAP + panel;
+
+    assert!(left_edge >= origin.x(), "Left board off the canvas: {:?}", layout);
+    assert!(left_end < right_edge, "Boards overlap: {:?}", layout);
+    assert!(right_end <= origin.x() + canvas[0], "Right board off the canvas: {:?}", layout);
+
+    let center_edge = layout.center().x() - frame - PANEL_GAP - panel;
+    assert!(center_edge >= origin.x() && layout.center().x() + grid_w + frame <= origin.x() + canvas[0]);
+
+    let bottom = layout.left().y() + 13.0 * 32.0 + frame;
+    assert!(layout.left().y() - frame >= origin.y() && bottom <= origin.y() + canvas[1]);
+}
+
+#[test]
+fn canvas_fits_two_boards() {
+    let layout = layout();
+
+    assert_eq!(29.0, layout.frame());
+    assert_eq!(layout.canvas_size(), layout.window_size());
+    assert_eq!(1.0, layout.scale());
+    assert_eq!(PixelPosition::new(0.0, 0.0), layout.origin());
+    assert_eq!(layout.left().y(), layout.right().y());
+    assert_fits(&layout);
+}
+
+#[test]
+fn canvas_scales_to_the_window() {
+    let mut layout = layout();
+    let canvas = layout.canvas_size();
+    let left = layout.left();
+
+    layout.resize(canvas[0] as u32 * 2, canvas[1] as u32 * 2);
+    assert_eq!(2.0, layout.scale());
+    assert_eq!(canvas, layout.view_size());
+    assert_eq!(left, layout.left());
+
+    layout.resize(canvas[0] as u32 / 2, canvas[1] as u32 / 2);
+    assert_eq!(0.5, layout.scale());
+    assert_fits(&layout);
+
+    // Minimized.
+    layout.resize(0, 0);
+    assert_eq!(0.5, layout.scale());
+}
+
+#[test]
+fn canvas_is_centered_in_spare_room() {
+    let mut layout = layout();
+    let canvas = layout.canvas_size();
+
+    // Wide: the height limits the scale, and the boards move to the middle.
+    layout.resize(canvas[0] as u32 * 3, canvas[1] as u32);
+    assert_eq!(1.0, layout.scale());
+    assert_eq!(canvas[0], layout.origin().x());
+    assert_eq!(0.0, layout.top());
+    assert_fits(&layout);
+
+    // Tall, at a different scale.
+    layout.resize(canvas[0] as u32 * 2, canvas[1] as u32 * 4);
+    assert_eq!(2.0, layout.scale());
+    assert_eq!([canvas[0], canvas[1] * 2.0], layout.view_size());
+    assert_eq!(canvas[1] / 2.0, layout.top());
+    assert_fits(&layout);
+}
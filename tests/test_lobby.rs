@@ -0,0 +1,237 @@
+use std::env;
+use std::fs::{self,File};
+use std::io::Read;
+use std::thread;
+use std::time::Duration;
+
+use puzzlefighter::lobby::*;
+use puzzlefighter::net::Connection;
+
+fn hello(name: &str, port: u16) -> LobbyMessage {
+    LobbyMessage::Hello { name: name.to_string(), port: port }
+}
+
+#[test]
+fn messages_round_trip() {
+    let messages = vec!(
+        hello("ann", 7777),
+        LobbyMessage::Waiting(vec!()),
+        LobbyMessage::Waiting(vec!("ann".to_string(), "bob".to_string())),
+        LobbyMessage::Host { id: 3, seed: 18446744073709551615, best_of: 5, opponent: "bob".to_string() },
+        LobbyMessage::Join { id: 3, address: "10.0.0.2:7777".to_string(), opponent: "ann".to_string() },
+        LobbyMessage::Result { id: 3, winner: "ann".to_string() },
+        LobbyMessage::Error("That name is taken".to_string()),
+    );
+
+    for message in messages {
+        assert_eq!(Ok(message.clone()), LobbyMessage::parse(&message.to_line()));
+    }
+
+    assert!(LobbyMessage::parse("hello ann").is_err());
+    assert!(LobbyMessage::parse("hello ann bob 7777").is_err());
+    assert!(LobbyMessage::parse("result x ann").is_err());
+    assert!(LobbyMessage::parse("").is_err());
+}
+
+#[test]
+fn first_two_waiting_are_paired() {
+    let mut lobby = Lobby::new(3, 1);
+
+    assert_eq!(vec!((1, LobbyMessage::Waiting(vec!("ann".to_string())))),
+               lobby.handle(1, "10.0.0.1", hello("ann", 7000)));
+
+    let sent = lobby.handle(2, "10.0.0.2", hello("bob", 7001));
+    match sent[0] {
+        (1, LobbyMessage::Host { id: 1, best_of: 3, ref opponent, .. }) => assert_eq!("bob", opponent),
+        ref other => panic!("Expected ann to host, got {:?}", other),
+    }
+    assert_eq!((2, LobbyMessage::Join { id: 1, address: "10.0.0.1:7000".to_string(), opponent: "ann".to_string() }),
+               sent[1]);
+    assert_eq!(2, sent.len());
+
+    lobby.handle(3, "10.0.0.3", hello("cat", 7002));
+    assert_eq!(vec!("cat".to_string()), lobby.waiting());
+}
+
+#[test]
+fn names_are_unique() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "10.0.0.1", hello("ann", 7000));
+
+    assert_eq!(vec!((2, LobbyMessage::Error("That name is taken".to_string()))),
+               lobby.handle(2, "10.0.0.2", hello("ann", 7000)));
+
+    // Free again once ann leaves.
+    lobby.disconnect(1);
+    assert_eq!(vec!((2, LobbyMessage::Waiting(vec!("ann".to_string())))),
+               lobby.handle(2, "10.0.0.2", hello("ann", 7000)));
+}
+
+#[test]
+fn results_are_recorded_when_agreed() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "10.0.0.1", hello("ann", 7000));
+    lobby.handle(2, "10.0.0.2", hello("bob", 7001));
+    lobby.handle(3, "10.0.0.3", hello("cat", 7002));
+    lobby.handle(4, "10.0.0.4", hello("dan", 7003));
+
+    let result = |id, winner: &str| LobbyMessage::Result { id: id, winner: winner.to_string() };
+
+    assert!(lobby.handle(1, "10.0.0.1", result(1, "bob")).is_empty());
+    assert!(lobby.results().is_empty());
+    lobby.handle(2, "10.0.0.2", result(1, "bob"));
+
+    lobby.handle(3, "10.0.0.3", result(2, "cat"));
+    lobby.handle(4, "10.0.0.4", result(2, "dan"));
+
+    // Only players in a match can report it, and only for one of them.
+    assert!(!lobby.handle(3, "10.0.0.3", result(1, "ann")).is_empty());
+    assert!(!lobby.handle(1, "10.0.0.1", result(9, "ann")).is_empty());
+
+    assert_eq!(vec!("1 ann bob bob", "2 cat dan disputed"),
+               lobby.results().iter().map(|result| result.to_line()).collect::<Vec<_>>());
+}
+
+#[test]
+fn leaving_settles_the_match() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "10.0.0.1", hello("ann", 7000));
+    lobby.handle(2, "10.0.0.2", hello("bob", 7001));
+
+    lobby.handle(1, "10.0.0.1", LobbyMessage::Result { id: 1, winner: "ann".to_string() });
+    lobby.disconnect(2);
+
+    assert_eq!(Some("ann".to_string()), lobby.results()[0].winner);
+
+    // And ann can wait for another.
+    assert_eq!(vec!((1, LobbyMessage::Waiting(vec!("ann".to_string())))),
+               lobby.handle(1, "10.0.0.1", hello("ann", 7000)));
+}
+
+#[test]
+fn reporting_then_waiting_again() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "10.0.0.1", hello("ann", 7000));
+    lobby.handle(2, "10.0.0.2", hello("bob", 7001));
+
+    // ann reports and waits again before bob has reported.
+    assert!(lobby.handle(1, "10.0.0.1", LobbyMessage::Result { id: 1, winner: "ann".to_string() }).is_empty());
+    assert_eq!(vec!((1, LobbyMessage::Waiting(vec!("ann".to_string())))),
+               lobby.handle(1, "10.0.0.1", hello("ann", 7000)));
+    assert!(lobby.results().is_empty());
+
+    // bob's report still counts, and then bob is paired with ann again.
+    assert!(lobby.handle(2, "10.0.0.2", LobbyMessage::Result { id: 1, winner: "ann".to_string() }).is_empty());
+    assert_eq!(vec!("1 ann bob ann"),
+               lobby.results().iter().map(|result| result.to_line()).collect::<Vec<_>>());
+
+    match lobby.handle(2, "10.0.0.2", hello("bob", 7001))[0] {
+        (1, LobbyMessage::Host { id: 2, .. }) => (),
+        ref other => panic!("Expected a new match, got {:?}", other),
+    }
+}
+
+#[test]
+fn waiting_again_without_a_winner() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "10.0.0.1", hello("ann", 7000));
+    lobby.handle(2, "10.0.0.2", hello("bob", 7001));
+
+    // Neither reports, as when a match ends without a winner.
+    assert_eq!(vec!((1, LobbyMessage::Waiting(vec!("ann".to_string())))),
+               lobby.handle(1, "10.0.0.1", hello("ann", 7000)));
+    assert_eq!(2, lobby.handle(2, "10.0.0.2", hello("bob", 7001)).len());
+    assert!(lobby.results().is_empty());
+
+    // Waiting twice is still refused.
+    lobby.handle(3, "10.0.0.3", hello("cat", 7002));
+    assert_eq!(vec!((3, LobbyMessage::Error("Already waiting".to_string()))),
+               lobby.handle(3, "10.0.0.3", hello("cat", 7002)));
+}
+
+#[test]
+fn ipv6_hosts_can_be_joined() {
+    let mut lobby = Lobby::new(3, 1);
+    lobby.handle(1, "::1", hello("ann", 7000));
+
+    match lobby.handle(2, "::1", hello("bob", 7001))[1] {
+        (2, LobbyMessage::Join { ref address, .. }) => assert_eq!("[::1]:7000", address),
+        ref other => panic!("Expected bob to join, got {:?}", other),
+    }
+}
+
+// A game talking to the lobby, one line at a time.
+struct Client {
+    connection: Connection,
+    received: Vec<LobbyMessage>,
+}
+
+impl Client {
+    fn connect(server: &Server) -> Self {
+        Client {
+            connection: Connection::connect(&format!("127.0.0.1:{}", server.port())).unwrap(),
+            received: Vec::new(),
+        }
+    }
+
+    fn send(&mut self, message: LobbyMessage) {
+        self.connection.send_line(&message.to_line()).unwrap();
+    }
+
+    // Poll the server until this client has heard something.
+    fn expect(&mut self, server: &mut Server) -> LobbyMessage {
+        for _ in 0..200 {
+            server.poll().unwrap();
+            for line in self.connection.receive_lines().unwrap() {
+                self.received.push(LobbyMessage::parse(&line).unwrap());
+            }
+            if !self.received.is_empty() {
+                return self.received.remove(0);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("Nothing arrived");
+    }
+}
+
+#[test]
+fn scripted_clients_play_a_match() {
+    let path = env::temp_dir().join("puzzlefighter-lobby-results.txt");
+    let _ = fs::remove_file(&path);
+    let mut server = Server::listen(0, 5, Some(path.clone())).unwrap();
+
+    let mut ann = Client::connect(&server);
+    ann.send(hello("ann", 7000));
+    assert_eq!(LobbyMessage::Waiting(vec!("ann".to_string())), ann.expect(&mut server));
+
+    let mut bob = Client::connect(&server);
+    bob.send(hello("bob", 7001));
+
+    let id = match ann.expect(&mut server) {
+        LobbyMessage::Host { id, best_of: 5, .. } => id,
+        other => panic!("Expected to host, got {:?}", other),
+    };
+    assert_eq!(LobbyMessage::Join { id: id, address: "127.0.0.1:7000".to_string(), opponent: "ann".to_string() },
+               bob.expect(&mut server));
+
+    ann.send(LobbyMessage::Result { id: id, winner: "ann".to_string() });
+    bob.send(LobbyMessage::Result { id: id, winner: "ann".to_string() });
+
+    let mut results = Vec::new();
+    for _ in 0..200 {
+        results.extend(server.poll().unwrap());
+        if !results.is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(1, results.len());
+
+    let mut saved = String::new();
+    File::open(&path).unwrap().read_to_string(&mut saved).unwrap();
+    assert_eq!(format!("{} ann bob ann\n", id), saved);
+    fs::remove_file(&path).unwrap();
+
+    bob.send(LobbyMessage::Result { id: id, winner: "bob".to_string() });
+    assert_eq!(LobbyMessage::Error("No such match".to_string()), bob.expect(&mut server));
+}
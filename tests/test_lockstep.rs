@@ -0,0 +1,191 @@
+use std::thread;
+use std::time::Duration;
+
+use puzzlefighter::*;
+use puzzlefighter::controls::Action;
+use puzzlefighter::input::Command;
+use puzzlefighter::lockstep::*;
+use puzzlefighter::match_controller::Side;
+use puzzlefighter::net::*;
+use puzzlefighter::rollback::Rollback;
+use puzzlefighter::settings::PlayerSettings;
+
+const SEED: u64 = 1234;
+
+fn simulation() -> Simulation {
+    Simulation::new(Dimension::new(6, 13), SEED, 3,
+                    PlayerSettings::default(), PlayerSettings::default())
+}
+
+// Without rollback, each side waits for the other's input.
+fn peers() -> (Rollback, Rollback) {
+    (Rollback::new(Side::Left, simulation(), 0), Rollback::new(Side::Right, simulation(), 0))
+}
+
+// Deliver everything one side has sent, as text like the network does.
+fn deliver(from: &mut Rollback, to: &mut Rollback) -> Result<(), String> {
+    for message in from.outgoing() {
+        try!(to.receive(Message::parse(&message.to_line()).unwrap()));
+    }
+    Ok(())
+}
+
+fn step_both(host: &mut Rollback, guest: &mut Rollback) -> Result<(), String> {
+    try!(host.step());
+    try!(guest.step());
+    try!(deliver(host, guest));
+    deliver(guest, host)
+}
+
+#[test]
+fn messages_round_trip() {
+    let messages = vec!(
+        Message::Hello { version: 1, seed: 18446744073709551615, best_of: 5, soft_drop: 16.5 },
+        Message::Input(12, vec!()),
+        Message::Input(13, vec!(Command::Press(Action::Left), Command::Release(Action::Turbo))),
+        Message::Hash(60, 0xdeadbeef),
+        Message::Watch { version: 3 },
+        Message::Setup { version: 3, seed: 99, best_of: 3, left_soft_drop: 16.0, right_soft_drop: 24.5 },
+        Message::Round { round: 2, left_wins: 1, right_wins: 0, tick: 4321 },
+        Message::Play(7, vec!(), vec!()),
+        Message::Play(8, vec!(Command::Press(Action::Clockwise)), vec!(Command::Release(Action::Left), Command::Press(Action::Turbo))),
+        Message::Bye,
+    );
+
+    for message in messages {
+        assert_eq!(Ok(message.clone()), Message::parse(&message.to_line()));
+    }
+
+    assert!(Message::parse("input 3 +jump").is_err());
+    assert!(Message::parse("input x").is_err());
+    assert!(Message::parse("hash 60 xyz").is_err());
+    assert!(Message::parse("hello 1 2 3").is_err());
+    assert!(Message::parse("play 3 +left").is_err());
+    assert!(Message::parse("").is_err());
+}
+
+#[test]
+fn seeded_boards_get_the_same_pieces() {
+    let pieces = |seed| {
+        let mut board = Board::new(Dimension::new(6, 13));
+        board.set_seed(seed);
+
+        (0..20).map(|_| {
+            let piece = board.next_piece().unwrap();
+            board.generate_next_piece();
+            (piece.blocks[0].color, piece.blocks[1].color, piece.blocks[1].breaker())
+        }).collect::<Vec<_>>()
+    };
+
+    assert_eq!(pieces(SEED), pieces(SEED));
+    assert!(pieces(SEED) != pieces(SEED + 1));
+}
+
+#[test]
+fn both_sides_play_the_same_game() {
+    let (mut host, mut guest) = peers();
+
+    for tick in 0..900 {
+        match tick % 90 {
+            0 => host.input(Command::Press(Action::Turbo)),
+            10 => guest.input(Command::Press(Action::Left)),
+            11 => guest.input(Command::Release(Action::Left)),
+            20 => host.input(Command::Press(Action::Clockwise)),
+            30 => guest.input(Command::Press(Action::Turbo)),
+            _ => {}
+        }
+
+        step_both(&mut host, &mut guest).unwrap();
+        assert_eq!(host.simulation().checksum(), guest.simulation().checksum());
+    }
+
+    let left = host.simulation().board(Side::Left).stats();
+    assert!(left.pieces > 5, "Left only placed {} pieces", left.pieces);
+    assert_eq!(left, guest.simulation().board(Side::Left).stats());
+    assert_eq!(host.simulation().board(Side::Right).stats(),
+               guest.simulation().board(Side::Right).stats());
+}
+
+#[test]
+fn waits_for_the_other_side() {
+    let (mut host, mut guest) = peers();
+
+    // Only the first few ticks are known without hearing from the guest.
+    for _ in 0..INPUT_DELAY {
+        assert!(host.can_step());
+        host.step().unwrap();
+    }
+    assert!(!host.can_step());
+    assert_eq!(INPUT_DELAY, host.simulation().tick());
+
+    guest.step().unwrap();
+    deliver(&mut guest, &mut host).unwrap();
+    assert!(host.can_step());
+}
+
+#[test]
+fn desyncs_are_caught() {
+    let mut tampered = simulation();
+    tampered.board_mut(Side::Left).attack(6);
+    let (mut host, mut guest) = (Rollback::new(Side::Left, simulation(), 0),
+                                 Rollback::new(Side::Right, tampered, 0));
+
+    let mut result = Ok(());
+    for _ in 0..HASH_INTERVAL * 2 {
+        result = step_both(&mut host, &mut guest);
+        if result.is_err() {
+            break;
+        }
+    }
+
+    assert_eq!(Err("Out of sync at tick 0".to_string()), result);
+}
+
+#[test]
+fn leaving_ends_the_match() {
+    let (mut host, _) = peers();
+
+    assert_eq!(Err("Opponent left".to_string()), host.receive(Message::Bye));
+}
+
+#[test]
+fn messages_cross_loopback() {
+    let host = Host::listen(0).unwrap();
+    let mut guest = Connection::connect(&format!("127.0.0.1:{}", host.port())).unwrap();
+
+    let mut connection = None;
+    for _ in 0..100 {
+        connection = host.accept().unwrap();
+        if connection.is_some() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    let mut connection = connection.expect("Guest never connected");
+
+    let sent = vec!(Message::Input(4, vec!(Command::Press(Action::Right))), Message::Hash(0, 42), Message::Bye);
+    for message in &sent {
+        guest.send(message).unwrap();
+    }
+
+    let mut received = Vec::new();
+    for _ in 0..100 {
+        received.extend(connection.receive().unwrap());
+        if received.len() == sent.len() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(sent, received);
+
+    drop(guest);
+    let mut closed = false;
+    for _ in 0..100 {
+        if connection.receive().is_err() {
+            closed = true;
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(closed);
+}
@@ -0,0 +1,82 @@
+use puzzlefighter::*;
+use puzzlefighter::match_controller::*;
+use puzzlefighter::board_renderer::RenderState;
+
+#[test]
+fn first_to_majority_wins_match() {
+    let mut controller = MatchController::new(3);
+
+    controller.report(false, true);
+    assert_eq!(MatchState::RoundOver(Some(Side::Left), 3.0), controller.state());
+    assert_eq!(Some(MatchEvent::NextRound), controller.update(3.0));
+    assert_eq!(2, controller.round());
+
+    controller.report(false, true);
+    assert_eq!(2, controller.wins(Side::Left));
+    assert_eq!(0, controller.wins(Side::Right));
+
+    match controller.state() {
+        MatchState::MatchOver(Side::Left, _) => {},
+        state => panic!("Expected match over, got {:?}", state),
+    }
+}
+
+#[test]
+fn banner_counts_down_before_next_round() {
+    let mut controller = MatchController::new(3);
+
+    controller.report(true, false);
+    assert_eq!(None, controller.update(1.0));
+    assert!(!controller.is_playing());
+    assert_eq!(Some(MatchEvent::NextRound), controller.update(2.5));
+    assert!(controller.is_playing());
+}
+
+#[test]
+fn draw_does_not_count() {
+    let mut controller = MatchController::new(1);
+
+    controller.report(true, true);
+    assert_eq!(MatchState::RoundOver(None, 3.0), controller.state());
+    assert_eq!(0, controller.wins(Side::Left));
+    assert_eq!(0, controller.wins(Side::Right));
+}
+
+#[test]
+fn reports_ignored_between_rounds() {
+    let mut controller = MatchController::new(5);
+
+    controller.report(false, true);
+    controller.report(false, true);
+    assert_eq!(1, controller.wins(Side::Left));
+}
+
+#[test]
+fn board_tops_out_when_spawn_column_full() {
+    let mut board = Board::new(Dimension::new(6, 3));
+
+    board.add_blocks(svec!(
+        "   R",
+        "   G",
+        "   R"
+    ));
+
+    board.update(0.1, &RenderState::new());
+    assert!(board.is_game_over());
+    assert!(board.current_piece().is_none());
+}
+
+#[test]
+fn board_plays_on_with_room_to_spawn() {
+    let mut board = Board::new(Dimension::new(6, 3));
+
+    board.add_blocks(svec!(
+        "   ",
+        "RRRR",
+        "GGGG"
+    ));
+
+    board.update(0.1, &RenderState::new());
+    assert!(!board.is_game_over());
+    assert!(board.current_piece().is_some());
+}
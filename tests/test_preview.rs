@@ -0,0 +1,67 @@
+use puzzlefighter::*;
+use puzzlefighter::settings::*;
+use make_board;
+
+fn colors(pieces: &[Piece]) -> Vec<(Color, Color)> {
+    pieces.iter().map(|piece| (piece.blocks[0].color, piece.blocks[1].color)).collect()
+}
+
+#[test]
+fn preview_shows_the_pieces_to_come() {
+    let pieces: Vec<Piece> = vec!(Color::Red, Color::Green, Color::Blue, Color::Yellow).into_iter()
+        .map(|color| Piece::new(Block::new(color, false), Block::new(color, true)))
+        .collect();
+
+    let mut board = make_board(6);
+    board.set_preview(3);
+    board.set_piece_sequence(pieces.clone());
+    assert_eq!(colors(&pieces[..3]), colors(&board.upcoming()));
+    assert_eq!(Some(4), board.pieces_remaining());
+
+    board.set_preview(5);
+    assert_eq!(colors(&pieces), colors(&board.upcoming()));
+    assert_eq!(Some(4), board.pieces_remaining());
+
+    board.generate_next_piece();
+    board.set_preview(1);
+    assert_eq!(colors(&pieces[1..2]), colors(&board.upcoming()));
+    assert_eq!(Some(3), board.pieces_remaining());
+
+    board.set_preview(0);
+    assert_eq!(1, board.upcoming().len());
+}
+
+#[test]
+fn preview_does_not_change_the_pieces() {
+    let mut short = make_board(6);
+    let mut long = make_board(6);
+    short.set_preview(1);
+    long.set_preview(MAX_PREVIEW);
+    short.set_seed(7);
+    long.set_seed(7);
+
+    for i in 0..20 {
+        assert_eq!(colors(&short.upcoming()), colors(&long.upcoming()[..1]), "Piece {}", i);
+        assert_eq!(short.checksum(), long.checksum());
+        if i == 10 {
+            long.set_preview(2);
+        }
+        short.generate_next_piece();
+        long.generate_next_piece();
+    }
+}
+
+#[test]
+fn preview_setting_is_validated() {
+    let mut settings = PlayerSettings::default();
+    assert_eq!(3, settings.preview);
+
+    assert!(settings.set("preview", "5").is_ok());
+    assert_eq!(5, settings.preview);
+    assert_eq!(Some(5.0), settings.get("preview"));
+
+    assert!(settings.set("preview", "0").is_err());
+    assert!(settings.set("preview", "6").is_err());
+    assert!(settings.set("preview", "2.5").is_err());
+    assert_eq!(5, settings.preview);
+}
@@ -0,0 +1,152 @@
+use std::fs::{self,File};
+use std::io::Write;
+
+use puzzlefighter::controls::*;
+use puzzlefighter::match_controller::Side;
+use puzzlefighter::profiles::*;
+use empty_dir;
+
+#[test]
+fn elo_rewards_upsets() {
+    let (winner, loser) = elo(STARTING_RATING, STARTING_RATING);
+    assert_eq!(STARTING_RATING + K_FACTOR / 2.0, winner);
+    assert_eq!(STARTING_RATING - K_FACTOR / 2.0, loser);
+
+    let (favourite, _) = elo(1800.0, 1400.0);
+    let (underdog, _) = elo(1400.0, 1800.0);
+    assert!(favourite - 1800.0 < 4.0);
+    assert!(underdog - 1400.0 > 28.0);
+
+    assert!((expected_score(1600.0, 1400.0) + expected_score(1400.0, 1600.0) - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn profiles_round_trip_through_config() {
+    let mut profile = PlayerProfile::new("ann", Controls::default());
+    let config = profile.to_config();
+    assert_eq!(Ok(profile.clone()), PlayerProfile::parse("ann", &config));
+
+    let dir = empty_dir("puzzlefighter-profiles-round-trip");
+    let mut profiles = PlayerProfiles::load_from(&dir);
+    profiles.create("ann", Controls::default()).unwrap();
+    profiles.create("bob", Controls::default()).unwrap();
+    profiles.record("bob", "ann", 2, 1).unwrap();
+
+    let loaded = PlayerProfiles::load_from(&dir);
+    profile = loaded.get("ann").unwrap().clone();
+    assert_eq!(profiles.get("ann"), Some(&profile));
+    assert_eq!(Ok(profile.clone()), PlayerProfile::parse("ann", &profile.to_config()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn matches_update_ratings_and_history() {
+    let dir = empty_dir("puzzlefighter-profiles-ladder");
+    let mut profiles = PlayerProfiles::load_from(&dir);
+    for name in vec!("ann", "bob", "cat") {
+        profiles.create(name, Controls::default()).unwrap();
+    }
+
+    profiles.record("bob", "ann", 2, 0).unwrap();
+    profiles.record("bob", "cat", 2, 1).unwrap();
+    profiles.record("ann", "cat", 3, 2).unwrap();
+
+    let names: Vec<&str> = profiles.leaderboard().iter().map(|profile| profile.name()).collect();
+    assert_eq!(vec!("bob", "ann", "cat"), names);
+
+    let ann = profiles.get("ann").unwrap();
+    assert_eq!((1, 1), (ann.wins(), ann.losses()));
+    assert_eq!(Ok(ann.history()[0].clone()), MatchRecord::parse("lost bob 0-2 1484"));
+    assert_eq!(ann.rating(), ann.history()[1].rating);
+
+    assert!(profiles.record("ann", "ann", 2, 0).is_err());
+    assert!(profiles.record("ann", "dan", 2, 0).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn profile_controls_are_per_side() {
+    let dir = empty_dir("puzzlefighter-profiles-controls");
+    let mut profiles = PlayerProfiles::load_from(&dir);
+    profiles.create("ann", Controls::default()).unwrap();
+
+    let mut controls = Controls::default();
+    controls.rebind(Side::Right, Action::Turbo, Binding::Key('x' as u32));
+    profiles.set_controls("ann", Side::Right, controls.player(Side::Right).clone()).unwrap();
+
+    let loaded = PlayerProfiles::load_from(&dir);
+    let ann = loaded.get("ann").unwrap();
+    let turbo = ann.controls().player(Side::Right).bindings_for(Action::Turbo);
+    assert!(turbo.contains(&Binding::Key('x' as u32)));
+    assert!(!turbo.contains(&Binding::Key(0x20)));
+    assert_eq!(Controls::default().player(Side::Left), ann.controls().player(Side::Left));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rebound_profiles_survive_a_reload() {
+    let dir = empty_dir("puzzlefighter-profiles-rebound");
+    let mut profiles = PlayerProfiles::load_from(&dir);
+    profiles.create("ann", Controls::default()).unwrap();
+
+    // The left player takes the right's rotate key. The profile's right
+    // side gives it up too, keeping its gamepad button.
+    let up = Binding::Key(0x4000_0052);
+    let mut controls = Controls::default();
+    controls.rebind(Side::Left, Action::AntiClockwise, up);
+    profiles.set_controls("ann", Side::Left, controls.player(Side::Left).clone()).unwrap();
+
+    let loaded = PlayerProfiles::load_from(&dir);
+    let ann = loaded.get("ann").unwrap();
+    assert_eq!(Some(Action::AntiClockwise), ann.controls().player(Side::Left).action_for(up));
+    assert_eq!(vec!(Binding::Button(1, 3)), ann.controls().player(Side::Right).bindings_for(Action::AntiClockwise));
+
+    // Taking the button as well would leave the right side unable to
+    // rotate, so is refused.
+    controls.rebind(Side::Left, Action::AntiClockwise, Binding::Button(1, 3));
+    assert!(profiles.set_controls("ann", Side::Left, controls.player(Side::Left).clone()).is_err());
+    assert_eq!(loaded.get("ann"), PlayerProfiles::load_from(&dir).get("ann"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_names_and_files_are_refused() {
+    let dir = empty_dir("puzzlefighter-profiles-bad");
+    let mut profiles = PlayerProfiles::load_from(&dir);
+
+    assert!(profiles.create("", Controls::default()).is_err());
+    assert!(profiles.create("../ann", Controls::default()).is_err());
+    assert!(profiles.create("a_very_long_name", Controls::default()).is_err());
+    profiles.create("ann", Controls::default()).unwrap();
+    assert!(profiles.create("ann", Controls::default()).is_err());
+
+    // A profile from a newer game is skipped, and not overwritten.
+    let newer = PlayerProfile::new("bob", Controls::default()).to_config()
+        .replace(&format!("version = {}", PROFILE_VERSION), &format!("version = {}", PROFILE_VERSION + 1));
+    assert!(PlayerProfile::parse("bob", &newer).unwrap_err().contains("newer"));
+    File::create(dir.join("bob.ini")).unwrap().write_all(newer.as_bytes()).unwrap();
+
+    let mut profiles = PlayerProfiles::load_from(&dir);
+    assert_eq!(vec!("ann".to_string()), profiles.names());
+    assert!(profiles.create("bob", Controls::default()).is_err());
+
+    let unversioned = PlayerProfile::new("cat", Controls::default()).to_config().replace("version = ", "# ");
+    assert!(PlayerProfile::parse("cat", &unversioned).is_err());
+    assert!(MatchRecord::parse("drew bob 1-1 1500").is_err());
+    assert!(MatchRecord::parse("won bob 2 1500").is_err());
+
+    // Ratings have to be numbers to be ranked.
+    let unrated = PlayerProfile::new("dan", Controls::default()).to_config()
+        .replace(&format!("rating = {}", STARTING_RATING), "rating = NaN");
+    assert!(PlayerProfile::parse("dan", &unrated).unwrap_err().contains("rating"));
+    File::create(dir.join("dan.ini")).unwrap().write_all(unrated.as_bytes()).unwrap();
+    assert_eq!(vec!("ann".to_string()), PlayerProfiles::load_from(&dir).leaderboard()
+        .iter().map(|profile| profile.name().to_string()).collect::<Vec<_>>());
+    assert!(MatchRecord::parse("won bob 2-1 inf").is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,49 @@
+use puzzlefighter::progression::*;
+
+#[test]
+fn level_increases_over_time() {
+    let mut progression = Progression::new();
+
+    assert_eq!(1, progression.level());
+    progression.update(29.0);
+    assert_eq!(1, progression.level());
+    progression.update(2.0);
+    assert_eq!(2, progression.level());
+}
+
+#[test]
+fn step_interval_shortens_with_level_down_to_floor() {
+    let mut progression = Progression::new();
+    let initial = progression.step_interval();
+
+    progression.update(30.0);
+    assert!(progression.step_interval() < initial);
+
+    for _ in 0..100 {
+        progression.update(30.0);
+    }
+    assert_eq!(0.1, progression.step_interval());
+}
+
+#[test]
+fn garbage_drops_on_a_timer() {
+    let mut progression = Progression::new();
+
+    assert_eq!(0, progression.update(10.0));
+    assert_eq!(2, progression.update(5.0));
+    assert_eq!(0, progression.update(1.0));
+}
+
+#[test]
+fn player_attacks_counter_garbage() {
+    let mut progression = Progression::new();
+
+    progression.counter(1);
+    assert_eq!(1, progression.update(15.0));
+
+    progression.counter(10);
+    assert_eq!(0, progression.update(15.0));
+
+    // Excess counter does not carry over
+    assert_eq!(3, progression.update(15.0));
+}
@@ -0,0 +1,51 @@
+use puzzlefighter::*;
+use puzzlefighter::projectile::*;
+use {chain_board,run_until_settled};
+
+#[test]
+fn finished_combos_announce_their_attack() {
+    let mut board = chain_board();
+    let sent: Vec<u32> = run_until_settled(&mut board).into_iter().filter_map(|event| match event {
+        BlockEvent::Attack(strength) => Some(strength),
+        _ => None,
+    }).collect();
+
+    assert_eq!(vec!(board.stats().attack_sent), sent);
+    assert_eq!(board.stats().attack_sent, board.take_attack());
+
+    let mut victim = Board::new(Dimension::new(6, 13));
+    victim.attack(sent[0]);
+    victim.attack(4);
+    assert_eq!(sent[0] + 4, victim.incoming_attack());
+}
+
+#[test]
+fn attacks_fly_in_an_arc() {
+    let from = PixelPosition::new(100.0, 300.0);
+    let to = PixelPosition::new(500.0, 20.0);
+    let mut projectile = Projectile::new(from, to, 6);
+
+    assert_eq!(from, projectile.position());
+    assert_eq!(to, projectile.position_at(FLIGHT_TIME));
+    assert_eq!(to, projectile.position_at(FLIGHT_TIME * 2.0));
+
+    // Above the straight line half way.
+    let middle = projectile.position_at(FLIGHT_TIME / 2.0);
+    assert!((middle.x() - 300.0).abs() < 1e-9);
+    assert!(middle.y() < 160.0);
+
+    projectile.update(FLIGHT_TIME / 2.0);
+    assert!(!projectile.has_landed());
+    projectile.update(FLIGHT_TIME / 2.0);
+    assert!(projectile.has_landed());
+}
+
+#[test]
+fn stronger_attacks_are_larger() {
+    let at = PixelPosition::new(0.0, 0.0);
+    let radius = |strength| Projectile::new(at, at, strength).radius();
+
+    assert!(radius(1) < radius(10));
+    assert!(radius(1) >= MIN_RADIUS);
+    assert_eq!(MAX_RADIUS, radius(1000));
+}
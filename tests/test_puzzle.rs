@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use puzzlefighter::*;
+use puzzlefighter::puzzle::*;
+use puzzlefighter::solver::*;
+
+fn standard() -> Dimension {
+    Dimension::new(6, 13)
+}
+
+#[test]
+fn parse_puzzle() {
+    let puzzle = Puzzle::parse("
+# A comment
+name: Test
+goal: damage 6
+pieces: Rr gY
+grid:
+R
+RG
+").unwrap();
+
+    assert_eq!("Test", puzzle.name);
+    assert_eq!(Goal::Damage(6), puzzle.goal);
+    assert_eq!(2, puzzle.piece_count());
+
+    let pieces = puzzle.pieces();
+    assert_eq!(Color::Red, pieces[0].blocks[0].color);
+    assert!(pieces[0].blocks[1].breaker());
+    assert!(pieces[1].blocks[0].breaker());
+    assert_eq!(Color::Yellow, pieces[1].blocks[1].color);
+
+    let board = puzzle.board(standard());
+    assert_eq!(3, board.grid().blocks().len());
+    assert_eq!(Some(2), board.pieces_remaining());
+}
+
+#[test]
+fn parse_errors() {
+    assert!(Puzzle::parse("name: x\ngoal: win\npieces: RR\ngrid:\nR").is_err());
+    assert!(Puzzle::parse("name: x\ngoal: chain\npieces: RR\ngrid:\nR").is_err());
+    assert!(Puzzle::parse("name: x\ngoal: clear\npieces: RRR\ngrid:\nR").is_err());
+    assert!(Puzzle::parse("name: x\ngoal: clear\npieces: RR").is_err());
+}
+
+#[test]
+fn board_waits_once_sequence_is_exhausted() {
+    let mut board = Board::new(standard());
+
+    board.set_piece_sequence(vec!(Piece::rand(0, 0)));
+    assert_eq!(Some(1), board.pieces_remaining());
+
+    board.generate_next_piece();
+    assert_eq!(Some(0), board.pieces_remaining());
+    assert!(board.next_piece().is_none());
+}
+
+#[test]
+fn unsolvable_puzzle_has_no_solution() {
+    let puzzle = Puzzle::parse("name: x\ngoal: clear\npieces: RR\ngrid:\nG").unwrap();
+
+    assert_eq!(None, solve(&puzzle, standard()));
+}
+
+#[test]
+fn shipped_puzzles_are_solvable() {
+    let puzzles = load_all(Path::new("assets/puzzles")).unwrap();
+    assert!(!puzzles.is_empty());
+
+    for puzzle in puzzles {
+        let solution = solve(&puzzle, standard());
+
+        assert!(solution.is_some(), "Puzzle `{}` has no solution", puzzle.name);
+    }
+}
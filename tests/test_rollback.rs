@@ -0,0 +1,216 @@
+use std::f64;
+
+use puzzlefighter::*;
+use puzzlefighter::controls::Action;
+use puzzlefighter::input::Command;
+use puzzlefighter::lockstep::*;
+use puzzlefighter::match_controller::Side;
+use puzzlefighter::net::*;
+use puzzlefighter::rollback::*;
+use puzzlefighter::settings::PlayerSettings;
+
+const TICKS: u64 = 600;
+
+fn simulation() -> Simulation {
+    Simulation::new(Dimension::new(6, 13), 1234, 3,
+                    PlayerSettings::default(), PlayerSettings::default())
+}
+
+fn bad_network() -> Conditions {
+    Conditions { latency: 0.06, jitter: 0.04, loss: 0.1 }
+}
+
+// What each player does on a tick. Both are busy, so late input often
+// differs from the guess that nothing happened.
+fn script(side: Side, tick: u64) -> Vec<Command> {
+    match (side, tick % 30) {
+        (Side::Left, 0) => vec!(Command::Press(Action::Turbo)),
+        (Side::Left, 12) => vec!(Command::Release(Action::Turbo), Command::Press(Action::Left)),
+        (Side::Left, 20) => vec!(Command::Press(Action::Clockwise)),
+        (Side::Right, 5) => vec!(Command::Press(Action::Right)),
+        (Side::Right, 15) => vec!(Command::Press(Action::Turbo)),
+        (Side::Right, 25) => vec!(Command::Release(Action::Turbo), Command::Press(Action::AntiClockwise)),
+        _ => vec!(),
+    }
+}
+
+struct Run {
+    checksum: u64,
+    stalls: u32,
+    rollbacks: u32,
+}
+
+// Play both sides for a number of ticks, a tick of time per frame, over the
+// given network. Everything still in flight is then delivered, so both sides
+// agree on every tick played.
+fn play(max_rollback: u64, conditions: Conditions) -> Run {
+    let mut peers = [Rollback::new(Side::Left, simulation(), max_rollback),
+                     Rollback::new(Side::Right, simulation(), max_rollback)];
+    let mut links = [Delayed::new(conditions, 1), Delayed::new(conditions, 2)];
+    let mut clock = 0.0;
+    let mut scripted = [None, None];
+    let mut stalls = 0;
+
+    while peers.iter().any(|peer| peer.simulation().tick() < TICKS) {
+        clock += TICK;
+        assert!(clock < 60.0, "Play got stuck");
+
+        for i in 0..2 {
+            let peer = &mut peers[i];
+            let tick = peer.simulation().tick();
+
+            if tick < TICKS {
+                // Input given while stalled waits for the next tick played.
+                if scripted[i] != Some(tick) {
+                    for command in script(peer.side(), tick) {
+                        peer.input(command);
+                    }
+                    scripted[i] = Some(tick);
+                }
+
+                peer.step().unwrap();
+                if peer.simulation().tick() == tick {
+                    stalls += 1;
+                }
+            }
+
+            for message in peer.outgoing() {
+                links[i].push(clock, message);
+            }
+        }
+
+        for i in 0..2 {
+            for message in links[i].ready(clock) {
+                peers[1 - i].receive(message).unwrap();
+            }
+        }
+    }
+
+    // Once for the inputs, again for the checksums they lead to.
+    for _ in 0..2 {
+        for i in 0..2 {
+            peers[i].confirm().unwrap();
+            for message in peers[i].outgoing() {
+                links[i].push(clock, message);
+            }
+        }
+        for i in 0..2 {
+            for message in links[i].ready(f64::MAX) {
+                peers[1 - i].receive(message).unwrap();
+            }
+        }
+    }
+
+    for peer in peers.iter() {
+        assert_eq!(TICKS, peer.confirmed().tick());
+        assert_eq!(peer.confirmed().checksum(), peer.simulation().checksum());
+    }
+    assert_eq!(peers[0].confirmed().checksum(), peers[1].confirmed().checksum());
+
+    Run {
+        checksum: peers[0].confirmed().checksum(),
+        stalls: stalls,
+        rollbacks: peers[0].rollbacks() + peers[1].rollbacks(),
+    }
+}
+
+#[test]
+fn bad_networks_play_the_same_game() {
+    let perfect = play(0, Conditions::default());
+    let rollback = play(DEFAULT_MAX_ROLLBACK, bad_network());
+
+    assert_eq!(0, perfect.rollbacks);
+    assert!(rollback.rollbacks > 0, "Nothing was rolled back");
+    assert_eq!(perfect.checksum, rollback.checksum);
+}
+
+#[test]
+fn rollback_stalls_less_than_lockstep() {
+    let lockstep = play(0, bad_network());
+    let rollback = play(DEFAULT_MAX_ROLLBACK, bad_network());
+
+    assert_eq!(lockstep.checksum, rollback.checksum);
+    assert!(rollback.stalls * 2 < lockstep.stalls,
+            "Rollback stalled {} times, lockstep {}", rollback.stalls, lockstep.stalls);
+}
+
+#[test]
+fn late_input_is_replayed() {
+    let mut host = Rollback::new(Side::Left, simulation(), DEFAULT_MAX_ROLLBACK);
+    let mut guest = Rollback::new(Side::Right, simulation(), DEFAULT_MAX_ROLLBACK);
+
+    guest.input(Command::Press(Action::Right));
+    for _ in 0..5 {
+        host.step().unwrap();
+        guest.step().unwrap();
+    }
+    assert!(host.simulation().checksum() != guest.simulation().checksum());
+
+    for message in guest.outgoing() {
+        host.receive(message).unwrap();
+    }
+    host.confirm().unwrap();
+
+    assert_eq!(1, host.rollbacks());
+    assert_eq!(host.simulation().board(Side::Right).checksum(),
+               guest.simulation().board(Side::Right).checksum());
+}
+
+#[test]
+fn guesses_stop_at_the_limit() {
+    let mut host = Rollback::new(Side::Left, simulation(), 4);
+
+    for _ in 0..10 {
+        host.step().unwrap();
+    }
+
+    assert_eq!(INPUT_DELAY + 4, host.simulation().tick());
+    assert_eq!(INPUT_DELAY, host.confirmed().tick());
+}
+
+#[test]
+fn input_for_confirmed_ticks_is_refused() {
+    let (mut host, mut guest) = (Rollback::new(Side::Left, simulation(), 0),
+                                 Rollback::new(Side::Right, simulation(), 0));
+
+    for _ in 0..INPUT_DELAY + 1 {
+        guest.step().unwrap();
+    }
+    for message in guest.outgoing() {
+        host.receive(message).unwrap();
+    }
+    host.step().unwrap();
+    host.step().unwrap();
+    host.step().unwrap();
+    host.step().unwrap();
+
+    assert!(host.receive(Message::Input(0, vec!(Command::Press(Action::Left)))).is_err());
+}
+
+#[test]
+fn delayed_messages_arrive_in_order() {
+    let conditions = Conditions { latency: 0.1, jitter: 0.05, loss: 0.3 };
+    let mut link = Delayed::new(conditions, 7);
+
+    for tick in 0..50 {
+        link.push(tick as f64 * TICK, Message::Input(tick, vec!()));
+    }
+
+    assert!(link.ready(0.09).is_empty());
+
+    let mut received = Vec::new();
+    let mut now = 0.0;
+    while !link.is_empty() {
+        now += TICK;
+        received.extend(link.ready(now));
+    }
+
+    let ticks: Vec<u64> = received.into_iter().map(|message| match message {
+        Message::Input(tick, _) => tick,
+        _ => panic!("Unexpected message"),
+    }).collect();
+    assert_eq!((0..50).collect::<Vec<u64>>(), ticks);
+
+    // Some messages were lost and sent again.
+    assert!(now > 50.0 * TICK + 0.15 + 0.2);
+}
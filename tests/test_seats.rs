@@ -0,0 +1,103 @@
+use puzzlefighter::controls::Binding;
+use puzzlefighter::input::InputEvent;
+use puzzlefighter::match_controller::Side;
+use puzzlefighter::seats::*;
+
+fn press(id: i32, button: u8) -> InputEvent {
+    InputEvent::Press(Binding::Button(id, button))
+}
+
+#[test]
+fn pressing_a_button_joins_the_next_free_seat() {
+    let mut seats = Seats::new();
+    seats.connect(Gamepad::new(7, "Generic USB Joystick"));
+    seats.connect(Gamepad::new(3, "Xbox 360 Controller"));
+
+    assert_eq!(Some(SeatEvent::Joined(Side::Left)), seats.handle(press(3, 0)));
+    assert_eq!(None, seats.handle(press(3, 1)));
+    assert_eq!(Some(SeatEvent::Joined(Side::Right)), seats.handle(press(7, 5)));
+
+    assert_eq!("Xbox 360 Controller", seats.gamepad(Side::Left).unwrap().name);
+    assert_eq!(7, seats.gamepad(Side::Right).unwrap().id);
+}
+
+#[test]
+fn only_connected_gamepad_buttons_join() {
+    let mut seats = Seats::new();
+    seats.connect(Gamepad::new(0, "Pad"));
+
+    assert_eq!(None, seats.handle(press(1, 0)));
+    assert_eq!(None, seats.handle(InputEvent::Axis(0, 0, 1.0)));
+    assert_eq!(None, seats.handle(InputEvent::Release(Binding::Button(0, 0))));
+    assert_eq!(None, seats.gamepad(Side::Left));
+}
+
+#[test]
+fn unplugging_frees_the_seat() {
+    let mut seats = Seats::new();
+    seats.connect(Gamepad::new(0, "Pad"));
+    seats.connect(Gamepad::new(1, "Pad"));
+    seats.handle(press(0, 0));
+    seats.handle(press(1, 0));
+
+    assert_eq!(Some(SeatEvent::Lost(Side::Left)), seats.disconnect(0));
+    assert_eq!(None, seats.disconnect(0));
+    assert_eq!(None, seats.gamepad(Side::Left));
+
+    // Plugged back in, it is a new device that joins the free seat.
+    seats.connect(Gamepad::new(2, "Pad"));
+    assert_eq!(Some(SeatEvent::Joined(Side::Left)), seats.handle(press(2, 9)));
+}
+
+#[test]
+fn seated_input_is_rewritten_for_the_seat() {
+    let profiles = Profiles::default();
+    let mut seats = Seats::new();
+    seats.connect(Gamepad::new(4, "Generic USB Joystick"));
+    seats.connect(Gamepad::new(5, "Sony PLAYSTATION(R)3 Controller"));
+    seats.handle(press(4, 0));
+    seats.handle(press(5, 0));
+
+    let left = seats.pad(Side::Left, &profiles).unwrap();
+    let right = seats.pad(Side::Right, &profiles).unwrap();
+
+    assert_eq!(Some(press(0, 3)), left.translate(press(4, 3)));
+    assert_eq!(Some(InputEvent::Axis(0, 1, -1.0)), left.translate(InputEvent::Axis(4, 1, -1.0)));
+    assert_eq!(None, left.translate(press(5, 3)));
+
+    // Square is on the left of a PlayStation pad, like button 3 on a
+    // generic one.
+    assert_eq!(Some(press(1, 3)), right.translate(press(5, 3)));
+    assert_eq!(Some(press(1, 0)), right.translate(press(5, 2)));
+
+    let key = InputEvent::Release(Binding::Key(32));
+    assert_eq!(Some(key), right.translate(key));
+}
+
+#[test]
+fn profiles_round_trip() {
+    let profiles = Profiles::default();
+    assert_eq!(Ok(profiles.clone()), Profiles::parse(&profiles.to_config()));
+
+    assert_eq!("Xbox", profiles.for_device("Microsoft X-Box 360 pad").name);
+    assert_eq!(9, profiles.for_device("Xbox One").button(7));
+    assert_eq!(7, profiles.for_device("Logitech Dual Action").button(7));
+}
+
+#[test]
+fn profiles_are_parsed() {
+    let profiles = Profiles::parse("\
+        [Arcade stick]\n\
+        match = Hori, Qanba\n\
+        button 5 = 9\n\
+        axis 6 = 0\n").unwrap();
+
+    let profile = profiles.for_device("HORI Fighting Stick");
+    assert_eq!(9, profile.button(5));
+    assert_eq!(0, profile.axis(6));
+
+    assert!(Profiles::parse("button 1 = 2").is_err());
+    assert!(Profiles::parse("[Pad]\nbutton one = 2").is_err());
+    assert!(Profiles::parse("[Pad]\nhat 1 = 2").is_err());
+    assert!(Profiles::parse("[Pad]\nbutton 1").is_err());
+}
@@ -0,0 +1,94 @@
+use std::fs::{self,File};
+use std::io::Write;
+use std::path::{Path,PathBuf};
+
+use puzzlefighter::*;
+use puzzlefighter::themes::*;
+use empty_dir;
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    File::create(path).unwrap().write_all(contents.as_bytes()).unwrap();
+}
+
+// Every block and breaker, in every color and age, has an image.
+fn assert_has_every_block(theme: &Theme) {
+    for color in vec!(Color::Red, Color::Green, Color::Blue, Color::Yellow) {
+        let mut blocks = vec!(Block::new(color, true));
+        for age in 0..5 {
+            blocks.push(Block::new_with_age(color, age));
+        }
+
+        for block in blocks {
+            let name = block.to_texture_name();
+            assert!(theme.image(&name).is_some(), "{} has no image for {}", theme.name(), name);
+        }
+    }
+}
+
+#[test]
+fn manifests_map_names_to_images() {
+    let dir = Path::new("/themes/neon");
+    let theme = Theme::parse(dir, "# Bright\nname = Neon\n\n[images]\nred = blocks/red.png\nboard=frame.png\n").unwrap();
+
+    assert_eq!("Neon", theme.name());
+    assert_eq!(vec!("board", "red"), theme.names());
+    assert_eq!(Some(dir.join("blocks/red.png").as_path()), theme.image("red"));
+    assert_eq!(None, theme.image("blue"));
+
+    assert!(Theme::parse(dir, "[images]\nred = red.png\n").is_err());
+    assert!(Theme::parse(dir, "name = Neon\n[sounds]\n").is_err());
+    assert!(Theme::parse(dir, "name = Neon\n[images]\nred\n").is_err());
+    assert!(Theme::parse(dir, "name = Neon\nauthor = ann\n").is_err());
+}
+
+#[test]
+fn default_theme_has_every_image() {
+    let theme = Theme::from_images(DEFAULT_THEME, Path::new("assets/gen")).unwrap();
+
+    for name in vec!("board", "splash", "ferns", "press-to-start") {
+        assert!(theme.image(name).is_some(), "No image for {}", name);
+    }
+
+    assert_has_every_block(&theme);
+}
+
+#[test]
+fn bundled_packs_have_every_block() {
+    let themes = find_themes(&[PathBuf::from("assets/themes")]);
+    let names: Vec<&str> = themes.iter().map(|theme| theme.name()).collect();
+    assert_eq!(vec!("High contrast", "Symbols"), names);
+
+    for theme in &themes {
+        assert_has_every_block(theme);
+    }
+}
+
+#[test]
+fn packs_are_found_and_checked() {
+    let dir = empty_dir("puzzlefighter-themes");
+
+    write(&dir.join("neon/theme.ini"), "name = Neon\n[images]\nred = red.png\nblue = missing.png\n");
+    write(&dir.join("neon/red.png"), "");
+    write(&dir.join("clay/theme.ini"), "name = Clay\n");
+    write(&dir.join("retro/theme.ini"), "name = Neon\n");
+    write(&dir.join("default/theme.ini"), "name = default\n");
+    write(&dir.join("broken/theme.ini"), "[images]\n");
+    write(&dir.join("notes/readme.txt"), "");
+
+    let themes = find_themes(&vec!(dir.clone(), dir.join("nowhere")));
+    let names: Vec<&str> = themes.iter().map(|theme| theme.name()).collect();
+    assert_eq!(vec!("Clay", "Neon"), names);
+
+    // Images that aren't there fall back to the default theme's.
+    assert_eq!(vec!("red"), themes[1].names());
+
+    let images = empty_dir("puzzlefighter-theme-images");
+    write(&images.join("red.png"), "");
+    write(&images.join("red_bl.png"), "");
+    write(&images.join("notes.txt"), "");
+    assert_eq!(vec!("red", "red_bl"), Theme::from_images("plain", &images).unwrap().names());
+
+    fs::remove_dir_all(&dir).unwrap();
+    fs::remove_dir_all(&images).unwrap();
+}
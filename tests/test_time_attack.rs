@@ -0,0 +1,69 @@
+use std::env;
+use std::fs::{self,File};
+use std::io::Write;
+
+use puzzlefighter::*;
+use puzzlefighter::records::*;
+use make_board;
+
+fn breaker_on(board: &mut Board, x: i8, y: i8, color: Color) {
+    let piece = Piece::new(Block::new(color, true), Block::new(color, false));
+    board.play_piece(piece.dup_to(GridPosition::new(x, y), Direction::Up));
+}
+
+#[test]
+fn largest_gem_and_best_combo_are_tracked() {
+    let mut board = make_board!(
+        "BB    ",
+        "RRR   ",
+        "RRR   ",
+        "RRRGG "
+    );
+
+    breaker_on(&mut board, 3, 1, Color::Red);
+
+    let stats = board.stats();
+    assert_eq!(9, stats.largest_gem);
+    assert_eq!(stats.attack_sent, stats.best_combo);
+    assert!(stats.best_combo > 0);
+
+    // A smaller follow up doesn't lower either.
+    let best = stats.best_combo;
+    breaker_on(&mut board, 5, 0, Color::Green);
+
+    let stats = board.stats();
+    assert_eq!(9, stats.largest_gem);
+    assert_eq!(best, stats.best_combo);
+}
+
+#[test]
+fn records_keep_only_the_best() {
+    let path = env::temp_dir().join("puzzlefighter-records-best.txt");
+    let _ = fs::remove_file(&path);
+
+    let mut records = Records::load_from(&path);
+    assert_eq!(None, records.best("time_attack"));
+
+    assert!(records.submit("time_attack", 10));
+    assert!(!records.submit("time_attack", 8));
+    assert!(!records.submit("time_attack", 10));
+    assert!(records.submit("time_attack", 12));
+
+    let records = Records::load_from(&path);
+    assert_eq!(Some(12), records.best("time_attack"));
+    assert_eq!(None, records.best("other"));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn malformed_records_are_ignored() {
+    let path = env::temp_dir().join("puzzlefighter-records-malformed.txt");
+    File::create(&path).unwrap().write_all(b"time_attack=nope\nendless=40\ngarbage\n").unwrap();
+
+    let records = Records::load_from(&path);
+    assert_eq!(None, records.best("time_attack"));
+    assert_eq!(Some(40), records.best("endless"));
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,60 @@
+use puzzlefighter::*;
+use puzzlefighter::board_renderer::RenderState;
+use make_board;
+
+fn falling_y(board: &Board) -> i8 {
+    board.current_piece().expect("No current piece").position.y()
+}
+
+#[test]
+fn piece_holds_without_gravity_until_turbo() {
+    let mut board = Board::new(Dimension::new(6, 13));
+    let render_state = RenderState::new();
+
+    board.set_gravity(false);
+    board.update(0.1, &render_state);
+    let start = falling_y(&board);
+
+    for _ in 0..20 {
+        board.update(1.0, &render_state);
+    }
+    assert_eq!(start, falling_y(&board));
+
+    board.turbo(true);
+    board.update(1.0, &render_state);
+    assert!(falling_y(&board) < start);
+}
+
+#[test]
+fn last_combo_is_kept_until_the_next_break() {
+    let mut board = make_board!(
+        "G     ",
+        "R     ",
+        "R G   "
+    );
+
+    let piece = Piece::new(Block::new(Color::Red, true), Block::new(Color::Green, true));
+    board.play_piece(piece.dup_to(GridPosition::new(1, 1), Direction::Up));
+
+    let stats = board.stats();
+    assert_eq!(2, stats.last_chain);
+    assert_eq!(3, stats.last_combo);
+
+    // A piece that breaks nothing leaves the last combo alone.
+    let piece = Piece::new(Block::new(Color::Blue, false), Block::new(Color::Yellow, false));
+    board.play_piece(piece.dup_to(GridPosition::new(5, 0), Direction::Up));
+
+    assert_eq!(stats.last_chain, board.stats().last_chain);
+    assert_eq!(stats.last_combo, board.stats().last_combo);
+}
+
+#[test]
+fn loaded_boards_are_checked_before_adding() {
+    let board = Board::new(Dimension::new(6, 13));
+    let lines = |rows: &[&str]| rows.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(Ok(()), board.check_blocks(&lines(&["   YYY", "  gYYY", "RRRGGB"])));
+    assert!(board.check_blocks(&lines(&["RRRGGBY"])).is_err());
+    assert!(board.check_blocks(&vec!("R".to_string(); 14)).is_err());
+    assert!(board.check_blocks(&lines(&["RRX"])).unwrap_err().contains("`X`"));
+}
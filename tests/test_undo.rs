@@ -0,0 +1,113 @@
+use puzzlefighter::*;
+use {make_board,assert_block,assert_no_block};
+
+fn drop_piece(board: &mut Board, x: i8, color: Color, breaker: bool) {
+    let piece = Piece::new(Block::new(color, breaker), Block::new(color, false));
+    board.play_piece(piece.dup_to(GridPosition::new(x, 0), Direction::Up));
+}
+
+#[test]
+fn history_is_off_by_default() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      "
+    );
+
+    drop_piece(&mut board, 0, Color::Red, false);
+    assert_eq!(0, board.undo_count());
+    assert!(!board.undo());
+}
+
+#[test]
+fn undo_and_redo_placements() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      ",
+        "RR    "
+    );
+    board.set_history_limit(10);
+
+    drop_piece(&mut board, 2, Color::Red, true);
+    assert!(board.grid().blocks().is_empty());
+    let broken = board.stats();
+    assert!(broken.attack_sent > 0);
+
+    drop_piece(&mut board, 5, Color::Blue, false);
+    assert_eq!(2, board.undo_count());
+
+    assert!(board.undo());
+    assert_eq!(broken, board.stats());
+    assert_no_block(&board, 5, 0);
+
+    assert!(board.undo());
+    assert_eq!(Stats::default(), board.stats());
+    assert_block(&board, 0, 0);
+    assert_block(&board, 1, 0);
+
+    // The undone piece is back at the top, ready to be played again.
+    let piece = board.current_piece().expect("No current piece");
+    assert!(piece.blocks[0].breaker());
+    assert_eq!(Color::Red, piece.blocks[0].color);
+
+    assert!(!board.undo());
+    assert_eq!(2, board.redo_count());
+
+    assert!(board.redo());
+    assert!(board.redo());
+    assert_eq!(2, board.stats().pieces);
+    assert_block(&board, 5, 0);
+    assert!(!board.redo());
+}
+
+#[test]
+fn placing_a_piece_clears_redo() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      "
+    );
+    board.set_history_limit(10);
+
+    drop_piece(&mut board, 0, Color::Red, false);
+    board.undo();
+    assert_eq!(1, board.redo_count());
+
+    drop_piece(&mut board, 3, Color::Green, false);
+    assert_eq!(0, board.redo_count());
+    assert_eq!(1, board.undo_count());
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      ",
+        "      "
+    );
+    board.set_history_limit(2);
+
+    for x in 0..4 {
+        drop_piece(&mut board, x, Color::Yellow, false);
+    }
+
+    assert_eq!(2, board.undo_count());
+}
+
+#[test]
+fn undo_emits_reset() {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      "
+    );
+    board.set_history_limit(10);
+
+    drop_piece(&mut board, 0, Color::Red, false);
+    board.undo();
+
+    let events: Vec<_> = board.consume_events().into_iter().collect();
+    assert_eq!(vec!(BlockEvent::Reset), events);
+}
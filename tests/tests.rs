@@ -2,6 +2,10 @@ extern crate puzzlefighter;
 
 pub use self::puzzlefighter::*;
 pub use std::rc::*;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+pub use self::puzzlefighter::board_renderer::RenderState;
 
 pub fn make_board(height: usize) -> Board {
     Board::new(Dimension::new(10, height as u32))
@@ -34,6 +38,14 @@ pub fn assert_no_block(board: &Board, x: i8, y: i8) {
     assert!(block.is_none(), "Block at ({}, {})", x, y);
 }
 
+// A fresh, empty directory under the system temp dir.
+pub fn empty_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
 macro_rules! svec {
     ( $( $x:expr ),* ) => {
         {
@@ -61,7 +73,67 @@ macro_rules! make_board {
     };
 }
 
+// The chain puzzle: a red breaker dropped next to the reds lets the green
+// fall onto the green breaker.
+pub fn chain_board() -> Board {
+    let mut board = make_board!(
+        "      ",
+        "      ",
+        "      ",
+        "  G   ",
+        "  R   ",
+        "  Rg  "
+    );
+    board.set_piece_sequence(vec!(Piece::new(Block::new(Color::Red, true), Block::new(Color::Yellow, false))));
+    board.turbo(true);
+    board
+}
+
+// Update a board until it waits for its next piece, handing each frame's
+// events to a callback.
+pub fn each_frame_until_settled<F>(board: &mut Board, mut frame: F) where F: FnMut(&Board, Vec<BlockEvent>) {
+    for _ in 0..1000 {
+        board.update(0.01, &RenderState::new());
+        let events = board.consume_events().into_iter().collect();
+        frame(board, events);
+
+        if board.is_waiting_for_piece() {
+            break;
+        }
+    }
+}
+
+// Update a board until it waits for its next piece, returning every event
+// along the way.
+pub fn run_until_settled(board: &mut Board) -> Vec<BlockEvent> {
+    let mut events = Vec::new();
+    each_frame_until_settled(board, |_, frame| events.extend(frame));
+    events
+}
+
 mod test_drop;
 mod test_fuse;
 mod test_debug;
 mod test_wall_kick;
+mod test_match;
+mod test_progression;
+mod test_puzzle;
+mod test_time_attack;
+mod test_training;
+mod test_undo;
+mod test_controls;
+mod test_input;
+mod test_seats;
+mod test_lockstep;
+mod test_rollback;
+mod test_broadcast;
+mod test_lobby;
+mod test_profiles;
+mod test_hud;
+mod test_preview;
+mod test_layout;
+mod test_gravity;
+mod test_effects;
+mod test_aging;
+mod test_projectile;
+mod test_themes;